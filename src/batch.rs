@@ -0,0 +1,271 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arrow::datatypes::{
+    DataType as ArrowType, Field as ArrowField, Fields, Schema as ArrowSchema, SchemaRef,
+};
+use arrow::json::ReaderBuilder;
+use arrow::record_batch::RecordBatch;
+use serde_json::Value;
+
+use crate::model::schema::{DataType, Field, Schema};
+use crate::source::{Record, RecordSource};
+
+pub const DEFAULT_BATCH_SIZE: usize = 1024;
+
+/// Derives the Arrow schema that record batches are decoded against.
+///
+/// Inferred types map to their natural Arrow types, with a few deliberate exceptions:
+/// bytes stay hex strings and timestamps stay integers, as `cat` prints them, because
+/// [`Schema`] records neither the encoding nor the unit. Null, unknown and child-less struct
+/// fields become JSON text. Flattened JSON fields such as `user.id` are nested back into
+/// structs. Every field is nullable, since inference only sees a sample.
+pub fn arrow_schema(schema: &Schema) -> ArrowSchema {
+    ArrowSchema::new(nest_fields(&schema.fields, ""))
+}
+
+/// Iterates a [`RecordSource`] as Arrow record batches of up to `batch_size` rows.
+pub struct Batches<S> {
+    source: S,
+    batch_size: usize,
+}
+
+impl<S: RecordSource> Batches<S> {
+    pub fn new(source: S, batch_size: usize) -> Self {
+        Self {
+            source,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.source.arrow_schema()
+    }
+}
+
+impl<S: RecordSource> Iterator for Batches<S> {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.source.next_batch(self.batch_size).transpose()
+    }
+}
+
+/// Reads up to `max_rows` records from `source` and decodes them into one batch.
+pub(crate) fn read_batch<S>(source: &mut S, max_rows: usize) -> Result<Option<RecordBatch>>
+where
+    S: RecordSource + ?Sized,
+{
+    let schema = source.arrow_schema();
+    let mut rows: Vec<Record> = Vec::new();
+    while rows.len() < max_rows {
+        match source.next() {
+            Some(record) => rows.push(conform_record(record?, schema.fields())),
+            None => break,
+        }
+    }
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    let mut decoder = ReaderBuilder::new(schema)
+        .with_batch_size(rows.len())
+        .with_coerce_primitive(true)
+        .build_decoder()
+        .context("build Arrow decoder")?;
+    decoder
+        .serialize(&rows)
+        .context("decode records into Arrow")?;
+    decoder.flush().context("decode records into Arrow")
+}
+
+// Groups `prefix`-relative dotted names into one Arrow field per top-level segment.
+fn nest_fields(fields: &[Field], prefix: &str) -> Vec<ArrowField> {
+    let mut names: Vec<&str> = Vec::new();
+    for field in fields {
+        let Some(rest) = field.name.strip_prefix(prefix) else {
+            continue;
+        };
+        let head = rest.split('.').next().unwrap_or(rest);
+        if !names.contains(&head) {
+            names.push(head);
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let path = format!("{prefix}{name}");
+            let own = fields.iter().find(|field| field.name == path);
+            let child_prefix = format!("{path}.");
+            let has_children = fields
+                .iter()
+                .any(|field| field.name.starts_with(&child_prefix));
+            let children = || Fields::from(nest_fields(fields, &child_prefix));
+            let dtype = match own.map(|field| &field.dtype) {
+                None | Some(DataType::Struct) if has_children => ArrowType::Struct(children()),
+                Some(DataType::List(inner)) if has_children && **inner == DataType::Struct => {
+                    list_of(ArrowType::Struct(children()))
+                }
+                Some(dtype) => map_type(dtype),
+                None => ArrowType::Utf8,
+            };
+            ArrowField::new(name, dtype, true)
+        })
+        .collect()
+}
+
+fn map_type(dtype: &DataType) -> ArrowType {
+    match dtype {
+        DataType::Bool => ArrowType::Boolean,
+        DataType::Int | DataType::Timestamp => ArrowType::Int64,
+        DataType::Float => ArrowType::Float64,
+        DataType::Date => ArrowType::Date32,
+        DataType::List(inner) => list_of(map_type(inner)),
+        DataType::String
+        | DataType::Bytes
+        | DataType::Null
+        | DataType::Struct
+        | DataType::Unknown => ArrowType::Utf8,
+    }
+}
+
+fn list_of(item: ArrowType) -> ArrowType {
+    ArrowType::List(Arc::new(ArrowField::new("item", item, true)))
+}
+
+fn conform_record(mut record: Record, fields: &Fields) -> Record {
+    for field in fields {
+        if let Some(value) = record.get_mut(field.name()) {
+            conform(value, field.data_type());
+        }
+    }
+    record
+}
+
+// Objects and arrays headed for a text column are kept as JSON text rather than rejected.
+// Text in a numeric or boolean column, such as a CSV cell outside the inference sample that
+// did not parse as the inferred type, becomes null.
+fn conform(value: &mut Value, dtype: &ArrowType) {
+    match (dtype, &mut *value) {
+        (ArrowType::Utf8, Value::Object(_) | Value::Array(_)) => {
+            *value = Value::String(value.to_string());
+        }
+        (ArrowType::Int64, Value::String(text)) if text.parse::<i64>().is_err() => {
+            *value = Value::Null;
+        }
+        (ArrowType::Float64, Value::String(text)) if text.parse::<f64>().is_err() => {
+            *value = Value::Null;
+        }
+        (ArrowType::Boolean, Value::String(_)) => *value = Value::Null,
+        (ArrowType::Struct(fields), Value::Object(map)) => {
+            for field in fields {
+                if let Some(child) = map.get_mut(field.name()) {
+                    conform(child, field.data_type());
+                }
+            }
+        }
+        (ArrowType::List(item), Value::Array(items)) => {
+            for child in items {
+                conform(child, item.data_type());
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::avro::AvroSource;
+    use crate::formats::csv::{CsvOptions, CsvSource};
+    use crate::formats::json::{JsonOptions, JsonSource};
+    use crate::formats::parquet::ParquetSource;
+    use crate::util::sample::SampleOptions;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Float64Type, Int64Type};
+    use std::path::Path;
+
+    #[test]
+    fn csv_rows_decode_to_typed_columns() {
+        let data = "id,score,name\n1,0.5,a\n2,,b\n3,2.5,c\n";
+        let source = CsvSource::from_reader(
+            data.as_bytes(),
+            &SampleOptions::default(),
+            &CsvOptions::default(),
+        )
+        .unwrap();
+        let batches: Vec<RecordBatch> = Batches::new(source, 2).collect::<Result<_>>().unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        let first = &batches[0];
+        assert_eq!(first.column(0).as_primitive::<Int64Type>().value(1), 2);
+        assert!(first.column(1).is_null(1));
+        assert_eq!(first.column(1).as_primitive::<Float64Type>().value(0), 0.5);
+        assert_eq!(first.column(2).as_string::<i32>().value(0), "a");
+    }
+
+    #[test]
+    fn cells_outside_the_sample_that_do_not_fit_become_null() {
+        let sample = SampleOptions {
+            rows: Some(1),
+            ..SampleOptions::default()
+        };
+        let source =
+            CsvSource::from_reader("id\n1\nn/a\n".as_bytes(), &sample, &CsvOptions::default())
+                .unwrap();
+        let batch = source
+            .into_batches(DEFAULT_BATCH_SIZE)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 1);
+        assert!(batch.column(0).is_null(1));
+    }
+
+    #[test]
+    fn flattened_json_fields_become_structs() {
+        let data = "{\"id\":1,\"user\":{\"name\":\"a\",\"tags\":[\"x\"]}}\n{\"id\":2}\n";
+        let source = JsonSource::from_reader(
+            data.as_bytes(),
+            &SampleOptions::default(),
+            &JsonOptions::default(),
+        )
+        .unwrap();
+        let schema = source.arrow_schema();
+        assert_eq!(
+            schema.field_with_name("user").unwrap().data_type(),
+            &ArrowType::Struct(Fields::from(vec![
+                ArrowField::new("name", ArrowType::Utf8, true),
+                ArrowField::new("tags", list_of(ArrowType::Utf8), true),
+            ]))
+        );
+        let batch = Batches::new(source, DEFAULT_BATCH_SIZE)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert!(batch.column(1).is_null(1));
+    }
+
+    #[test]
+    fn parquet_batches_keep_file_schema() {
+        let source = ParquetSource::open(Path::new("tests/fixtures/sample.parquet")).unwrap();
+        let file_schema = source.arrow_schema();
+        let batches: Vec<RecordBatch> = Batches::new(source, 1).collect::<Result<_>>().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].schema(), file_schema);
+    }
+
+    #[test]
+    fn avro_records_decode_against_inferred_schema() {
+        let source = AvroSource::open(Path::new("tests/fixtures/sample.avro")).unwrap();
+        let batch = source
+            .into_batches(DEFAULT_BATCH_SIZE)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(1), 2);
+    }
+}
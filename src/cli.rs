@@ -1,8 +1,21 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
+use crate::commands::InputOptions;
+use crate::commands::cat::CatOutput;
+use crate::commands::detect::DetectOutput;
+use crate::commands::sample::{DEFAULT_SAMPLE_SIZE, SampleOutput, SampleSize};
+use crate::dataset::PartitionFilter;
 use crate::formats::Format;
+use crate::formats::csv::{CsvOptions, HeaderMode};
+use crate::formats::delta::DeltaOptions;
+use crate::formats::detect::MismatchPolicy;
+use crate::formats::json::{JsonOptions, RecordsPath};
+use crate::formats::protobuf::{ProtoFraming, ProtobufOptions};
+use crate::formats::spreadsheet::{SheetHeader, SpreadsheetOptions};
+use crate::util::encoding::TextEncoding;
+use crate::util::sample::{DEFAULT_SAMPLE_ROWS, SampleMode, SampleOptions};
 use clap::{ArgAction, builder::BoolishValueParser};
 
 #[derive(Debug, Parser)]
@@ -20,11 +33,10 @@ pub struct Cli {
 pub enum Command {
     /// Detect format and print schema
     Schema {
-        /// Input file path
+        /// Input file, directory or quoted glob pattern
         file: PathBuf,
-        /// Override detected format
-        #[arg(long, value_enum)]
-        format: Option<FormatArg>,
+        #[command(flatten)]
+        input: InputArgs,
         /// Show the detected format name
         #[arg(
             long,
@@ -44,15 +56,97 @@ pub enum Command {
     },
     /// Print file contents (raw for JSON/CSV, JSON Lines for binary formats)
     Cat {
-        /// Input file path
+        /// Input file, directory or quoted glob pattern
         file: PathBuf,
-        /// Override detected format
-        #[arg(long, value_enum)]
-        format: Option<FormatArg>,
-        /// Limit number of records for Parquet/Avro/ORC
+        #[command(flatten)]
+        input: InputArgs,
+        /// Limit number of records (implies jsonl output)
         #[arg(long)]
         limit: Option<usize>,
+        /// Skip this many leading records (implies jsonl output)
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        /// Only output these top-level columns, in this order (implies jsonl output)
+        #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+        columns: Vec<String>,
+        /// Output layout (default: raw for a single JSON/CSV file, jsonl otherwise)
+        #[arg(long, value_enum)]
+        output: Option<OutputArg>,
+    },
+    /// Print the detected format, optionally with the evidence for it
+    Detect {
+        /// Input file or directory (`-` for stdin)
+        file: PathBuf,
+        /// Show each detection rule's result, the confidence and any conflicting evidence
+        #[arg(long)]
+        explain: bool,
+        /// Output layout (json always includes the full explanation)
+        #[arg(long, value_enum, default_value_t = DetectOutputArg::Text)]
+        output: DetectOutputArg,
+        /// Check each file's extension against its content and fail on a mismatch
+        /// (--strict=warn only warns)
+        #[arg(
+            long,
+            value_enum,
+            value_name = "MODE",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "error"
+        )]
+        strict: Option<StrictArg>,
+    },
+    /// Print a random sample of records (CSV for CSV input, JSON Lines otherwise)
+    Sample {
+        /// Input file, directory or quoted glob pattern
+        file: PathBuf,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Number of records to keep, chosen uniformly at random (--seed picks the sample)
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = DEFAULT_SAMPLE_SIZE,
+            conflicts_with = "fraction"
+        )]
+        rows: usize,
+        /// Keep each record with this probability (0 to 1) instead of a fixed number
+        #[arg(long, value_parser = parse_fraction)]
+        fraction: Option<f64>,
+        /// Only output these top-level columns, in this order
+        #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+        columns: Vec<String>,
+        /// Output layout (default: csv for CSV input, jsonl otherwise)
+        #[arg(long, value_enum)]
+        output: Option<SampleOutputArg>,
     },
+    /// Browse records and schema in an interactive terminal viewer
+    View {
+        /// Input file, directory or quoted glob pattern
+        file: PathBuf,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+}
+
+/// The mismatch policy for `--strict`: ignore mismatches when it is not given.
+pub fn mismatch_policy(strict: Option<StrictArg>) -> MismatchPolicy {
+    strict.map_or(MismatchPolicy::Ignore, StrictArg::to_policy)
+}
+
+/// The `sample` size: `--fraction` when given, else `-n`.
+pub fn sample_size(rows: usize, fraction: Option<f64>) -> SampleSize {
+    fraction.map_or(SampleSize::Rows(rows), SampleSize::Fraction)
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a number"))?;
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err(format!("{fraction} is not between 0 and 1"))
+    }
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -62,6 +156,24 @@ pub enum FormatArg {
     Parquet,
     Avro,
     Orc,
+    /// Arrow IPC file or stream (Feather v2)
+    #[value(alias = "feather")]
+    ArrowIpc,
+    /// Excel or OpenDocument workbook
+    #[value(alias = "xlsx", alias = "ods")]
+    Spreadsheet,
+    /// Protobuf messages (needs --proto-descriptor and --message)
+    #[value(alias = "proto")]
+    Protobuf,
+    /// Concatenated MessagePack values
+    #[value(name = "msgpack", alias = "messagepack")]
+    MessagePack,
+    /// Concatenated CBOR values
+    Cbor,
+    /// Delta Lake table directory
+    Delta,
+    /// Iceberg table directory or metadata.json file
+    Iceberg,
 }
 
 impl FormatArg {
@@ -72,6 +184,335 @@ impl FormatArg {
             FormatArg::Parquet => Format::Parquet,
             FormatArg::Avro => Format::Avro,
             FormatArg::Orc => Format::Orc,
+            FormatArg::ArrowIpc => Format::ArrowIpc,
+            FormatArg::Spreadsheet => Format::Spreadsheet,
+            FormatArg::Protobuf => Format::Protobuf,
+            FormatArg::MessagePack => Format::MessagePack,
+            FormatArg::Cbor => Format::Cbor,
+            FormatArg::Delta => Format::Delta,
+            FormatArg::Iceberg => Format::Iceberg,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum StrictArg {
+    /// Print a warning and read the file as detected
+    Warn,
+    /// Refuse to read the file
+    Error,
+}
+
+impl StrictArg {
+    pub fn to_policy(self) -> MismatchPolicy {
+        match self {
+            StrictArg::Warn => MismatchPolicy::Warn,
+            StrictArg::Error => MismatchPolicy::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputArg {
+    /// Input bytes unchanged (JSON/CSV only)
+    Raw,
+    /// One typed JSON object per record
+    Jsonl,
+    /// Aligned text table for reading in a terminal (nested values flattened or abbreviated)
+    Table,
+}
+
+impl OutputArg {
+    pub fn to_output(self) -> CatOutput {
+        match self {
+            OutputArg::Raw => CatOutput::Raw,
+            OutputArg::Jsonl => CatOutput::Jsonl,
+            OutputArg::Table => CatOutput::Table,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DetectOutputArg {
+    /// Format name, or a readable report with --explain
+    Text,
+    /// One JSON object for scripting
+    Json,
+}
+
+impl DetectOutputArg {
+    pub fn to_output(self) -> DetectOutput {
+        match self {
+            DetectOutputArg::Text => DetectOutput::Text,
+            DetectOutputArg::Json => DetectOutput::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SampleOutputArg {
+    /// One typed JSON object per record
+    Jsonl,
+    /// Header row plus one row per record; nested values as JSON text
+    Csv,
+    /// A Parquet file (redirect stdout to a file)
+    Parquet,
+}
+
+impl SampleOutputArg {
+    pub fn to_output(self) -> SampleOutput {
+        match self {
+            SampleOutputArg::Jsonl => SampleOutput::Jsonl,
+            SampleOutputArg::Csv => SampleOutput::Csv,
+            SampleOutputArg::Parquet => SampleOutput::Parquet,
+        }
+    }
+}
+
+/// The input flags of the commands that read records.
+#[derive(Debug, Clone, Args)]
+pub struct InputArgs {
+    /// Override detected format
+    #[arg(long, value_enum)]
+    pub format: Option<FormatArg>,
+    /// Check each file's extension against its content and fail on a mismatch
+    /// (--strict=warn only warns)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "error"
+    )]
+    pub strict: Option<StrictArg>,
+    /// Encoding of CSV/JSON input, transcoded to UTF-8 (e.g. windows-1252, shift_jis,
+    /// utf-16le); a byte order mark takes precedence
+    #[arg(long, value_name = "ENCODING", default_value_t = TextEncoding::Auto)]
+    pub encoding: TextEncoding,
+    #[command(flatten)]
+    pub sample: SampleArgs,
+    #[command(flatten)]
+    pub csv: CsvArgs,
+    #[command(flatten)]
+    pub json: JsonArgs,
+    #[command(flatten)]
+    pub spreadsheet: SpreadsheetArgs,
+    #[command(flatten)]
+    pub protobuf: ProtobufArgs,
+    #[command(flatten)]
+    pub delta: DeltaArgs,
+    /// Only read partitions where COLUMN=VALUE (Hive-style partition columns only)
+    #[arg(long = "filter", value_name = "COLUMN=VALUE")]
+    pub filters: Vec<PartitionFilter>,
+}
+
+impl InputArgs {
+    pub fn to_options(&self) -> InputOptions {
+        InputOptions {
+            format: self
+                .format
+                .map(FormatArg::to_format)
+                .or_else(|| self.protobuf.implied_format()),
+            sample: self.sample.to_options(),
+            encoding: self.encoding,
+            csv: self.csv.to_options(),
+            json: self.json.to_options(),
+            spreadsheet: self.spreadsheet.to_options(),
+            protobuf: self.protobuf.to_options(),
+            delta: self.delta.to_options(),
+            filters: self.filters.clone(),
+            mismatch_policy: mismatch_policy(self.strict),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SampleArgs {
+    /// Number of records sampled for JSON/CSV schema inference
+    #[arg(long, default_value_t = DEFAULT_SAMPLE_ROWS, conflicts_with = "full_scan")]
+    pub sample_rows: usize,
+    /// Inspect every record for JSON/CSV schema inference
+    #[arg(long)]
+    pub full_scan: bool,
+    /// How sampled records are chosen
+    #[arg(long, value_enum, default_value_t = SamplingArg::Head)]
+    pub sampling: SamplingArg,
+    /// Seed for random sampling
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+impl SampleArgs {
+    pub fn to_options(&self) -> SampleOptions {
+        SampleOptions {
+            rows: (!self.full_scan).then_some(self.sample_rows),
+            mode: self.sampling.to_mode(),
+            seed: self.seed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvArgs {
+    /// Treat the first CSV row as data and generate column names (column_1..N)
+    #[arg(long, conflicts_with = "detect_header")]
+    pub no_header: bool,
+    /// Guess whether the first CSV row is a header
+    #[arg(long)]
+    pub detect_header: bool,
+    /// CSV column names to use instead of the header row (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub column_names: Vec<String>,
+    /// Extra CSV cell values treated as null (comma-separated, e.g. NA,NULL,\N)
+    #[arg(long, value_delimiter = ',')]
+    pub null_values: Vec<String>,
+    /// Extra CSV cell values treated as true (comma-separated, e.g. yes,Y,1)
+    #[arg(long, value_delimiter = ',')]
+    pub true_values: Vec<String>,
+    /// Extra CSV cell values treated as false (comma-separated, e.g. no,N,0)
+    #[arg(long, value_delimiter = ',')]
+    pub false_values: Vec<String>,
+}
+
+impl CsvArgs {
+    pub fn to_options(&self) -> CsvOptions {
+        let header = if self.no_header {
+            HeaderMode::Absent
+        } else if self.detect_header {
+            HeaderMode::Detect
+        } else {
+            HeaderMode::Present
+        };
+        CsvOptions {
+            header,
+            column_names: self.column_names.clone(),
+            null_values: self.null_values.clone(),
+            true_values: self.true_values.clone(),
+            false_values: self.false_values.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct JsonArgs {
+    /// Read the JSON records found at this path inside the document (e.g. $.data.items)
+    #[arg(long, value_name = "PATH")]
+    pub records_path: Option<RecordsPath>,
+}
+
+impl JsonArgs {
+    pub fn to_options(&self) -> JsonOptions {
+        JsonOptions {
+            records_path: self.records_path.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SpreadsheetArgs {
+    /// Spreadsheet sheet to read, by name or 1-based position (default: the first sheet)
+    #[arg(long, value_name = "SHEET")]
+    pub sheet: Option<String>,
+    /// Spreadsheet row holding the column names, 1-based; 0 means no header row
+    /// (default: the first non-empty row)
+    #[arg(long, value_name = "ROW")]
+    pub header_row: Option<usize>,
+}
+
+impl SpreadsheetArgs {
+    pub fn to_options(&self) -> SpreadsheetOptions {
+        let header = match self.header_row {
+            None => SheetHeader::FirstRow,
+            Some(0) => SheetHeader::Absent,
+            Some(row) => SheetHeader::Row(row),
+        };
+        SpreadsheetOptions {
+            sheet: self.sheet.clone(),
+            header,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ProtobufArgs {
+    /// Protobuf descriptor set (protoc --descriptor_set_out) or .proto file; repeatable
+    #[arg(long, value_name = "FILE")]
+    pub proto_descriptor: Vec<PathBuf>,
+    /// Fully qualified protobuf message type, e.g. pkg.Type (implies --format protobuf)
+    #[arg(long, value_name = "TYPE", requires = "proto_descriptor")]
+    pub message: Option<String>,
+    /// How protobuf messages are framed (default: detect)
+    #[arg(long, value_enum, default_value_t = ProtoFramingArg::Auto)]
+    pub proto_framing: ProtoFramingArg,
+}
+
+impl ProtobufArgs {
+    pub fn to_options(&self) -> ProtobufOptions {
+        ProtobufOptions {
+            descriptors: self.proto_descriptor.clone(),
+            message: self.message.clone(),
+            framing: self.proto_framing.to_framing(),
+        }
+    }
+
+    /// Protobuf cannot be detected, so naming a message type selects it.
+    pub fn implied_format(&self) -> Option<Format> {
+        self.message.as_ref().map(|_| Format::Protobuf)
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DeltaArgs {
+    /// Read this Delta table version instead of the latest one
+    #[arg(long, value_name = "VERSION")]
+    pub as_of_version: Option<u64>,
+}
+
+impl DeltaArgs {
+    pub fn to_options(&self) -> DeltaOptions {
+        DeltaOptions {
+            version: self.as_of_version,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ProtoFramingArg {
+    /// Length-delimited if the input splits cleanly into messages, otherwise one message
+    Auto,
+    /// Each message is prefixed with its varint length
+    Delimited,
+    /// The whole input is one message
+    Single,
+}
+
+impl ProtoFramingArg {
+    pub fn to_framing(self) -> ProtoFraming {
+        match self {
+            ProtoFramingArg::Auto => ProtoFraming::Auto,
+            ProtoFramingArg::Delimited => ProtoFraming::Delimited,
+            ProtoFramingArg::Single => ProtoFraming::Single,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SamplingArg {
+    /// First N records
+    Head,
+    /// Uniform random sample over the whole input
+    Random,
+    /// Evenly spaced records across the whole input
+    Stratified,
+}
+
+impl SamplingArg {
+    pub fn to_mode(self) -> SampleMode {
+        match self {
+            SamplingArg::Head => SampleMode::Head,
+            SamplingArg::Random => SampleMode::Random,
+            SamplingArg::Stratified => SampleMode::Stratified,
         }
     }
 }
@@ -1,50 +1,177 @@
-use std::io::Read;
+use std::fs::File;
+use std::io::{Read, Write};
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 
-use crate::formats::{self, Format, detect::detect_format, detect::detect_format_prefix};
+use crate::commands::InputOptions;
+use crate::dataset::{Dataset, OpenOptions, resolve_inputs};
+use crate::formats::Format;
+use crate::formats::detect::{
+    check_extension, detect_format, detect_format_prefix, detect_table_format,
+};
+use crate::render::{jsonl, table};
+use crate::source::RecordSource;
+use crate::util::encoding;
 use crate::util::errors::UnsupportedFormatError;
 use crate::util::io;
 
 const STDIN_PREFIX_LIMIT: usize = 64 * 1024;
 
-pub fn run(path: &Path, format_override: Option<Format>, limit: Option<usize>) -> Result<()> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatOutput {
+    /// Copy a single input unchanged (JSON and CSV only), apart from transcoding it to UTF-8.
+    Raw,
+    /// One JSON object per record; JSON values that are not objects are copied as they are.
+    Jsonl,
+    /// An aligned text table, fitted to the terminal width.
+    Table,
+}
+
+/// Options for `cat` beyond the input path.
+#[derive(Debug, Clone, Default)]
+pub struct CatOptions {
+    /// Number of leading records to skip; set with `limit`, output defaults to JSON Lines.
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Top-level columns to keep, in order; empty keeps every column.
+    pub columns: Vec<String>,
+    /// Output layout; `None` keeps a single text file raw and renders everything else, or a
+    /// selection of columns or records, as JSON Lines.
+    pub output: Option<CatOutput>,
+    pub input: InputOptions,
+}
+
+/// Writes the input's records to `out`; mismatched extensions are reported on stderr.
+pub fn run<W: Write>(path: &Path, options: &CatOptions, out: &mut W) -> Result<()> {
+    // Raw output copies the input unchanged, so it cannot select columns or records; asking
+    // for either defaults to JSON Lines.
+    let selects = !options.columns.is_empty()
+        || options.input.json.records_path.is_some()
+        || options.offset > 0
+        || options.limit.is_some();
+    let output = match options.output {
+        None if selects => Some(CatOutput::Jsonl),
+        Some(CatOutput::Raw) if selects => bail!(
+            "raw output copies the input unchanged, so it cannot apply --columns, --records-path, --offset or --limit; use --output jsonl"
+        ),
+        output => output,
+    };
     if io::is_stdin_path(path) {
-        let format = if let Some(format) = format_override {
-            format
-        } else {
-            let stdin = std::io::stdin();
-            let mut stdin_lock = stdin.lock();
-            let prefix = io::read_prefix(&mut stdin_lock, STDIN_PREFIX_LIMIT)?;
-            let format = detect_format_prefix(&prefix)?;
-            let mut reader = std::io::Cursor::new(prefix).chain(stdin_lock);
-            return stream_stdin_cat(format, &mut reader);
+        let stdin = std::io::stdin().lock();
+        let (format, reader): (Format, Box<dyn Read>) = match options.input.format {
+            Some(format) => (format, Box::new(stdin)),
+            None => {
+                let mut stdin = stdin;
+                let prefix = io::read_prefix(&mut stdin, STDIN_PREFIX_LIMIT)?;
+                let format = detect_format_prefix(&prefix)?;
+                (format, Box::new(std::io::Cursor::new(prefix).chain(stdin)))
+            }
         };
-        let stdin = std::io::stdin();
-        let mut reader = stdin.lock();
-        return stream_stdin_cat(format, &mut reader);
+        let output = output_for(format, output)?;
+        if output == CatOutput::Raw {
+            std::io::copy(&mut encoding::decode(reader, options.input.encoding)?, out)?;
+            return Ok(());
+        }
+        super::report_stdin_sampling(&options.input.sample, &mut std::io::stderr())?;
+        let dataset = options.open_options().format(format).open_reader(reader)?;
+        return write_records(dataset, output, out);
     }
 
-    let format = format_override.unwrap_or(detect_format(path)?);
-    match format {
-        Format::Json | Format::Csv => io::stream_file(path, &mut std::io::stdout())?,
-        Format::Parquet => formats::parquet::cat(path, limit)?,
-        Format::Avro => formats::avro::cat(path, limit)?,
-        Format::Orc => formats::orc::cat(path, limit)?,
+    // A table is read from its metadata rather than as a directory of files.
+    let table = match options.input.format {
+        Some(format) => Some(format).filter(Format::is_table),
+        None => detect_table_format(path),
+    };
+    if let Some(format) = table {
+        let output = output_for(format, output)?;
+        return write_records(options.open_options().open(path)?, output, out);
     }
-    Ok(())
+
+    let files = resolve_inputs(path, &options.input.filters)?;
+    // Several files are read as one dataset, so a CSV header is written once and files in
+    // different formats are an error rather than concatenated. Partition columns only exist
+    // in records.
+    let partitioned = files.iter().any(|file| !file.partitions.is_empty());
+    let output = match output {
+        None if files.len() > 1 || partitioned => Some(CatOutput::Jsonl),
+        Some(CatOutput::Raw) if files.len() > 1 => bail!(
+            "raw output copies a single file, but {} matches {} files; use --output jsonl",
+            path.display(),
+            files.len()
+        ),
+        Some(CatOutput::Raw) if partitioned => bail!(
+            "raw output cannot add the partition columns of {}; use --output jsonl",
+            path.display()
+        ),
+        output => output,
+    };
+    let format = options
+        .input
+        .format
+        .map_or_else(|| detect_format(&files[0].path), Ok)?;
+    let output = output_for(format, output)?;
+    if output == CatOutput::Raw {
+        let path = &files[0].path;
+        if options.input.format.is_none() {
+            let mismatch = check_extension(path, options.input.mismatch_policy)?;
+            super::report_mismatches(mismatch.as_slice(), &mut std::io::stderr())?;
+        }
+        let reader = File::open(path).with_context(|| format!("open {}", path.display()))?;
+        std::io::copy(&mut encoding::decode(reader, options.input.encoding)?, out)?;
+        return Ok(());
+    }
+    let dataset = options.open_options().open(path)?;
+    super::report_mismatches(dataset.mismatches(), &mut std::io::stderr())?;
+    write_records(dataset, output, out)
 }
 
-fn stream_stdin_cat(format: Format, reader: &mut impl std::io::Read) -> Result<()> {
-    match format {
-        Format::Json | Format::Csv => {
-            std::io::copy(reader, &mut std::io::stdout())?;
-            Ok(())
+impl CatOptions {
+    fn open_options(&self) -> OpenOptions {
+        let mut open = self.input.open_options().offset(self.offset);
+        if let Some(limit) = self.limit {
+            open = open.limit(limit);
         }
-        Format::Parquet | Format::Avro | Format::Orc => Err(UnsupportedFormatError::new(
-            "stdin input is only supported for JSON and CSV",
-        )
+        if !self.columns.is_empty() {
+            open = open.columns(self.columns.iter().cloned());
+        }
+        open
+    }
+}
+
+// JSON Lines copies JSON values that are not objects unchanged; the table needs records.
+fn write_records(mut dataset: Dataset, output: CatOutput, writer: &mut impl Write) -> Result<()> {
+    if output == CatOutput::Table {
+        return table::write_records(writer, dataset, io::terminal_width());
+    }
+    while let Some(value) = dataset.next_value() {
+        jsonl::write_line(writer, &value?)?;
+    }
+    Ok(())
+}
+
+fn output_for(format: Format, output: Option<CatOutput>) -> Result<CatOutput> {
+    let binary = matches!(
+        format,
+        Format::Parquet
+            | Format::Avro
+            | Format::Orc
+            | Format::ArrowIpc
+            | Format::Spreadsheet
+            | Format::Protobuf
+            | Format::MessagePack
+            | Format::Cbor
+            | Format::Delta
+            | Format::Iceberg
+    );
+    match output {
+        Some(CatOutput::Raw) if binary => Err(UnsupportedFormatError::new(format!(
+            "raw output is not supported for {}; use --output jsonl",
+            format.as_str()
+        ))
         .into()),
+        Some(output) => Ok(output),
+        None if binary => Ok(CatOutput::Jsonl),
+        None => Ok(CatOutput::Raw),
     }
 }
@@ -0,0 +1,123 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::formats::detect::{
+    Detection, Evidence, MismatchPolicy, explain_format, explain_format_prefix,
+};
+use crate::render::jsonl;
+use crate::util::io;
+
+const STDIN_PREFIX_LIMIT: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DetectOutput {
+    /// The format name, or with `explain` the evidence as text.
+    #[default]
+    Text,
+    /// One JSON object with the format and all the evidence.
+    Json,
+}
+
+/// Options for `detect` beyond the input path.
+#[derive(Debug, Clone, Default)]
+pub struct DetectOptions {
+    /// Show every rule's evidence, the confidence and any conflicts, not just the format.
+    pub explain: bool,
+    pub output: DetectOutput,
+    /// What to do when the file's extension and content name different formats.
+    pub mismatch_policy: MismatchPolicy,
+}
+
+/// Writes the detected format of the input to `out`.
+///
+/// Every rule runs, so conflicting evidence can be reported. When no rule matches, the
+/// explanation (if requested) is written before the usual detection error is returned; a
+/// mismatched extension is likewise reported after the output, as a warning or an error.
+pub fn run<W: Write>(path: &Path, options: &DetectOptions, out: &mut W) -> Result<()> {
+    let detection = if io::is_stdin_path(path) {
+        let mut stdin = std::io::stdin().lock();
+        let mut prefix = Vec::new();
+        (&mut stdin)
+            .take(STDIN_PREFIX_LIMIT as u64)
+            .read_to_end(&mut prefix)?;
+        explain_format_prefix(&prefix)
+    } else {
+        explain_format(path)?
+    };
+    match options.output {
+        DetectOutput::Json => jsonl::write_line(out, &to_json(path, &detection))?,
+        DetectOutput::Text if options.explain => write_explanation(&detection, out)?,
+        DetectOutput::Text => {
+            if let Some(format) = detection.format() {
+                writeln!(out, "{}", format.as_str())?;
+            }
+        }
+    }
+    let mismatch = options.mismatch_policy.apply(detection.mismatch(path))?;
+    super::report_mismatches(mismatch.as_slice(), &mut std::io::stderr())?;
+    detection.into_format().map(|_| ())
+}
+
+fn write_explanation(detection: &Detection, out: &mut impl Write) -> Result<()> {
+    let format = detection.chosen().map_or("-".to_string(), found);
+    writeln!(out, "format: {format}")?;
+    if let Some(chosen) = detection.chosen() {
+        writeln!(out, "rule: {}", chosen.rule.as_str())?;
+    }
+    if let Some(confidence) = detection.confidence() {
+        writeln!(out, "confidence: {}", confidence.as_str())?;
+    }
+    for conflict in detection.conflicts() {
+        writeln!(
+            out,
+            "conflict: {} says {} ({})",
+            conflict.rule.as_str(),
+            found(conflict),
+            conflict.detail
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "rule\tformat\tevidence")?;
+    for evidence in &detection.evidence {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            evidence.rule.as_str(),
+            found(evidence),
+            evidence.detail
+        )?;
+    }
+    Ok(())
+}
+
+// The format a rule found, marking formats that cannot be read.
+fn found(evidence: &Evidence) -> String {
+    match (evidence.format, evidence.unsupported) {
+        (Some(format), _) => format.as_str().to_string(),
+        (None, Some(unsupported)) => format!("{} (unsupported)", unsupported.as_str()),
+        (None, None) => "-".to_string(),
+    }
+}
+
+fn to_json(path: &Path, detection: &Detection) -> Value {
+    let evidence = |evidence: &Evidence| {
+        json!({
+            "rule": evidence.rule.as_str(),
+            "format": evidence.format.map(|format| format.as_str()),
+            "unsupported": evidence.unsupported.map(|unsupported| unsupported.as_str()),
+            "detail": evidence.detail,
+        })
+    };
+    json!({
+        "path": path.display().to_string(),
+        "format": detection.format().map(|format| format.as_str()),
+        "unsupported": detection.unsupported().map(|unsupported| unsupported.as_str()),
+        "rule": detection.chosen().map(|chosen| chosen.rule.as_str()),
+        "confidence": detection.confidence().map(|confidence| confidence.as_str()),
+        "conflicts": detection.conflicts().into_iter().map(evidence).collect::<Vec<_>>(),
+        "evidence": detection.evidence.iter().map(evidence).collect::<Vec<_>>(),
+    })
+}
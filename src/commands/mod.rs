@@ -1,2 +1,85 @@
 pub mod cat;
+pub mod detect;
+pub mod sample;
 pub mod schema;
+pub mod view;
+
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::dataset::{OpenOptions, PartitionFilter};
+use crate::formats::Format;
+use crate::formats::csv::CsvOptions;
+use crate::formats::delta::DeltaOptions;
+use crate::formats::detect::{Mismatch, MismatchPolicy};
+use crate::formats::json::JsonOptions;
+use crate::formats::protobuf::ProtobufOptions;
+use crate::formats::spreadsheet::SpreadsheetOptions;
+use crate::util::encoding::TextEncoding;
+use crate::util::sample::{SampleMode, SampleOptions};
+
+/// How the commands that read records open their input.
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    pub format: Option<Format>,
+    /// How records are chosen for schema inference.
+    pub sample: SampleOptions,
+    /// Encoding of CSV and JSON input; detected by default.
+    pub encoding: TextEncoding,
+    pub csv: CsvOptions,
+    pub json: JsonOptions,
+    pub spreadsheet: SpreadsheetOptions,
+    pub protobuf: ProtobufOptions,
+    pub delta: DeltaOptions,
+    /// Partition filters applied when reading a directory or glob.
+    pub filters: Vec<PartitionFilter>,
+    /// What to do when a file's extension and content name different formats.
+    pub mismatch_policy: MismatchPolicy,
+}
+
+impl InputOptions {
+    fn open_options(&self) -> OpenOptions {
+        let mut open = OpenOptions::new()
+            .sample(self.sample.clone())
+            .encoding(self.encoding)
+            .csv(self.csv.clone())
+            .json(self.json.clone())
+            .spreadsheet(self.spreadsheet.clone())
+            .protobuf(self.protobuf.clone())
+            .delta(self.delta.clone())
+            .mismatch_policy(self.mismatch_policy);
+        if let Some(format) = self.format {
+            open = open.format(format);
+        }
+        for filter in &self.filters {
+            open = open.filter(filter.clone());
+        }
+        open
+    }
+}
+
+// Warns about files whose extension names a different format than their content.
+fn report_mismatches(mismatches: &[Mismatch], writer: &mut impl Write) -> Result<()> {
+    for mismatch in mismatches {
+        writeln!(writer, "warning: {mismatch}")?;
+    }
+    Ok(())
+}
+
+// Warns that stdin, which cannot be read twice, is typed from its first records: random and
+// stratified sampling would need the whole input before the first record is written.
+fn report_stdin_sampling(sample: &SampleOptions, writer: &mut impl Write) -> Result<()> {
+    let mode = match sample.mode {
+        SampleMode::Head => return Ok(()),
+        SampleMode::Random => "random",
+        SampleMode::Stratified => "stratified",
+    };
+    if let Some(rows) = sample.rows {
+        writeln!(
+            writer,
+            "warning: --sampling {mode} is not supported for stdin; the schema is inferred from the first {rows} records"
+        )?;
+    }
+    Ok(())
+}
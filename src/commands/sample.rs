@@ -0,0 +1,333 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use arrow::array::UInt32Array;
+use arrow::compute::{concat_batches, take_record_batch};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use serde_json::Value;
+
+use crate::batch::DEFAULT_BATCH_SIZE;
+use crate::commands::InputOptions;
+use crate::dataset::{OpenOptions, project_record, project_schema};
+use crate::formats::Format;
+use crate::formats::detect::{check_extension, detect_format};
+use crate::formats::parquet::ParquetSource;
+use crate::model::schema::Schema;
+use crate::render::{csv, jsonl};
+use crate::source::{Record, RecordSource};
+use crate::util::io;
+use crate::util::sample::{
+    Rng, SampleMode, SampleOptions, Sampler, bernoulli_indices, choose_indices,
+};
+
+pub const DEFAULT_SAMPLE_SIZE: usize = 1000;
+
+/// How many records `sample` keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    /// Up to this many records, chosen uniformly.
+    Rows(usize),
+    /// Each record independently with this probability.
+    Fraction(f64),
+}
+
+impl Default for SampleSize {
+    fn default() -> Self {
+        SampleSize::Rows(DEFAULT_SAMPLE_SIZE)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleOutput {
+    /// One JSON object per record.
+    Jsonl,
+    /// A header row followed by one row per record.
+    Csv,
+    /// A Parquet file.
+    Parquet,
+}
+
+/// Options for `sample` beyond the input path.
+#[derive(Debug, Clone, Default)]
+pub struct SampleCommandOptions {
+    pub size: SampleSize,
+    /// Seed for choosing records; the same seed and input give the same sample.
+    pub seed: u64,
+    /// Top-level columns to keep, in order; empty keeps every column.
+    pub columns: Vec<String>,
+    /// Output layout; `None` writes CSV for CSV input and JSON Lines otherwise.
+    pub output: Option<SampleOutput>,
+    /// How the input is opened; its `sample` picks the records read for schema inference,
+    /// not the ones written.
+    pub input: InputOptions,
+}
+
+/// Writes a random sample of the input's records to `out`, in input order; mismatched
+/// extensions are reported on stderr.
+///
+/// Records are reservoir-sampled in one pass. A single Parquet file is sampled from its footer
+/// instead: the row count picks the rows, and only the row groups holding them are read.
+pub fn run<W: Write>(path: &Path, options: &SampleCommandOptions, out: &mut W) -> Result<()> {
+    if io::is_stdin_path(path) {
+        super::report_stdin_sampling(&options.input.sample, &mut std::io::stderr())?;
+        let dataset = options
+            .open_options()
+            .open_reader(std::io::stdin().lock())?;
+        return sample_source(dataset, options, out);
+    }
+    if path.is_file() && options.input.filters.is_empty() {
+        let format = options
+            .input
+            .format
+            .map_or_else(|| detect_format(path), Ok)?;
+        if format == Format::Parquet {
+            if options.input.format.is_none() {
+                let mismatch = check_extension(path, options.input.mismatch_policy)?;
+                super::report_mismatches(mismatch.as_slice(), &mut std::io::stderr())?;
+            }
+            return sample_parquet(path, options, out);
+        }
+    }
+    let dataset = options.open_options().open(path)?;
+    super::report_mismatches(dataset.mismatches(), &mut std::io::stderr())?;
+    sample_source(dataset, options, out)
+}
+
+impl SampleCommandOptions {
+    fn open_options(&self) -> OpenOptions {
+        let open = self.input.open_options();
+        if self.columns.is_empty() {
+            return open;
+        }
+        open.columns(self.columns.iter().cloned())
+    }
+}
+
+/// The sampled records, read back as a source so every output shares the record path.
+struct Sample {
+    format: Format,
+    schema: Schema,
+    records: VecDeque<Record>,
+}
+
+impl Iterator for Sample {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.pop_front().map(Ok)
+    }
+}
+
+impl RecordSource for Sample {
+    fn format(&self) -> Format {
+        self.format
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
+
+// Parquet output samples the source's own batches, so Arrow types such as timestamps and
+// structs are kept; other outputs sample records.
+fn sample_source(
+    source: impl RecordSource,
+    options: &SampleCommandOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    if options.output == Some(SampleOutput::Parquet) {
+        let schema = source.arrow_schema();
+        let batches = draw_batches(source, options)?;
+        return write_parquet(schema, batches.into_iter().map(Ok), out);
+    }
+    write_sample(draw(source, options)?, options, out)
+}
+
+fn draw(source: impl RecordSource, options: &SampleCommandOptions) -> Result<Sample> {
+    let format = source.format();
+    let schema = source.schema().clone();
+    let records = match options.size {
+        SampleSize::Rows(rows) => {
+            let mut sampler = Sampler::new(&SampleOptions {
+                rows: Some(rows),
+                mode: SampleMode::Random,
+                seed: options.seed,
+            });
+            for (index, record) in source.enumerate() {
+                sampler.offer((index, record?));
+            }
+            let mut kept = sampler.finish();
+            kept.sort_unstable_by_key(|(index, _)| *index);
+            kept.into_iter().map(|(_, record)| record).collect()
+        }
+        SampleSize::Fraction(fraction) => {
+            let mut rng = Rng::new(options.seed);
+            let mut kept = VecDeque::new();
+            for record in source {
+                let record = record?;
+                if rng.next_f64() < fraction {
+                    kept.push_back(record);
+                }
+            }
+            kept
+        }
+    };
+    Ok(Sample {
+        format,
+        schema,
+        records,
+    })
+}
+
+// Chooses the same records as `draw`, copying the chosen rows out of each batch.
+fn draw_batches(
+    mut source: impl RecordSource,
+    options: &SampleCommandOptions,
+) -> Result<Vec<RecordBatch>> {
+    let schema = source.arrow_schema();
+    let take = |batch: &RecordBatch, rows: Vec<u32>| {
+        take_record_batch(batch, &UInt32Array::from(rows)).context("copy sampled rows")
+    };
+    match options.size {
+        SampleSize::Rows(rows) => {
+            let mut sampler = Sampler::new(&SampleOptions {
+                rows: Some(rows),
+                mode: SampleMode::Random,
+                seed: options.seed,
+            });
+            let mut index = 0u64;
+            while let Some(batch) = source.next_batch(DEFAULT_BATCH_SIZE)? {
+                for row in 0..batch.num_rows() {
+                    sampler.offer_with(|| (index, take(&batch, vec![row as u32])));
+                    index += 1;
+                }
+            }
+            let mut kept = sampler.finish();
+            kept.sort_unstable_by_key(|(index, _)| *index);
+            let kept = kept
+                .into_iter()
+                .map(|(_, batch)| batch)
+                .collect::<Result<Vec<_>>>()?;
+            Ok(vec![
+                concat_batches(&schema, &kept).context("combine sampled rows")?,
+            ])
+        }
+        SampleSize::Fraction(fraction) => {
+            let mut rng = Rng::new(options.seed);
+            let mut kept = Vec::new();
+            while let Some(batch) = source.next_batch(DEFAULT_BATCH_SIZE)? {
+                let rows: Vec<u32> = (0..batch.num_rows() as u32)
+                    .filter(|_| rng.next_f64() < fraction)
+                    .collect();
+                if !rows.is_empty() {
+                    kept.push(take(&batch, rows)?);
+                }
+            }
+            Ok(kept)
+        }
+    }
+}
+
+fn sample_parquet(path: &Path, options: &SampleCommandOptions, out: &mut impl Write) -> Result<()> {
+    let mut source = ParquetSource::open(path)?;
+    let mut rng = Rng::new(options.seed);
+    let rows = match options.size {
+        SampleSize::Rows(rows) => choose_indices(source.num_rows(), rows, &mut rng),
+        SampleSize::Fraction(fraction) => bernoulli_indices(source.num_rows(), fraction, &mut rng),
+    };
+    source.select_rows(&rows)?;
+    if options.columns.is_empty() {
+        // Parquet output keeps the file's own Arrow types.
+        return write_sample(source, options, out);
+    }
+    let columns = Some(options.columns.as_slice());
+    let schema = project_schema(source.schema().clone(), columns)?;
+    let records = source
+        .map(|record| record.map(|record| project_record(record, columns)))
+        .collect::<Result<_>>()?;
+    let sample = Sample {
+        format: Format::Parquet,
+        schema,
+        records,
+    };
+    write_sample(sample, options, out)
+}
+
+fn write_sample<S: RecordSource>(
+    source: S,
+    options: &SampleCommandOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    let output = match options.output {
+        Some(output) => output,
+        None if source.format() == Format::Csv => SampleOutput::Csv,
+        None => SampleOutput::Jsonl,
+    };
+    match output {
+        SampleOutput::Jsonl => {
+            for record in source {
+                jsonl::write_line(out, &Value::Object(record?))?;
+            }
+        }
+        SampleOutput::Csv => {
+            let records = source.collect::<Result<Vec<_>>>()?;
+            csv::write_records(out, &records)?;
+        }
+        SampleOutput::Parquet => {
+            let batches = source.into_batches(DEFAULT_BATCH_SIZE);
+            write_parquet(batches.schema(), batches, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_parquet(
+    schema: SchemaRef,
+    batches: impl Iterator<Item = Result<RecordBatch>>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut writer =
+        ArrowWriter::try_new(Vec::new(), schema, None).context("create Parquet writer")?;
+    for batch in batches {
+        writer.write(&batch?).context("write Parquet batch")?;
+    }
+    out.write_all(&writer.into_inner().context("finish Parquet file")?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_csv(size: SampleSize, seed: u64) -> Vec<Record> {
+        let input: String = std::iter::once("id\n".to_string())
+            .chain((0..200).map(|id| format!("{id}\n")))
+            .collect();
+        let dataset = OpenOptions::new()
+            .open_reader(std::io::Cursor::new(input))
+            .unwrap();
+        let options = SampleCommandOptions {
+            size,
+            seed,
+            ..SampleCommandOptions::default()
+        };
+        draw(dataset, &options).unwrap().records.into()
+    }
+
+    #[test]
+    fn samples_are_seeded_and_in_input_order() {
+        let first = sample_csv(SampleSize::Rows(10), 42);
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, sample_csv(SampleSize::Rows(10), 42));
+        assert_ne!(first, sample_csv(SampleSize::Rows(10), 43));
+        let ids: Vec<i64> = first.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let kept = sample_csv(SampleSize::Fraction(0.5), 1).len();
+        assert!((60..140).contains(&kept));
+    }
+}
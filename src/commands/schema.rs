@@ -1,75 +1,168 @@
-use std::io::Read;
+use std::io::{Read, Write};
 use std::path::Path;
 
 use anyhow::Result;
 
-use crate::formats::{self, Format, detect::detect_format, detect::detect_format_prefix};
+use crate::commands::InputOptions;
+use crate::dataset::FieldConflict;
+use crate::formats::binary_json::{BinaryJsonSource, Encoding};
+use crate::formats::detect::detect_format_prefix;
+use crate::formats::{self, Format};
 use crate::render::schema_text;
+use crate::source::RecordSource;
+use crate::util::encoding;
 use crate::util::errors::UnsupportedFormatError;
 use crate::util::io;
 
 const STDIN_PREFIX_LIMIT: usize = 64 * 1024;
 
-pub fn run(
-    path: &Path,
-    format_override: Option<Format>,
-    show_format_name: bool,
-    show_columns: bool,
-) -> Result<()> {
+/// Options for `schema` beyond the input path.
+#[derive(Debug, Clone)]
+pub struct SchemaOptions {
+    pub show_format_name: bool,
+    pub show_columns: bool,
+    pub input: InputOptions,
+}
+
+impl Default for SchemaOptions {
+    fn default() -> Self {
+        Self {
+            show_format_name: true,
+            show_columns: true,
+            input: InputOptions::default(),
+        }
+    }
+}
+
+/// Writes the input's schema to `out`; conflicting field types and mismatched extensions are
+/// reported on stderr.
+pub fn run<W: Write>(path: &Path, options: &SchemaOptions, out: &mut W) -> Result<()> {
     if io::is_stdin_path(path) {
-        if let Some(format) = format_override {
+        if let Some(format) = options.input.format {
             let stdin = std::io::stdin();
             let reader = stdin.lock();
-            return render_schema_from_reader(format, reader, show_format_name, show_columns);
+            return render_schema_from_reader(format, reader, options, out);
         }
         let stdin = std::io::stdin();
         let mut stdin_lock = stdin.lock();
         let prefix = io::read_prefix(&mut stdin_lock, STDIN_PREFIX_LIMIT)?;
         let format = detect_format_prefix(&prefix)?;
         let reader = std::io::Cursor::new(prefix).chain(stdin_lock);
-        return render_schema_from_reader(format, reader, show_format_name, show_columns);
+        return render_schema_from_reader(format, reader, options, out);
     }
 
-    let format = format_override.unwrap_or(detect_format(path)?);
-    let schema = match format {
-        Format::Json => formats::json::infer_schema(path)?,
-        Format::Csv => formats::csv::infer_schema(path)?,
-        Format::Parquet => formats::parquet::infer_schema(path)?,
-        Format::Avro => formats::avro::infer_schema(path)?,
-        Format::Orc => formats::orc::infer_schema(path)?,
-    };
+    let dataset = options.input.open_options().open(path)?;
+    super::report_mismatches(dataset.mismatches(), &mut std::io::stderr())?;
+    report_conflicts(dataset.conflicts(), &mut std::io::stderr())?;
+
     schema_text::render(
-        &schema,
-        format,
-        show_format_name,
-        show_columns,
-        &mut std::io::stdout(),
+        dataset.schema(),
+        dataset.format(),
+        dataset.json_layout(),
+        dataset.table_info().as_ref(),
+        options.show_format_name,
+        options.show_columns,
+        out,
     )?;
     Ok(())
 }
 
-fn render_schema_from_reader<R: Read>(
+// Lists fields whose non-null types differ between files, grouped by type.
+fn report_conflicts(conflicts: &[FieldConflict], writer: &mut impl Write) -> Result<()> {
+    for conflict in conflicts {
+        let details = conflict
+            .types
+            .iter()
+            .map(|(dtype, files)| {
+                let files = files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<_>>();
+                format!("{dtype} in {}", files.join(", "))
+            })
+            .collect::<Vec<_>>();
+        writeln!(
+            writer,
+            "warning: field `{}` has conflicting types: {}",
+            conflict.field,
+            details.join("; ")
+        )?;
+    }
+    Ok(())
+}
+
+fn render_schema_from_reader<R: Read + 'static>(
     format: Format,
     reader: R,
-    show_format_name: bool,
-    show_columns: bool,
+    options: &SchemaOptions,
+    out: &mut impl Write,
 ) -> Result<()> {
-    let schema = match format {
-        Format::Json => formats::json::infer_schema_reader(reader)?,
-        Format::Csv => formats::csv::infer_schema_reader(reader)?,
-        Format::Parquet | Format::Avro | Format::Orc => {
-            return Err(UnsupportedFormatError::new(
-                "stdin input is only supported for JSON and CSV",
-            )
-            .into());
+    // CSV and JSON are sampled from all of stdin; binary records are typed from the first ones.
+    if matches!(format, Format::MessagePack | Format::Cbor) {
+        super::report_stdin_sampling(&options.input.sample, &mut std::io::stderr())?;
+    }
+    let (schema, json_layout) = match format {
+        Format::Json => {
+            let reader = encoding::decode(reader, options.input.encoding)?;
+            let (schema, layout) =
+                formats::json::infer_reader(reader, &options.input.sample, &options.input.json)?;
+            (schema, Some(layout))
+        }
+        Format::Csv => {
+            let reader = encoding::decode(reader, options.input.encoding)?;
+            let schema = formats::csv::infer_schema_reader(
+                reader,
+                &options.input.sample,
+                &options.input.csv,
+            )?;
+            (schema, None)
+        }
+        Format::ArrowIpc => (
+            formats::arrow_ipc::ArrowIpcSource::from_reader(reader)?
+                .schema()
+                .clone(),
+            None,
+        ),
+        Format::Spreadsheet => (
+            formats::spreadsheet::SpreadsheetSource::from_reader(
+                reader,
+                &options.input.sample,
+                &options.input.spreadsheet,
+            )?
+            .schema()
+            .clone(),
+            None,
+        ),
+        Format::Protobuf => (
+            formats::protobuf::ProtobufSource::from_reader(reader, &options.input.protobuf)?
+                .schema()
+                .clone(),
+            None,
+        ),
+        Format::MessagePack => (
+            BinaryJsonSource::from_reader(reader, Encoding::MessagePack, &options.input.sample)?
+                .schema()
+                .clone(),
+            None,
+        ),
+        Format::Cbor => (
+            BinaryJsonSource::from_reader(reader, Encoding::Cbor, &options.input.sample)?
+                .schema()
+                .clone(),
+            None,
+        ),
+        Format::Parquet | Format::Avro | Format::Orc | Format::Delta | Format::Iceberg => {
+            return Err(UnsupportedFormatError::new(formats::STDIN_UNSUPPORTED).into());
         }
     };
     schema_text::render(
         &schema,
         format,
-        show_format_name,
-        show_columns,
-        &mut std::io::stdout(),
+        json_layout,
+        None,
+        options.show_format_name,
+        options.show_columns,
+        out,
     )?;
     Ok(())
 }
@@ -0,0 +1,56 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::crossterm::terminal;
+
+use crate::commands::InputOptions;
+use crate::source::RecordSource;
+use crate::util::io;
+use crate::view::{self, App};
+
+/// Options for `view` beyond the input path.
+#[derive(Debug, Clone, Default)]
+pub struct ViewOptions {
+    pub input: InputOptions,
+}
+
+/// Shows the input in an interactive viewer until the user quits.
+///
+/// The input is opened, and its first page read, before the terminal is taken over, so read
+/// errors are reported as for any other command. Mismatch warnings are printed then too, and
+/// are visible again once the viewer exits.
+pub fn run(path: &Path, options: &ViewOptions) -> Result<()> {
+    if io::is_stdin_path(path) {
+        bail!("`view` reads keys from the terminal, so it cannot read stdin; pass a path");
+    }
+    if !std::io::stdout().is_terminal() {
+        bail!("`view` needs an interactive terminal; use `cat` to print records");
+    }
+    let dataset = options.input.open_options().open(path)?;
+    super::report_mismatches(dataset.mismatches(), &mut std::io::stderr())?;
+    let title = format!("{} ({})", path.display(), dataset.format().as_str());
+    let (_, height) = terminal::size().context("read terminal size")?;
+    let mut app = App::new(title, Box::new(dataset), view::page_size(height))?;
+
+    let mut terminal = ratatui::try_init().context("start terminal")?;
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    loop {
+        let size = terminal.size().context("read terminal size")?;
+        app.set_page(view::page_size(size.height))?;
+        terminal.draw(|frame| view::draw(frame, app))?;
+        if let Event::Key(key) = event::read().context("read terminal input")?
+            && key.kind == KeyEventKind::Press
+            && !app.handle_key(key)?
+        {
+            return Ok(());
+        }
+    }
+}
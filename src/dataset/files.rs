@@ -0,0 +1,310 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::formats::csv::{CsvOptions, infer_scalar, typed_value};
+use crate::model::schema::{DataType, Field};
+
+/// Directory name Hive uses for null partition values.
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// One file of a dataset, with the Hive-style `key=value` partition values parsed from the
+/// directories between the dataset root and the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFile {
+    pub path: PathBuf,
+    /// Partition values in path order; `None` is the Hive default (null) partition.
+    pub partitions: Vec<(String, Option<String>)>,
+}
+
+/// An equality filter on a partition column, written as `column=value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionFilter {
+    pub column: String,
+    pub value: String,
+}
+
+impl FromStr for PartitionFilter {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once('=') {
+            Some((column, value)) if !column.is_empty() => Ok(Self {
+                column: column.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("expected COLUMN=VALUE, got `{text}`")),
+        }
+    }
+}
+
+impl PartitionFilter {
+    fn matches(&self, value: Option<&str>) -> bool {
+        let Some(value) = value else {
+            return self.value == HIVE_DEFAULT_PARTITION;
+        };
+        if value == self.value {
+            return true;
+        }
+        // Compare numerically so `month=3` selects a `month=03` directory.
+        match (value.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(left), Ok(right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
+/// Expands an input argument into the files it names, in a deterministic (sorted) order.
+///
+/// A regular file is returned unchanged. A directory is walked recursively, skipping entries
+/// whose names start with `.` or `_` (such as Spark's `_SUCCESS` markers). A path that does
+/// not exist but contains `*`, `?` or `[` is treated as a glob pattern.
+///
+/// Partition directories rejected by `filters` are pruned without being read.
+pub fn resolve_inputs(path: &Path, filters: &[PartitionFilter]) -> Result<Vec<DataFile>> {
+    let mut keys = Vec::new();
+    let files = if path.is_dir() {
+        let mut files = Vec::new();
+        collect_dir(path, &mut Vec::new(), filters, &mut keys, &mut files)?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        if files.is_empty() && keys.is_empty() {
+            bail!("no data files found in directory {}", path.display());
+        }
+        files
+    } else if !path.exists() && is_glob(path) {
+        expand_glob(path, filters, &mut keys)?
+    } else {
+        vec![DataFile {
+            path: path.to_path_buf(),
+            partitions: Vec::new(),
+        }]
+    };
+
+    for filter in filters {
+        if !keys.contains(&filter.column) {
+            bail!(
+                "filter column `{}` is not a partition column",
+                filter.column
+            );
+        }
+    }
+    if files.is_empty() {
+        bail!("no files match the partition filters");
+    }
+    Ok(files)
+}
+
+/// Derives partition columns from the files' paths, typing values like CSV cells.
+pub fn partition_fields(files: &[DataFile]) -> Vec<Field> {
+    let options = CsvOptions::default();
+    let mut fields: Vec<Field> = Vec::new();
+    for file in files {
+        for (key, value) in &file.partitions {
+            let dtype = value
+                .as_deref()
+                .map_or(DataType::Null, |value| infer_scalar(value, &options));
+            match fields.iter_mut().find(|field| &field.name == key) {
+                Some(field) => {
+                    field.dtype = DataType::merge(&field.dtype, &dtype);
+                    field.nullable |= value.is_none();
+                }
+                None => fields.push(Field {
+                    name: key.clone(),
+                    dtype,
+                    nullable: value.is_none(),
+                }),
+            }
+        }
+    }
+    for field in &mut fields {
+        let everywhere = files
+            .iter()
+            .all(|file| file.partitions.iter().any(|(key, _)| key == &field.name));
+        field.nullable |= !everywhere;
+    }
+    fields
+}
+
+/// Builds the partition columns injected into each record read from `file`.
+pub fn partition_values(file: &DataFile, fields: &[Field]) -> Map<String, JsonValue> {
+    let options = CsvOptions::default();
+    let mut map = Map::new();
+    for field in fields {
+        let value = file
+            .partitions
+            .iter()
+            .find(|(key, _)| key == &field.name)
+            .and_then(|(_, value)| value.as_deref())
+            .map_or(JsonValue::Null, |value| {
+                typed_value(value, &field.dtype, &options)
+            });
+        map.insert(field.name.clone(), value);
+    }
+    map
+}
+
+fn collect_dir(
+    dir: &Path,
+    partitions: &mut Vec<(String, Option<String>)>,
+    filters: &[PartitionFilter],
+    keys: &mut Vec<String>,
+    files: &mut Vec<DataFile>,
+) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("read directory {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.context("read directory entry")?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name.starts_with('_') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            let partition = parse_partition(&name);
+            if let Some((key, value)) = &partition {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+                if !accepts(filters, key, value.as_deref()) {
+                    continue;
+                }
+            }
+            let pushed = partition.is_some();
+            partitions.extend(partition);
+            collect_dir(&path, partitions, filters, keys, files)?;
+            if pushed {
+                partitions.pop();
+            }
+        } else if path.is_file() {
+            files.push(DataFile {
+                path,
+                partitions: partitions.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn expand_glob(
+    path: &Path,
+    filters: &[PartitionFilter],
+    keys: &mut Vec<String>,
+) -> Result<Vec<DataFile>> {
+    let pattern = path.to_str().context("glob pattern is not valid UTF-8")?;
+    let root: PathBuf = path
+        .components()
+        .take_while(|component| !is_glob(Path::new(component.as_os_str())))
+        .collect();
+    let mut files = Vec::new();
+    for entry in glob::glob(pattern).context("parse glob pattern")? {
+        let entry = entry.context("read glob match")?;
+        if !entry.is_file() {
+            continue;
+        }
+        let relative = entry.strip_prefix(&root).unwrap_or(&entry);
+        let mut partitions = Vec::new();
+        if let Some(parent) = relative.parent() {
+            for component in parent.components() {
+                if let Component::Normal(name) = component
+                    && let Some(partition) = parse_partition(&name.to_string_lossy())
+                {
+                    if !keys.contains(&partition.0) {
+                        keys.push(partition.0.clone());
+                    }
+                    partitions.push(partition);
+                }
+            }
+        }
+        if partitions
+            .iter()
+            .all(|(key, value)| accepts(filters, key, value.as_deref()))
+        {
+            files.push(DataFile {
+                path: entry,
+                partitions,
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    if files.is_empty() && keys.is_empty() {
+        bail!("no files match {pattern}");
+    }
+    Ok(files)
+}
+
+fn accepts(filters: &[PartitionFilter], key: &str, value: Option<&str>) -> bool {
+    filters
+        .iter()
+        .filter(|filter| filter.column == key)
+        .all(|filter| filter.matches(value))
+}
+
+fn parse_partition(name: &str) -> Option<(String, Option<String>)> {
+    let (key, value) = name.split_once('=')?;
+    if key.is_empty() {
+        return None;
+    }
+    let value = percent_decode(value);
+    let value = (value != HIVE_DEFAULT_PARTITION).then_some(value);
+    Some((percent_decode(key), value))
+}
+
+// Hive escapes characters such as `/`, `:` and `=` in partition values as `%XX`.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%'
+            && let Some(byte) = text
+                .get(idx + 1..idx + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            idx += 3;
+            continue;
+        }
+        out.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy()
+        .chars()
+        .any(|c| matches!(c, '*' | '?' | '['))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hive_partition_segments() {
+        assert_eq!(
+            parse_partition("year=2024"),
+            Some(("year".to_string(), Some("2024".to_string())))
+        );
+        assert_eq!(
+            parse_partition("ts=2024-03-01%2012%3A00"),
+            Some(("ts".to_string(), Some("2024-03-01 12:00".to_string())))
+        );
+        assert_eq!(
+            parse_partition("region=__HIVE_DEFAULT_PARTITION__"),
+            Some(("region".to_string(), None))
+        );
+        assert_eq!(parse_partition("part-0.parquet"), None);
+    }
+
+    #[test]
+    fn filter_compares_numbers() {
+        let filter: PartitionFilter = "month=3".parse().unwrap();
+        assert!(filter.matches(Some("03")));
+        assert!(!filter.matches(Some("04")));
+        assert!(!filter.matches(None));
+    }
+}
@@ -0,0 +1,754 @@
+mod files;
+
+pub use files::{DataFile, PartitionFilter, partition_fields, partition_values, resolve_inputs};
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use serde_json::{Map, Value};
+
+use crate::batch;
+use crate::formats::csv::CsvOptions;
+use crate::formats::delta::DeltaOptions;
+use crate::formats::detect::{
+    Mismatch, MismatchPolicy, detect_format_checked, detect_format_prefix, detect_table_format,
+};
+use crate::formats::json::{self, JsonLayout, JsonOptions};
+use crate::formats::protobuf::ProtobufOptions;
+use crate::formats::spreadsheet::SpreadsheetOptions;
+use crate::formats::{self, Format, FormatOptions};
+use crate::model::schema::{DataType, Field, Schema};
+use crate::model::table::TableInfo;
+use crate::source::{Record, RecordSource};
+use crate::util::encoding::TextEncoding;
+use crate::util::errors::UnsupportedFormatError;
+use crate::util::io;
+use crate::util::sample::SampleOptions;
+use crate::util::window::RowWindow;
+
+/// How much of a one-pass reader is buffered to detect its format.
+const READER_PREFIX_LIMIT: usize = 64 * 1024;
+
+/// Opens a file, directory or glob pattern with the default options.
+pub fn open(path: impl AsRef<Path>) -> Result<Dataset> {
+    OpenOptions::new().open(path)
+}
+
+/// Options for opening a [`Dataset`], set builder-style:
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let dataset = megrez::OpenOptions::new()
+///     .limit(10)
+///     .columns(["id", "name"])
+///     .open("events/")?;
+/// for record in dataset {
+///     println!("{}", serde_json::Value::Object(record?));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpenOptions {
+    format: Option<Format>,
+    offset: usize,
+    limit: Option<usize>,
+    columns: Option<Vec<String>>,
+    formats: FormatOptions,
+    filters: Vec<PartitionFilter>,
+    mismatch_policy: MismatchPolicy,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads every file as `format` instead of detecting it.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Skips this many leading records of the whole dataset.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Stops after this many records of the whole dataset.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Keeps only these top-level columns, in this order.
+    pub fn columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the encoding of CSV and JSON input, which is detected by default.
+    pub fn encoding(mut self, encoding: TextEncoding) -> Self {
+        self.formats.encoding = encoding;
+        self
+    }
+
+    /// Controls how many records text formats read to infer their schema.
+    pub fn sample(mut self, sample: SampleOptions) -> Self {
+        self.formats.sample = sample;
+        self
+    }
+
+    pub fn csv(mut self, csv: CsvOptions) -> Self {
+        self.formats.csv = csv;
+        self
+    }
+
+    pub fn json(mut self, json: JsonOptions) -> Self {
+        self.formats.json = json;
+        self
+    }
+
+    pub fn spreadsheet(mut self, spreadsheet: SpreadsheetOptions) -> Self {
+        self.formats.spreadsheet = spreadsheet;
+        self
+    }
+
+    /// Sets the descriptor and message type used to decode protobuf input. Protobuf has no
+    /// magic bytes to detect, so naming a message type also selects the protobuf format unless
+    /// one is already set.
+    pub fn protobuf(mut self, protobuf: ProtobufOptions) -> Self {
+        if protobuf.message.is_some() {
+            self.format.get_or_insert(Format::Protobuf);
+        }
+        self.formats.protobuf = protobuf;
+        self
+    }
+
+    /// Selects the Delta table version to read (time travel); the latest by default.
+    pub fn delta(mut self, delta: DeltaOptions) -> Self {
+        self.formats.delta = delta;
+        self
+    }
+
+    /// Adds a partition filter, applied when opening a directory or glob.
+    pub fn filter(mut self, filter: PartitionFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Checks each detected file's extension against its content; see [`Dataset::mismatches`].
+    /// Files read with an explicit [`format`](Self::format), tables and stdin are not checked.
+    pub fn mismatch_policy(mut self, policy: MismatchPolicy) -> Self {
+        self.mismatch_policy = policy;
+        self
+    }
+
+    /// Opens a file, a directory of files, a glob pattern or a table (Delta Lake or Iceberg).
+    ///
+    /// Every file is inferred up front so the dataset's schema is known before any record is
+    /// read; files are then opened one at a time while iterating. A table's schema and data
+    /// files come from its metadata instead.
+    pub fn open(&self, path: impl AsRef<Path>) -> Result<Dataset> {
+        let path = path.as_ref();
+        let table = match self.format {
+            Some(format) => Some(format).filter(Format::is_table),
+            None => detect_table_format(path),
+        };
+        if let Some(format) = table {
+            if !self.filters.is_empty() {
+                bail!(UnsupportedFormatError::new(format!(
+                    "partition filters are not supported for {} tables",
+                    format.as_str()
+                )));
+            }
+            let source = formats::open_table(path, format, &self.formats)?;
+            return self.open_source(source);
+        }
+
+        let files = resolve_inputs(path, &self.filters)?;
+        let mut mismatches = Vec::new();
+        let mut file_formats = Vec::with_capacity(files.len());
+        for file in &files {
+            let file_format = match self.format {
+                Some(format) => format,
+                None => {
+                    let (format, mismatch) =
+                        detect_format_checked(&file.path, self.mismatch_policy)?;
+                    mismatches.extend(mismatch);
+                    format
+                }
+            };
+            file_formats.push(file_format);
+        }
+        let format = file_formats[0];
+        let mut schemas = Vec::with_capacity(files.len());
+        for (file, file_format) in files.iter().zip(file_formats) {
+            if file_format != format {
+                return Err(UnsupportedFormatError::new(format!(
+                    "dataset mixes formats: {} is {} but {} is {}",
+                    files[0].path.display(),
+                    format.as_str(),
+                    file.path.display(),
+                    file_format.as_str()
+                ))
+                .into());
+            }
+            let schema = formats::infer_schema(&file.path, format, &self.formats);
+            // A mislabeled file usually fails here; the mismatch explains why.
+            let schema = match mismatches
+                .iter()
+                .find(|mismatch| mismatch.path == file.path)
+            {
+                Some(mismatch) => schema.with_context(|| mismatch.to_string())?,
+                None => schema?,
+            };
+            schemas.push(schema);
+        }
+
+        let mut schema = schemas[0].clone();
+        for other in &schemas[1..] {
+            schema = schema.merge(other);
+        }
+        // A partition value wins over a file column of the same name, as in Hive and Spark:
+        // `next_record` overwrites the column, so the schema takes the partition's type.
+        let partitions = partition_fields(&files);
+        for field in &partitions {
+            match schema
+                .fields
+                .iter_mut()
+                .find(|column| column.name == field.name)
+            {
+                Some(column) => *column = field.clone(),
+                None => schema.fields.push(field.clone()),
+            }
+        }
+        let conflicts = find_conflicts(&files, &schemas);
+        // Projected and partitioned records differ from the files' own batches.
+        let arrow_schema = if self.columns.is_none() && partitions.is_empty() {
+            shared_arrow_schema(&files, &schemas, format, &self.formats)?
+        } else {
+            None
+        };
+        let json_layout = match format {
+            Format::Json => {
+                let file = formats::open_text(&files[0].path, self.formats.encoding)?;
+                Some(json::detect_layout(file, &self.formats.json)?)
+            }
+            _ => None,
+        };
+
+        Ok(Dataset {
+            format,
+            json_layout,
+            table_info: None,
+            schema: project_schema(schema, self.columns.as_deref())?,
+            arrow_schema,
+            conflicts,
+            mismatches,
+            pending: files.into_iter().zip(schemas).collect(),
+            partitions,
+            current: None,
+            window: RowWindow::new(self.offset, self.limit),
+            columns: self.columns.clone(),
+            options: self.formats.clone(),
+        })
+    }
+
+    /// Opens input that can only be read once, such as stdin.
+    ///
+    /// Parquet, Avro and ORC cannot be read this way. Without a format override the format is
+    /// detected from the first 64 KiB, and the schema is inferred from the first `sample.rows`
+    /// records, which are buffered.
+    pub fn open_reader<R: Read + 'static>(&self, mut reader: R) -> Result<Dataset> {
+        let source = match self.format {
+            Some(format) => formats::open_reader(reader, format, &self.formats)?,
+            None => {
+                let prefix = io::read_prefix(&mut reader, READER_PREFIX_LIMIT)?;
+                let format = detect_format_prefix(&prefix)?;
+                let reader = std::io::Cursor::new(prefix).chain(reader);
+                formats::open_reader(reader, format, &self.formats)?
+            }
+        };
+        self.open_source(source)
+    }
+
+    // A dataset over a single source that is already open.
+    fn open_source(&self, source: Box<dyn RecordSource>) -> Result<Dataset> {
+        Ok(Dataset {
+            format: source.format(),
+            json_layout: source.json_layout(),
+            table_info: source.table_info(),
+            schema: project_schema(source.schema().clone(), self.columns.as_deref())?,
+            arrow_schema: self.columns.is_none().then(|| source.arrow_schema()),
+            conflicts: Vec::new(),
+            mismatches: Vec::new(),
+            pending: VecDeque::new(),
+            partitions: Vec::new(),
+            current: Some((source, Map::new())),
+            window: RowWindow::new(self.offset, self.limit),
+            columns: self.columns.clone(),
+            options: self.formats.clone(),
+        })
+    }
+}
+
+/// A field whose non-null type differs between the files of a dataset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldConflict {
+    pub field: String,
+    /// Each type seen, with the files that have it.
+    pub types: Vec<(DataType, Vec<PathBuf>)>,
+}
+
+/// The records of one or more files in the same format, read as a single source.
+///
+/// Partition columns parsed from the files' paths are added to every record, replacing a file
+/// column of the same name, and the offset and limit apply to the dataset as a whole.
+pub struct Dataset {
+    format: Format,
+    json_layout: Option<JsonLayout>,
+    table_info: Option<TableInfo>,
+    schema: Schema,
+    // Set when every file's batches share this Arrow schema, so they are returned unchanged
+    // rather than rebuilt from records.
+    arrow_schema: Option<SchemaRef>,
+    conflicts: Vec<FieldConflict>,
+    mismatches: Vec<Mismatch>,
+    pending: VecDeque<(DataFile, Schema)>,
+    partitions: Vec<Field>,
+    current: Option<(Box<dyn RecordSource>, Map<String, Value>)>,
+    window: RowWindow,
+    columns: Option<Vec<String>>,
+    options: FormatOptions,
+}
+
+impl Dataset {
+    /// Fields whose types differ between files; the merged schema widens them.
+    pub fn conflicts(&self) -> &[FieldConflict] {
+        &self.conflicts
+    }
+
+    /// Files whose extension names a different format than their content, when opened with
+    /// [`MismatchPolicy::Warn`].
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+
+    // Opens the next file when needed and skips what is left of the offset. Returns false at
+    // the end of the dataset or once the limit is reached.
+    fn advance(&mut self) -> Result<bool> {
+        loop {
+            if self.window.is_done() {
+                return Ok(false);
+            }
+            if self.current.is_none() {
+                let Some((file, schema)) = self.pending.pop_front() else {
+                    return Ok(false);
+                };
+                let source =
+                    formats::open_with_schema(&file.path, self.format, schema, &self.options)?;
+                let extra = partition_values(&file, &self.partitions);
+                self.current = Some((source, extra));
+            }
+            let Some((source, _)) = self.current.as_mut() else {
+                continue;
+            };
+            if self.window.offset() > 0 {
+                let skipped = source.skip_records(self.window.offset())?;
+                self.window.skip(skipped);
+                if self.window.offset() > 0 {
+                    self.current = None;
+                    continue;
+                }
+            }
+            return Ok(true);
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        while self.advance()? {
+            let Some((source, extra)) = self.current.as_mut() else {
+                break;
+            };
+            let Some(record) = source.next() else {
+                self.current = None;
+                continue;
+            };
+            let mut record = record?;
+            self.window.admit();
+            for (key, value) in extra.iter() {
+                record.insert(key.clone(), value.clone());
+            }
+            return Ok(Some(project_record(record, self.columns.as_deref())));
+        }
+        Ok(None)
+    }
+
+    // As `next_record`, but values that are not objects are returned as read. Partition
+    // columns and projections need an object, so they are applied to records instead.
+    fn next_file_value(&mut self) -> Result<Option<Value>> {
+        if self.columns.is_some() || !self.partitions.is_empty() {
+            return Ok(self.next_record()?.map(Value::Object));
+        }
+        while self.advance()? {
+            let Some((source, _)) = self.current.as_mut() else {
+                break;
+            };
+            let Some(value) = source.next_value() else {
+                self.current = None;
+                continue;
+            };
+            let value = value?;
+            self.window.admit();
+            return Ok(Some(value));
+        }
+        Ok(None)
+    }
+
+    // Returns the current file's own batches, cut to the limit.
+    fn next_file_batch(&mut self, max_rows: usize) -> Result<Option<RecordBatch>> {
+        while self.advance()? {
+            let Some((source, _)) = self.current.as_mut() else {
+                break;
+            };
+            let max_rows = self
+                .window
+                .remaining()
+                .map_or(max_rows, |remaining| remaining.min(max_rows));
+            let Some(batch) = source.next_batch(max_rows)? else {
+                self.current = None;
+                continue;
+            };
+            self.window.admit_rows(batch.num_rows());
+            return Ok(Some(batch));
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for Dataset {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.next_record();
+        if record.is_err() {
+            self.pending.clear();
+            self.current = None;
+        }
+        record.transpose()
+    }
+}
+
+impl RecordSource for Dataset {
+    fn format(&self) -> Format {
+        self.format
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn json_layout(&self) -> Option<JsonLayout> {
+        self.json_layout
+    }
+
+    fn table_info(&self) -> Option<TableInfo> {
+        self.table_info.clone()
+    }
+
+    fn next_value(&mut self) -> Option<Result<Value>> {
+        let value = self.next_file_value();
+        if value.is_err() {
+            self.pending.clear();
+            self.current = None;
+        }
+        value.transpose()
+    }
+
+    fn arrow_schema(&self) -> SchemaRef {
+        match &self.arrow_schema {
+            Some(schema) => schema.clone(),
+            None => Arc::new(batch::arrow_schema(&self.schema)),
+        }
+    }
+
+    // Files that share an Arrow schema return their own batches (Parquet's are decoded
+    // natively); other datasets decode their records against the merged schema.
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RecordBatch>> {
+        if self.arrow_schema.is_none() {
+            return batch::read_batch(self, max_rows);
+        }
+        let batch = self.next_file_batch(max_rows);
+        if batch.is_err() {
+            self.pending.clear();
+            self.current = None;
+        }
+        batch
+    }
+}
+
+// The Arrow schema every file's batches share, if they all have the same one.
+fn shared_arrow_schema(
+    files: &[DataFile],
+    schemas: &[Schema],
+    format: Format,
+    options: &FormatOptions,
+) -> Result<Option<SchemaRef>> {
+    let mut shared: Option<SchemaRef> = None;
+    for (file, schema) in files.iter().zip(schemas) {
+        let arrow_schema = formats::arrow_schema(&file.path, format, schema, options)?;
+        match &shared {
+            Some(first) if *first != arrow_schema => return Ok(None),
+            Some(_) => {}
+            None => shared = Some(arrow_schema),
+        }
+    }
+    Ok(shared)
+}
+
+// Groups each field's non-null types across files; fields with a single type are dropped.
+fn find_conflicts(files: &[DataFile], schemas: &[Schema]) -> Vec<FieldConflict> {
+    let mut by_field: BTreeMap<&str, BTreeMap<String, (DataType, Vec<PathBuf>)>> = BTreeMap::new();
+    for (file, schema) in files.iter().zip(schemas) {
+        for field in &schema.fields {
+            if field.dtype == DataType::Null {
+                continue;
+            }
+            by_field
+                .entry(field.name.as_str())
+                .or_default()
+                .entry(field.dtype.to_string())
+                .or_insert_with(|| (field.dtype.clone(), Vec::new()))
+                .1
+                .push(file.path.clone());
+        }
+    }
+    by_field
+        .into_iter()
+        .filter(|(_, types)| types.len() > 1)
+        .map(|(name, types)| FieldConflict {
+            field: name.to_string(),
+            types: types.into_values().collect(),
+        })
+        .collect()
+}
+
+// Keeps the requested columns; flattened JSON fields such as `user.id` belong to `user`.
+pub(crate) fn project_schema(schema: Schema, columns: Option<&[String]>) -> Result<Schema> {
+    let Some(columns) = columns else {
+        return Ok(schema);
+    };
+    let mut fields = Vec::new();
+    for column in columns {
+        let nested = format!("{column}.");
+        let before = fields.len();
+        fields.extend(
+            schema
+                .fields
+                .iter()
+                .filter(|field| &field.name == column || field.name.starts_with(&nested))
+                .cloned(),
+        );
+        if fields.len() == before {
+            bail!("unknown column `{column}`");
+        }
+        // Each element of a list has its own value, so one cannot stand for the row.
+        let list = column
+            .match_indices('.')
+            .map(|(idx, _)| &column[..idx])
+            .find(|parent| {
+                schema
+                    .fields
+                    .iter()
+                    .any(|field| &field.name == parent && matches!(field.dtype, DataType::List(_)))
+            });
+        if let Some(list) = list {
+            bail!("column `{column}` is inside the list `{list}`; select `{list}` instead");
+        }
+    }
+    Ok(Schema { fields })
+}
+
+// A flattened column such as `user.id` is read from, and written back into, its object.
+pub(crate) fn project_record(record: Record, columns: Option<&[String]>) -> Record {
+    let Some(columns) = columns else {
+        return record;
+    };
+    let mut projected = Record::new();
+    for column in columns {
+        match record.get(column) {
+            Some(value) => {
+                projected.insert(column.clone(), value.clone());
+            }
+            None => {
+                let value = nested_value(&record, column).cloned();
+                insert_nested(&mut projected, column, value.unwrap_or(Value::Null));
+            }
+        }
+    }
+    projected
+}
+
+fn nested_value<'a>(record: &'a Record, path: &str) -> Option<&'a Value> {
+    if let Some(value) = record.get(path) {
+        return Some(value);
+    }
+    let (head, rest) = path.split_once('.')?;
+    match record.get(head)? {
+        Value::Object(object) => nested_value(object, rest),
+        _ => None,
+    }
+}
+
+fn insert_nested(record: &mut Record, path: &str, value: Value) {
+    let Some((head, rest)) = path.split_once('.') else {
+        record.insert(path.to_string(), value);
+        return;
+    };
+    let parent = record
+        .entry(head)
+        .or_insert_with(|| Value::Object(Record::new()));
+    if !parent.is_object() {
+        *parent = Value::Object(Record::new());
+    }
+    if let Value::Object(object) = parent {
+        insert_nested(object, rest, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_applies_window_and_projection() {
+        let input = "id,name,score\n1,a,0.5\n2,b,1.5\n3,c,2.5\n";
+        let dataset = OpenOptions::new()
+            .offset(1)
+            .limit(1)
+            .columns(["score", "id"])
+            .open_reader(input.as_bytes())
+            .expect("open reader");
+        assert_eq!(dataset.format(), Format::Csv);
+        let names: Vec<&str> = dataset
+            .schema()
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["score", "id"]);
+
+        let records: Vec<Value> = dataset
+            .map(|record| Value::Object(record.expect("read record")))
+            .collect();
+        assert_eq!(records, vec![serde_json::json!({"score": 1.5, "id": 2})]);
+    }
+
+    fn write_parquet(path: &Path, ids: Vec<i64>) {
+        use arrow::array::{Array, ArrayRef, Int64Array, StructArray, TimestampMicrosecondArray};
+        use arrow::datatypes::{DataType as ArrowType, Field as ArrowField, TimeUnit};
+
+        let len = ids.len();
+        let meta = StructArray::from(vec![(
+            Arc::new(ArrowField::new("score", ArrowType::Int64, true)),
+            Arc::new(Int64Array::from(ids.clone())) as ArrayRef,
+        )]);
+        let schema = Arc::new(arrow::datatypes::Schema::new(vec![
+            ArrowField::new("id", ArrowType::Int64, false),
+            ArrowField::new(
+                "at",
+                ArrowType::Timestamp(TimeUnit::Microsecond, None),
+                true,
+            ),
+            ArrowField::new("meta", meta.data_type().clone(), true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(ids)),
+            Arc::new(TimestampMicrosecondArray::from(vec![0; len])),
+            Arc::new(meta),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn projection_reads_nested_columns_from_their_objects() {
+        let input = r#"[{"id": 1, "user": {"id": "u1", "active": true}, "items": [{"n": 1}]}]"#;
+        let options = OpenOptions::new().columns(["user.id", "id"]);
+        let dataset = options.open_reader(input.as_bytes()).expect("open reader");
+        let records: Vec<Value> = dataset
+            .map(|record| Value::Object(record.expect("read record")))
+            .collect();
+        assert_eq!(
+            records,
+            vec![serde_json::json!({"user": {"id": "u1"}, "id": 1})]
+        );
+
+        let Err(err) = OpenOptions::new()
+            .columns(["items.n"])
+            .open_reader(input.as_bytes())
+        else {
+            panic!("a field inside a list was selected");
+        };
+        assert_eq!(
+            err.to_string(),
+            "column `items.n` is inside the list `items`; select `items` instead"
+        );
+    }
+    #[test]
+    fn parquet_batches_keep_their_arrow_types() {
+        let dir = std::env::temp_dir().join(format!("megrez_dataset_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_parquet(&dir.join("part-0.parquet"), vec![1, 2]);
+        write_parquet(&dir.join("part-1.parquet"), vec![3, 4]);
+        let file_schema = formats::parquet::ParquetSource::open(&dir.join("part-0.parquet"))
+            .unwrap()
+            .arrow_schema();
+
+        for dataset in [crate::open(dir.join("part-0.parquet")), crate::open(&dir)] {
+            let dataset = dataset.unwrap();
+            assert_eq!(dataset.arrow_schema(), file_schema);
+            assert!(!file_schema.field_with_name("id").unwrap().is_nullable());
+            let batches: Vec<RecordBatch> =
+                dataset.into_batches(1024).collect::<Result<_>>().unwrap();
+            assert!(batches.iter().all(|batch| batch.schema() == file_schema));
+        }
+
+        // The limit cuts the batches, across files.
+        let dataset = OpenOptions::new().limit(3).open(&dir).unwrap();
+        let rows: Vec<usize> = dataset
+            .into_batches(1024)
+            .map(|batch| batch.unwrap().num_rows())
+            .collect();
+        assert_eq!(rows, vec![2, 1]);
+
+        // Projected records no longer match the files, so they are decoded from records.
+        let dataset = OpenOptions::new().columns(["id"]).open(&dir).unwrap();
+        assert_ne!(dataset.arrow_schema(), file_schema);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_column_is_an_error() {
+        let err = OpenOptions::new()
+            .columns(["missing"])
+            .open_reader(&b"{\"a\":1}\n"[..])
+            .err()
+            .expect("projection fails");
+        assert_eq!(err.to_string(), "unknown column `missing`");
+    }
+}
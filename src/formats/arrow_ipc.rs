@@ -0,0 +1,209 @@
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::record_batch::RecordBatch;
+
+use crate::formats::Format;
+use crate::formats::parquet::{batch_row_to_json, schema_from_arrow};
+use crate::model::schema::Schema;
+use crate::source::{Record, RecordSource};
+
+/// Leading (and trailing) magic of the Arrow IPC file format, also used by Feather v2.
+pub const MAGIC_FILE: &[u8; 6] = b"ARROW1";
+/// Continuation marker that starts every message of the Arrow IPC stream format.
+pub const MAGIC_STREAM: &[u8; 4] = b"\xff\xff\xff\xff";
+
+type BatchIter = Box<dyn Iterator<Item = Result<RecordBatch, ArrowError>>>;
+
+pub fn infer_schema(path: &Path) -> Result<Schema> {
+    Ok(ArrowIpcSource::open(path)?.schema)
+}
+
+/// Reads Arrow IPC files (including Feather v2) and IPC streams.
+pub struct ArrowIpcSource {
+    batches: BatchIter,
+    batch: Option<RecordBatch>,
+    row: usize,
+    schema: Schema,
+    arrow_schema: SchemaRef,
+}
+
+impl ArrowIpcSource {
+    /// Opens an IPC file, or an IPC stream saved to disk.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path).context("open Arrow IPC file")?;
+        let mut magic = [0u8; 6];
+        let read = file.read(&mut magic).context("read Arrow IPC magic")?;
+        let file = File::open(path).context("open Arrow IPC file")?;
+        if read == magic.len() && &magic == MAGIC_FILE {
+            let reader = FileReader::try_new_buffered(file, None).context("read Arrow IPC file")?;
+            Ok(Self::new(reader.schema(), Box::new(reader)))
+        } else {
+            Self::from_stream(file)
+        }
+    }
+
+    /// Reads input that can only be read once. Streams are decoded as they arrive; the file
+    /// format keeps its index in a footer, so it is buffered in memory first.
+    pub fn from_reader<R: Read + 'static>(mut reader: R) -> Result<Self> {
+        let mut magic = Vec::with_capacity(MAGIC_FILE.len());
+        (&mut reader)
+            .take(MAGIC_FILE.len() as u64)
+            .read_to_end(&mut magic)
+            .context("read Arrow IPC magic")?;
+        if magic == MAGIC_FILE {
+            let mut bytes = magic;
+            reader
+                .read_to_end(&mut bytes)
+                .context("read Arrow IPC file")?;
+            let reader =
+                FileReader::try_new(Cursor::new(bytes), None).context("read Arrow IPC file")?;
+            Ok(Self::new(reader.schema(), Box::new(reader)))
+        } else {
+            Self::from_stream(Cursor::new(magic).chain(reader))
+        }
+    }
+
+    fn from_stream<R: Read + 'static>(reader: R) -> Result<Self> {
+        let reader =
+            StreamReader::try_new_buffered(reader, None).context("read Arrow IPC stream")?;
+        Ok(Self::new(reader.schema(), Box::new(reader)))
+    }
+
+    fn new(arrow_schema: SchemaRef, batches: BatchIter) -> Self {
+        Self {
+            batches,
+            batch: None,
+            row: 0,
+            schema: schema_from_arrow(&arrow_schema),
+            arrow_schema,
+        }
+    }
+
+    // Loads the next batch, returning false at the end of the input.
+    fn read_batch(&mut self) -> Result<bool> {
+        match self.batches.next() {
+            Some(batch) => {
+                self.batch = Some(batch.context("read Arrow IPC record batch")?);
+                self.row = 0;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        loop {
+            if let Some(batch) = &self.batch
+                && self.row < batch.num_rows()
+            {
+                let record = batch_row_to_json(batch, self.row);
+                self.row += 1;
+                return Ok(Some(record));
+            }
+            if !self.read_batch()? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+impl Iterator for ArrowIpcSource {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+impl RecordSource for ArrowIpcSource {
+    fn format(&self) -> Format {
+        Format::ArrowIpc
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn arrow_schema(&self) -> SchemaRef {
+        self.arrow_schema.clone()
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RecordBatch>> {
+        loop {
+            if let Some(batch) = &self.batch
+                && self.row < batch.num_rows()
+            {
+                let len = max_rows.max(1).min(batch.num_rows() - self.row);
+                let slice = batch.slice(self.row, len);
+                self.row += len;
+                return Ok(Some(slice));
+            }
+            if !self.read_batch()? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType as ArrowType, Field as ArrowField, Schema as ArrowSchema};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+    use std::sync::Arc;
+
+    fn sample_batch() -> RecordBatch {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", ArrowType::Int64, false),
+            ArrowField::new("name", ArrowType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn records(source: ArrowIpcSource) -> Vec<String> {
+        source
+            .map(|record| serde_json::Value::Object(record.unwrap()).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reads_file_and_stream_formats() {
+        let batch = sample_batch();
+        let mut file_bytes = Vec::new();
+        let mut writer = FileWriter::try_new(&mut file_bytes, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        let mut stream_bytes = Vec::new();
+        let mut writer = StreamWriter::try_new(&mut stream_bytes, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        assert!(file_bytes.starts_with(MAGIC_FILE));
+        assert!(stream_bytes.starts_with(MAGIC_STREAM));
+        let expected = vec![
+            "{\"id\":1,\"name\":\"a\"}".to_string(),
+            "{\"id\":2,\"name\":null}".to_string(),
+        ];
+        for bytes in [file_bytes, stream_bytes] {
+            let source = ArrowIpcSource::from_reader(Cursor::new(bytes)).unwrap();
+            assert_eq!(source.schema().fields[0].name, "id");
+            assert_eq!(records(source), expected);
+        }
+    }
+}
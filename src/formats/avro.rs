@@ -6,14 +6,18 @@ use apache_avro::Schema as AvroSchema;
 use apache_avro::types::Value;
 use serde_json::Value as JsonValue;
 
+use crate::formats::Format;
 use crate::model::schema::{DataType, Field, Schema};
-use crate::render::jsonl;
+use crate::source::{Record, RecordSource, into_record};
+use crate::util::hex;
 
 pub fn infer_schema(path: &Path) -> Result<Schema> {
     let file = File::open(path).context("open Avro file")?;
     let reader = apache_avro::Reader::new(file).context("read Avro container")?;
-    let schema = reader.writer_schema().clone();
+    Ok(schema_from_avro(reader.writer_schema()))
+}
 
+fn schema_from_avro(schema: &AvroSchema) -> Schema {
     let fields = match schema {
         AvroSchema::Record(record) => record
             .fields
@@ -28,7 +32,7 @@ pub fn infer_schema(path: &Path) -> Result<Schema> {
             })
             .collect(),
         other => {
-            let (dtype, nullable) = schema_to_dtype(&other);
+            let (dtype, nullable) = schema_to_dtype(other);
             vec![Field {
                 name: "value".to_string(),
                 dtype,
@@ -36,29 +40,41 @@ pub fn infer_schema(path: &Path) -> Result<Schema> {
             }]
         }
     };
+    Schema { fields }
+}
 
-    Ok(Schema { fields })
+/// Reads the records of an Avro object container file.
+pub struct AvroSource {
+    reader: apache_avro::Reader<'static, File>,
+    schema: Schema,
 }
 
-pub fn cat(path: &Path, limit: Option<usize>) -> Result<()> {
-    let file = File::open(path).context("open Avro file")?;
-    let reader = apache_avro::Reader::new(file).context("read Avro container")?;
-    let mut out = std::io::stdout();
-    let mut count = 0usize;
+impl AvroSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).context("open Avro file")?;
+        let reader = apache_avro::Reader::new(file).context("read Avro container")?;
+        let schema = schema_from_avro(reader.writer_schema());
+        Ok(Self { reader, schema })
+    }
+}
 
-    for record in reader {
-        let value = record.context("read Avro record")?;
-        let json = avro_value_to_json(&value);
-        jsonl::write_line(&mut out, &json)?;
-        count += 1;
-        if let Some(limit) = limit
-            && count >= limit
-        {
-            break;
-        }
+impl Iterator for AvroSource {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.reader.next()?.context("read Avro record");
+        Some(value.map(|value| into_record(avro_value_to_json(&value))))
+    }
+}
+
+impl RecordSource for AvroSource {
+    fn format(&self) -> Format {
+        Format::Avro
     }
 
-    Ok(())
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
 }
 
 fn schema_to_dtype(schema: &AvroSchema) -> (DataType, bool) {
@@ -109,8 +125,8 @@ fn avro_value_to_json(value: &Value) -> JsonValue {
         Value::Long(v) => JsonValue::from(*v),
         Value::Float(v) => JsonValue::from(*v),
         Value::Double(v) => JsonValue::from(*v),
-        Value::Bytes(bytes) => JsonValue::String(bytes_to_hex(bytes)),
-        Value::Fixed(_, bytes) => JsonValue::String(bytes_to_hex(bytes)),
+        Value::Bytes(bytes) => JsonValue::String(hex::encode(bytes)),
+        Value::Fixed(_, bytes) => JsonValue::String(hex::encode(bytes)),
         Value::String(v) => JsonValue::String(v.clone()),
         Value::Enum(_, v) => JsonValue::String(v.clone()),
         Value::Uuid(v) => JsonValue::String(v.to_string()),
@@ -126,6 +142,7 @@ fn avro_value_to_json(value: &Value) -> JsonValue {
         Value::Array(items) => JsonValue::Array(items.iter().map(avro_value_to_json).collect()),
         Value::Map(map) => {
             let mut entries: Vec<_> = map.iter().collect();
+            #[allow(clippy::unnecessary_sort_by)]
             entries.sort_by(|(a, _), (b, _)| a.cmp(b));
             let mut out = serde_json::Map::new();
             for (key, val) in entries {
@@ -143,11 +160,3 @@ fn avro_value_to_json(value: &Value) -> JsonValue {
         Value::Union(_, boxed) => avro_value_to_json(boxed.as_ref()),
     }
 }
-
-fn bytes_to_hex(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        out.push_str(&format!("{:02x}", byte));
-    }
-    out
-}
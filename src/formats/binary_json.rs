@@ -0,0 +1,348 @@
+//! MessagePack and CBOR record streams: concatenated values, each read as one record.
+//!
+//! Values are decoded into JSON values, so schema inference and output are shared with JSON.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Number, Value};
+
+use crate::formats::Format;
+use crate::formats::json;
+use crate::model::schema::Schema;
+use crate::source::{Record, RecordSource, into_record};
+use crate::util::hex;
+use crate::util::sample::SampleOptions;
+
+/// How much input detection decodes. A first record that does not end within this many bytes
+/// is accepted on its leading byte alone.
+pub const SNIFF_LEN: usize = 64 * 1024;
+
+/// CBOR's self-describe tag (55799), which may prefix a CBOR stream.
+const CBOR_SELF_DESCRIBE: &[u8; 3] = b"\xd9\xd9\xf7";
+
+/// The binary encoding of a record stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    MessagePack,
+    Cbor,
+}
+
+impl Encoding {
+    pub fn format(self) -> Format {
+        match self {
+            Encoding::MessagePack => Format::MessagePack,
+            Encoding::Cbor => Format::Cbor,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::MessagePack => "MessagePack",
+            Encoding::Cbor => "CBOR",
+        }
+    }
+}
+
+/// Detects a MessagePack or CBOR record stream from the start of the input.
+///
+/// Records are maps, so the first byte must start a map: 0x80-0x8f, 0xde or 0xdf for
+/// MessagePack, 0xa0-0xbb or 0xbf for CBOR. Because text can begin with those bytes too (a
+/// windows-1252 `€` is an empty MessagePack map), the first value must also decode to a
+/// non-empty map, and be followed by a second map unless it fills the input. A value that
+/// runs past a full `SNIFF_LEN` prefix counts as decoding.
+pub fn detect_prefix(prefix: &[u8]) -> Option<Encoding> {
+    if prefix.starts_with(CBOR_SELF_DESCRIBE) {
+        return Some(Encoding::Cbor);
+    }
+    let encoding = match prefix.first()? {
+        0x80..=0x8f | 0xde | 0xdf => Encoding::MessagePack,
+        0xa0..=0xbb | 0xbf => Encoding::Cbor,
+        _ => return None,
+    };
+    let mut reader = prefix;
+    match read_value(encoding, &mut reader) {
+        Ok(Value::Object(record)) if !record.is_empty() => {}
+        Err(DecodeError::Eof) if prefix.len() >= SNIFF_LEN => return Some(encoding),
+        _ => return None,
+    }
+    if reader.is_empty() {
+        return Some(encoding);
+    }
+    match read_value(encoding, &mut reader) {
+        Ok(Value::Object(_)) => Some(encoding),
+        Err(DecodeError::Eof) if prefix.len() >= SNIFF_LEN => Some(encoding),
+        _ => None,
+    }
+}
+
+pub fn infer_schema(path: &Path, encoding: Encoding, sample: &SampleOptions) -> Result<Schema> {
+    let file = open_file(path, encoding)?;
+    json::infer_values(Values::new(file, encoding), sample)
+}
+
+/// Reads a MessagePack or CBOR stream as records. Values that are not maps are returned as
+/// `{"value": ...}`, as for JSON.
+pub struct BinaryJsonSource<R: Read> {
+    values: Values<R>,
+    buffered: VecDeque<Value>,
+    schema: Schema,
+}
+
+impl BinaryJsonSource<File> {
+    pub fn open(path: &Path, encoding: Encoding, sample: &SampleOptions) -> Result<Self> {
+        let schema = infer_schema(path, encoding, sample)?;
+        Ok(Self::with_schema(
+            open_file(path, encoding)?,
+            encoding,
+            schema,
+        ))
+    }
+}
+
+impl<R: Read> BinaryJsonSource<R> {
+    pub fn with_schema(reader: R, encoding: Encoding, schema: Schema) -> Self {
+        Self {
+            values: Values::new(reader, encoding),
+            buffered: VecDeque::new(),
+            schema,
+        }
+    }
+
+    /// For input that cannot be read twice: the first `sample.rows` values are buffered for
+    /// inference before any record is returned.
+    pub fn from_reader(reader: R, encoding: Encoding, sample: &SampleOptions) -> Result<Self> {
+        let mut values = Values::new(reader, encoding);
+        let (schema, buffered) = json::infer_leading_values(&mut values, sample)?;
+        Ok(Self {
+            values,
+            buffered,
+            schema,
+        })
+    }
+}
+
+impl<R: Read> Iterator for BinaryJsonSource<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = match self.buffered.pop_front() {
+            Some(value) => Ok(value),
+            None => self.values.next()?,
+        };
+        Some(value.map(into_record))
+    }
+}
+
+impl<R: Read> RecordSource for BinaryJsonSource<R> {
+    fn format(&self) -> Format {
+        self.values.encoding.format()
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
+
+/// The concatenated values of a stream, decoded one at a time.
+struct Values<R: Read> {
+    reader: BufReader<R>,
+    encoding: Encoding,
+    index: usize,
+    done: bool,
+}
+
+impl<R: Read> Values<R> {
+    fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            encoding,
+            index: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Values<R> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.reader.fill_buf() {
+            Ok([]) => {
+                self.done = true;
+                return None;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err).context("read input"));
+            }
+        }
+        let value = read_value(self.encoding, &mut self.reader);
+        self.index += 1;
+        Some(value.map_err(|err| {
+            self.done = true;
+            anyhow::Error::new(err).context(format!(
+                "decode {} value {}",
+                self.encoding.name(),
+                self.index
+            ))
+        }))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DecodeError {
+    #[error("unexpected end of input")]
+    Eof,
+    #[error("{0}")]
+    Invalid(String),
+}
+
+fn read_value<R: Read>(encoding: Encoding, reader: &mut R) -> Result<Value, DecodeError> {
+    match encoding {
+        Encoding::MessagePack => {
+            let value = rmpv::decode::read_value(reader).map_err(|err| match err {
+                rmpv::decode::Error::InvalidMarkerRead(io_err)
+                | rmpv::decode::Error::InvalidDataRead(io_err)
+                    if io_err.kind() == io::ErrorKind::UnexpectedEof =>
+                {
+                    DecodeError::Eof
+                }
+                other => DecodeError::Invalid(other.to_string()),
+            })?;
+            Ok(msgpack_to_json(value))
+        }
+        Encoding::Cbor => {
+            let value =
+                ciborium::de::from_reader::<ciborium::Value, _>(reader).map_err(
+                    |err| match err {
+                        ciborium::de::Error::Io(io_err)
+                            if io_err.kind() == io::ErrorKind::UnexpectedEof =>
+                        {
+                            DecodeError::Eof
+                        }
+                        other => DecodeError::Invalid(other.to_string()),
+                    },
+                )?;
+            Ok(cbor_to_json(value))
+        }
+    }
+}
+
+// Binary values become hex strings and non-string map keys their JSON text.
+fn msgpack_to_json(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(i) => match i.as_i64() {
+            Some(i) => Value::from(i),
+            None => i.as_u64().map_or(Value::Null, Value::from),
+        },
+        rmpv::Value::F32(f) => float(f64::from(f)),
+        rmpv::Value::F64(f) => float(f),
+        rmpv::Value::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => Value::Null,
+        },
+        rmpv::Value::Binary(bytes) => Value::String(hex::encode(&bytes)),
+        rmpv::Value::Array(items) => Value::Array(items.into_iter().map(msgpack_to_json).collect()),
+        rmpv::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (map_key(msgpack_to_json(key)), msgpack_to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        rmpv::Value::Ext(_, data) => Value::String(hex::encode(&data)),
+    }
+}
+
+// Tags are dropped in favour of the tagged value, so an epoch-time tag reads as its number.
+fn cbor_to_json(value: ciborium::Value) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            if let Ok(i) = i64::try_from(i) {
+                Value::from(i)
+            } else if let Ok(u) = u64::try_from(i) {
+                Value::from(u)
+            } else {
+                Value::String(i.to_string())
+            }
+        }
+        ciborium::Value::Float(f) => float(f),
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(bytes) => Value::String(hex::encode(&bytes)),
+        ciborium::Value::Array(items) => {
+            Value::Array(items.into_iter().map(cbor_to_json).collect())
+        }
+        ciborium::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (map_key(cbor_to_json(key)), cbor_to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        ciborium::Value::Tag(_, value) => cbor_to_json(*value),
+        _ => Value::Null,
+    }
+}
+
+fn map_key(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn open_file(path: &Path, encoding: Encoding) -> Result<File> {
+    File::open(path).with_context(|| format!("open {} file", encoding.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_record_streams_by_leading_map() {
+        // {"a": 1} in each encoding.
+        assert_eq!(detect_prefix(b"\x81\xa1a\x01"), Some(Encoding::MessagePack));
+        assert_eq!(detect_prefix(b"\xa1\x61a\x01"), Some(Encoding::Cbor));
+        assert_eq!(detect_prefix(b"\xd9\xd9\xf7\xa0"), Some(Encoding::Cbor));
+        // A truncated map in a short input is not a record stream.
+        assert_eq!(detect_prefix(b"\xde\x00\x05\xa1a"), None);
+        assert_eq!(detect_prefix(b"id,name\n"), None);
+        // Text whose first byte happens to start a map: `€uro,amount` in windows-1252 starts
+        // with an empty map, and Shift_JIS `あ` with a map whose second value is not a map.
+        assert_eq!(detect_prefix(b"\x80uro,amount\r\n\x805,10\r\n"), None);
+        assert_eq!(detect_prefix(b"\x81\xa1a\x01id,name\n"), None);
+        assert_eq!(
+            detect_prefix(b"\x81\xa1a\x01\x81\xa1a\x02"),
+            Some(Encoding::MessagePack)
+        );
+    }
+
+    #[test]
+    fn reads_concatenated_values() {
+        let bytes: &[u8] = b"\x82\xa2id\x01\xa4tags\x92\xa1x\xc4\x01\xff\x81\xa2id\xcd\x01\x00";
+        let records: Vec<Record> =
+            BinaryJsonSource::from_reader(bytes, Encoding::MessagePack, &SampleOptions::default())
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["tags"], serde_json::json!(["x", "ff"]));
+        assert_eq!(records[1]["id"], serde_json::json!(256));
+    }
+}
@@ -1,68 +1,465 @@
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use csv::StringRecord;
+use serde_json::Value as JsonValue;
 
+use crate::formats::Format;
 use crate::model::schema::{DataType, Field, Schema};
+use crate::source::{Record, RecordSource};
+use crate::util::sample::{SampleOptions, Sampler};
 
-const SAMPLE_LIMIT: usize = 1000;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderMode {
+    /// The first row holds column names.
+    #[default]
+    Present,
+    /// Every row is data; names are generated as `column_1..N`.
+    Absent,
+    /// Treat the first row as a header when it is all text and the rows below it are typed.
+    Detect,
+}
 
-pub fn infer_schema(path: &Path) -> Result<Schema> {
-    let file = File::open(path).context("open CSV file")?;
-    infer_schema_reader(file)
+/// Options used when interpreting CSV text.
+///
+/// Empty or whitespace-only cells are always null, and `true`/`false` (any case) are always
+/// booleans; the vocabularies here extend those defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub header: HeaderMode,
+    /// Column names used instead of the header row or generated names.
+    pub column_names: Vec<String>,
+    /// Cell values treated as null, matched exactly after trimming.
+    pub null_values: Vec<String>,
+    /// Cell values treated as `true`, matched case-insensitively after trimming. Numeric tokens
+    /// (such as `1`) only type a column as `bool` when every cell in it is a boolean token.
+    pub true_values: Vec<String>,
+    /// Cell values treated as `false`, matched case-insensitively after trimming.
+    pub false_values: Vec<String>,
 }
 
-pub fn infer_schema_reader<R: Read>(reader: R) -> Result<Schema> {
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_reader(reader);
-    let headers = reader.headers().context("read CSV headers")?.clone();
+impl CsvOptions {
+    pub fn is_null(&self, value: &str) -> bool {
+        let trimmed = value.trim();
+        trimmed.is_empty() || self.null_values.iter().any(|token| token == trimmed)
+    }
+
+    pub fn parse_bool(&self, value: &str) -> Option<bool> {
+        let trimmed = value.trim();
+        let matches = |tokens: &[String]| {
+            tokens
+                .iter()
+                .any(|token| token.eq_ignore_ascii_case(trimmed))
+        };
+        if trimmed.eq_ignore_ascii_case("true") || matches(&self.true_values) {
+            Some(true)
+        } else if trimmed.eq_ignore_ascii_case("false") || matches(&self.false_values) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// Builds a CSV reader that leaves header handling to [`read_header`].
+pub fn reader_builder() -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false);
+    builder
+}
+
+/// How many rows below the first are compared with it when guessing whether it is a header.
+const HEADER_SNIFF_ROWS: usize = 100;
+
+/// Reads the first row and resolves the column names.
+///
+/// Returns the rows that were read but are data: the first row when the file has no header
+/// row, and the rows read ahead to decide that in [`HeaderMode::Detect`].
+pub fn read_header<R: Read>(
+    reader: &mut csv::Reader<R>,
+    options: &CsvOptions,
+) -> Result<(Vec<String>, Vec<StringRecord>)> {
+    let mut first = StringRecord::new();
+    if !reader.read_record(&mut first).context("read CSV headers")? {
+        return Ok((options.column_names.clone(), Vec::new()));
+    }
+    let mut rows = Vec::new();
+    let has_header = match options.header {
+        HeaderMode::Present => true,
+        HeaderMode::Absent => false,
+        HeaderMode::Detect => {
+            for record in reader.records().take(HEADER_SNIFF_ROWS) {
+                rows.push(record.context("read CSV record")?);
+            }
+            looks_like_header(&first, &rows, options)
+        }
+    };
+    if options.column_names.len() > first.len() {
+        bail!(
+            "{} column names were given but the CSV has {} columns",
+            options.column_names.len(),
+            first.len()
+        );
+    }
+    let names = (0..first.len())
+        .map(|idx| match options.column_names.get(idx) {
+            Some(name) => name.clone(),
+            None if has_header => first[idx].to_string(),
+            None => format!("column_{}", idx + 1),
+        })
+        .collect();
+    if !has_header {
+        rows.insert(0, first);
+    }
+    Ok((names, rows))
+}
 
-    let mut dtypes = vec![DataType::Null; headers.len()];
-    let mut nullable = vec![false; headers.len()];
+// Header names are text: a first row containing numbers, booleans or empty cells is data. An
+// all-text first row is a header only when some column below it is typed (numbers, booleans),
+// or when there are no rows below it to compare with.
+fn looks_like_header(first: &StringRecord, rows: &[StringRecord], options: &CsvOptions) -> bool {
+    let all_text = first
+        .iter()
+        .all(|value| matches!(infer_scalar(value, options), DataType::String));
+    if !all_text {
+        return false;
+    }
+    if rows.is_empty() {
+        return true;
+    }
+    let mut stats = ColumnStats::new(first.len());
+    for row in rows {
+        stats.observe(row, options);
+    }
+    let names = vec![String::new(); first.len()];
+    stats
+        .into_schema(&names)
+        .fields
+        .iter()
+        .any(|field| !matches!(field.dtype, DataType::String | DataType::Null))
+}
 
-    for (idx, record) in reader.records().enumerate() {
-        if idx >= SAMPLE_LIMIT {
+pub fn infer_schema(path: &Path, sample: &SampleOptions, options: &CsvOptions) -> Result<Schema> {
+    let file = File::open(path).context("open CSV file")?;
+    infer_schema_reader(file, sample, options)
+}
+
+pub fn infer_schema_reader<R: Read>(
+    reader: R,
+    sample: &SampleOptions,
+    options: &CsvOptions,
+) -> Result<Schema> {
+    let mut reader = reader_builder().from_reader(reader);
+    let (headers, rows) = read_header(&mut reader, options)?;
+
+    let mut stats = ColumnStats::new(headers.len());
+    let mut sampler = Sampler::new(sample);
+    let records = rows.into_iter().map(Ok).chain(reader.records());
+    for record in records {
+        if sampler.is_done() {
             break;
         }
         let record = record.context("read CSV record")?;
-        for col in 0..headers.len() {
-            let value = record.get(col).unwrap_or("");
-            let inferred = infer_scalar(value);
-            if matches!(inferred, DataType::Null) {
-                nullable[col] = true;
+        if let Some(record) = sampler.offer(record) {
+            stats.observe(&record, options);
+        }
+    }
+    for record in sampler.finish() {
+        stats.observe(&record, options);
+    }
+
+    Ok(stats.into_schema(&headers))
+}
+
+/// Reads CSV rows as records, typing each cell by a schema inferred from a sample.
+pub struct CsvSource<R: Read> {
+    records: csv::StringRecordsIntoIter<R>,
+    pending: VecDeque<StringRecord>,
+    schema: Schema,
+    options: CsvOptions,
+}
+
+impl CsvSource<File> {
+    pub fn open(path: &Path, sample: &SampleOptions, options: &CsvOptions) -> Result<Self> {
+        let schema = infer_schema(path, sample, options)?;
+        let file = File::open(path).context("open CSV file")?;
+        Self::with_schema(file, schema, options)
+    }
+}
+
+impl<R: Read> CsvSource<R> {
+    pub fn with_schema(reader: R, schema: Schema, options: &CsvOptions) -> Result<Self> {
+        let mut reader = reader_builder().from_reader(reader);
+        let (_, rows) = read_header(&mut reader, options)?;
+        Ok(Self {
+            records: reader.into_records(),
+            pending: rows.into(),
+            schema,
+            options: options.clone(),
+        })
+    }
+
+    /// For input that cannot be read twice: the first `sample.rows` records are buffered for
+    /// inference before any record is returned.
+    pub fn from_reader(reader: R, sample: &SampleOptions, options: &CsvOptions) -> Result<Self> {
+        let mut reader = reader_builder().from_reader(reader);
+        let (headers, rows) = read_header(&mut reader, options)?;
+        let mut records = reader.into_records();
+
+        let limit = sample.rows.unwrap_or(usize::MAX);
+        let mut stats = ColumnStats::new(headers.len());
+        let mut pending: VecDeque<StringRecord> = rows.into();
+        while pending.len() < limit {
+            match records.next() {
+                Some(record) => pending.push_back(record.context("read CSV record")?),
+                None => break,
             }
-            dtypes[col] = DataType::merge(&dtypes[col], &inferred);
         }
+        for record in pending.iter().take(limit) {
+            stats.observe(record, options);
+        }
+        Ok(Self {
+            records,
+            pending,
+            schema: stats.into_schema(&headers),
+            options: options.clone(),
+        })
+    }
+}
+
+impl<R: Read> Iterator for CsvSource<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.pending.pop_front() {
+            Some(record) => record,
+            None => match self.records.next()?.context("read CSV record") {
+                Ok(record) => record,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+        Some(Ok(record_to_json(&self.schema, &record, &self.options)))
     }
+}
 
-    let mut fields = Vec::with_capacity(headers.len());
-    for (idx, name) in headers.iter().enumerate() {
-        fields.push(Field {
-            name: name.to_string(),
-            dtype: dtypes[idx].clone(),
-            nullable: nullable[idx],
-        });
+impl<R: Read> RecordSource for CsvSource<R> {
+    fn format(&self) -> Format {
+        Format::Csv
     }
 
-    Ok(Schema { fields })
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
 }
 
-fn infer_scalar(value: &str) -> DataType {
+fn record_to_json(schema: &Schema, record: &StringRecord, options: &CsvOptions) -> Record {
+    let mut map = Record::new();
+    for (idx, field) in schema.fields.iter().enumerate() {
+        let value = record.get(idx).unwrap_or("");
+        map.insert(
+            field.name.clone(),
+            typed_value(value, &field.dtype, options),
+        );
+    }
+    map
+}
+
+// Cells that do not parse as the inferred type (e.g. outside the sample) are kept as strings.
+pub(crate) fn typed_value(value: &str, dtype: &DataType, options: &CsvOptions) -> JsonValue {
+    if options.is_null(value) {
+        return JsonValue::Null;
+    }
     let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return DataType::Null;
+    let typed = match dtype {
+        DataType::Bool => options.parse_bool(value).map(JsonValue::Bool),
+        DataType::Int => trimmed.parse::<i64>().ok().map(JsonValue::from),
+        DataType::Float => trimmed
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(JsonValue::Number),
+        _ => None,
+    };
+    typed.unwrap_or_else(|| JsonValue::String(value.to_string()))
+}
+
+struct ColumnStats {
+    dtypes: Vec<DataType>,
+    nullable: Vec<bool>,
+    // Whether every non-null cell so far is a boolean token, including numeric ones such as
+    // `1`/`0` that were typed as numbers above.
+    all_bool: Vec<bool>,
+}
+
+impl ColumnStats {
+    fn new(width: usize) -> Self {
+        Self {
+            dtypes: vec![DataType::Null; width],
+            nullable: vec![false; width],
+            all_bool: vec![true; width],
+        }
     }
-    let lower = trimmed.to_ascii_lowercase();
-    if lower == "true" || lower == "false" {
-        return DataType::Bool;
+
+    fn observe(&mut self, record: &StringRecord, options: &CsvOptions) {
+        for col in 0..self.dtypes.len() {
+            let value = record.get(col).unwrap_or("");
+            let inferred = infer_scalar(value, options);
+            if matches!(inferred, DataType::Null) {
+                self.nullable[col] = true;
+            } else if options.parse_bool(value).is_none() {
+                self.all_bool[col] = false;
+            }
+            self.dtypes[col] = DataType::merge(&self.dtypes[col], &inferred);
+        }
     }
+
+    fn into_schema(self, headers: &[String]) -> Schema {
+        let fields = headers
+            .iter()
+            .zip(self.dtypes)
+            .zip(self.nullable.into_iter().zip(self.all_bool))
+            .map(|((name, dtype), (nullable, all_bool))| Field {
+                name: name.clone(),
+                dtype: if all_bool && dtype != DataType::Null {
+                    DataType::Bool
+                } else {
+                    dtype
+                },
+                nullable,
+            })
+            .collect();
+        Schema { fields }
+    }
+}
+
+/// Infers the type of one cell. Numbers win over boolean tokens such as `1`/`0`, so an id
+/// column stays numeric; a column is only typed `bool` when every cell is a boolean token
+/// (see `ColumnStats`).
+pub(crate) fn infer_scalar(value: &str, options: &CsvOptions) -> DataType {
+    if options.is_null(value) {
+        return DataType::Null;
+    }
+    let trimmed = value.trim();
     if trimmed.parse::<i64>().is_ok() {
         return DataType::Int;
     }
     if trimmed.parse::<f64>().is_ok() {
         return DataType::Float;
     }
+    if options.parse_bool(value).is_some() {
+        return DataType::Bool;
+    }
     DataType::String
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_types(data: &str, options: &CsvOptions) -> Vec<(String, String, bool)> {
+        let schema = infer_schema_reader(data.as_bytes(), &SampleOptions::default(), options)
+            .expect("infer schema");
+        schema
+            .fields
+            .iter()
+            .map(|f| (f.name.clone(), f.dtype.to_string(), f.nullable))
+            .collect()
+    }
+
+    #[test]
+    fn custom_null_and_bool_tokens() {
+        let data = "score,flag\n1.5,Y\nNA,N\n\\N,yes\n";
+        let default = field_types(data, &CsvOptions::default());
+        assert_eq!(
+            default,
+            vec![
+                ("score".to_string(), "string".to_string(), false),
+                ("flag".to_string(), "string".to_string(), false),
+            ]
+        );
+
+        let options = CsvOptions {
+            header: HeaderMode::Present,
+            column_names: Vec::new(),
+            null_values: vec!["NA".to_string(), "\\N".to_string()],
+            true_values: vec!["yes".to_string(), "y".to_string()],
+            false_values: vec!["no".to_string(), "n".to_string()],
+        };
+        assert_eq!(
+            field_types(data, &options),
+            vec![
+                ("score".to_string(), "float".to_string(), true),
+                ("flag".to_string(), "bool".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn numeric_bool_tokens_only_type_all_token_columns() {
+        let options = CsvOptions {
+            true_values: vec!["yes".to_string(), "Y".to_string(), "1".to_string()],
+            false_values: vec!["no".to_string(), "N".to_string(), "0".to_string()],
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            field_types("id,flag,bit\n1,yes,1\n2,no,0\n3,yes,1\n", &options),
+            vec![
+                ("id".to_string(), "int".to_string(), false),
+                ("flag".to_string(), "bool".to_string(), false),
+                ("bit".to_string(), "bool".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn headerless_names_and_detection() {
+        let data = "1,alice\n2,bob\n";
+        let absent = CsvOptions {
+            header: HeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            field_types(data, &absent),
+            vec![
+                ("column_1".to_string(), "int".to_string(), false),
+                ("column_2".to_string(), "string".to_string(), false),
+            ]
+        );
+
+        let named = CsvOptions {
+            header: HeaderMode::Detect,
+            column_names: vec!["id".to_string()],
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            field_types(data, &named),
+            vec![
+                ("id".to_string(), "int".to_string(), false),
+                ("column_2".to_string(), "string".to_string(), false),
+            ]
+        );
+
+        let detect = CsvOptions {
+            header: HeaderMode::Detect,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            field_types("id,name\n1,alice\n", &detect),
+            vec![
+                ("id".to_string(), "int".to_string(), false),
+                ("name".to_string(), "string".to_string(), false),
+            ]
+        );
+        // Every row is text, so nothing sets the first row apart as a header.
+        assert_eq!(
+            field_types("alice,paris\nbob,rome\n", &detect),
+            vec![
+                ("column_1".to_string(), "string".to_string(), false),
+                ("column_2".to_string(), "string".to_string(), false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,584 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value as JsonValue;
+
+use crate::formats::Format;
+use crate::formats::csv::{CsvOptions, typed_value};
+use crate::formats::parquet::ParquetSource;
+use crate::model::schema::{DataType, Field, Schema};
+use crate::model::table::TableInfo;
+use crate::source::{Record, RecordSource};
+use crate::util::errors::UnsupportedFormatError;
+
+/// Directory holding a Delta table's transaction log.
+pub const LOG_DIR: &str = "_delta_log";
+
+/// Reader features that do not change how data files are read.
+const SUPPORTED_READER_FEATURES: &[&str] = &["timestampNtz", "v2Checkpoint", "vacuumProtocolCheck"];
+
+/// Options used when reading Delta tables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeltaOptions {
+    /// Table version to read instead of the latest one.
+    pub version: Option<u64>,
+}
+
+/// Whether `path` is the root directory of a Delta table.
+pub fn is_table(path: &Path) -> bool {
+    path.join(LOG_DIR).is_dir()
+}
+
+pub fn infer_schema(path: &Path, options: &DeltaOptions) -> Result<Schema> {
+    Ok(Snapshot::load(path, options)?.schema)
+}
+
+/// The active data files and schema of a Delta table at one version.
+#[derive(Debug)]
+struct Snapshot {
+    version: u64,
+    schema: Schema,
+    partition_columns: Vec<String>,
+    files: Vec<DataFile>,
+}
+
+#[derive(Debug)]
+struct DataFile {
+    path: PathBuf,
+    partition_values: Record,
+}
+
+/// The commits and checkpoints found in `_delta_log/`, by version.
+#[derive(Debug, Default)]
+struct LogListing {
+    commits: BTreeMap<u64, PathBuf>,
+    /// Checkpoint parts by version; a multi-part checkpoint is usable once every part exists.
+    checkpoints: BTreeMap<u64, (usize, Vec<PathBuf>)>,
+}
+
+impl LogListing {
+    fn read(log_dir: &Path) -> Result<Self> {
+        let mut listing = Self::default();
+        let entries = fs::read_dir(log_dir)
+            .with_context(|| format!("read Delta log {}", log_dir.display()))?;
+        for entry in entries {
+            let path = entry.context("read Delta log entry")?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some((version, rest)) = name.split_once('.') else {
+                continue;
+            };
+            let Ok(version) = version.parse::<u64>() else {
+                continue;
+            };
+            let parts: Vec<&str> = rest.split('.').collect();
+            match parts.as_slice() {
+                ["json"] => {
+                    listing.commits.insert(version, path);
+                }
+                ["checkpoint", "parquet"] => {
+                    listing.checkpoints.insert(version, (1, vec![path]));
+                }
+                ["checkpoint", _, total, "parquet"] => {
+                    let Ok(total) = total.parse::<usize>() else {
+                        continue;
+                    };
+                    let entry = listing
+                        .checkpoints
+                        .entry(version)
+                        .or_insert_with(|| (total, Vec::new()));
+                    entry.1.push(path);
+                }
+                // UUID-named (v2) checkpoints, checksums and compacted logs are not needed
+                // when the commits themselves are present.
+                _ => {}
+            }
+        }
+        Ok(listing)
+    }
+
+    fn latest_version(&self) -> Option<u64> {
+        let commit = self.commits.keys().next_back().copied();
+        let checkpoint = self
+            .complete_checkpoints()
+            .next_back()
+            .map(|(version, _)| version);
+        commit.max(checkpoint)
+    }
+
+    fn complete_checkpoints(&self) -> impl DoubleEndedIterator<Item = (u64, &Vec<PathBuf>)> {
+        self.checkpoints
+            .iter()
+            .filter(|(_, (total, parts))| parts.len() == *total)
+            .map(|(version, (_, parts))| (*version, parts))
+    }
+}
+
+impl Snapshot {
+    /// Replays the log up to the requested (or latest) version: the newest checkpoint at or
+    /// before it, then each later commit in order.
+    fn load(root: &Path, options: &DeltaOptions) -> Result<Self> {
+        let listing = LogListing::read(&root.join(LOG_DIR))?;
+        let Some(latest) = listing.latest_version() else {
+            bail!("Delta log {} has no commits", root.join(LOG_DIR).display());
+        };
+        let version = options.version.unwrap_or(latest);
+        if version > latest {
+            bail!("Delta table version {version} does not exist (latest is {latest})");
+        }
+
+        let mut state = LogState::default();
+        let checkpoint = listing
+            .complete_checkpoints()
+            .rev()
+            .find(|(checkpoint, _)| *checkpoint <= version);
+        let start = match checkpoint {
+            Some((checkpoint, parts)) => {
+                let mut parts = parts.clone();
+                parts.sort();
+                for part in &parts {
+                    let source = ParquetSource::open(part)
+                        .with_context(|| format!("read Delta checkpoint {}", part.display()))?;
+                    for action in source {
+                        state.apply(&JsonValue::Object(action?))?;
+                    }
+                }
+                checkpoint + 1
+            }
+            None => 0,
+        };
+        for commit in start..=version {
+            let Some(path) = listing.commits.get(&commit) else {
+                bail!("Delta commit {commit} is missing from the log; it may have been cleaned up");
+            };
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("read Delta commit {}", path.display()))?;
+            for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                let action: JsonValue = serde_json::from_str(line)
+                    .with_context(|| format!("parse Delta commit {}", path.display()))?;
+                state.apply(&action)?;
+            }
+        }
+        state.into_snapshot(root, version)
+    }
+}
+
+#[derive(Debug, Default)]
+struct LogState {
+    metadata: Option<JsonValue>,
+    files: BTreeMap<String, JsonValue>,
+}
+
+impl LogState {
+    /// Applies one action. Each log line (or checkpoint row) holds a single action, keyed by
+    /// its kind; kinds that do not affect the snapshot, such as `commitInfo`, are ignored.
+    fn apply(&mut self, action: &JsonValue) -> Result<()> {
+        if let Some(protocol) = non_null(action, "protocol") {
+            check_protocol(protocol)?;
+        }
+        if let Some(metadata) = non_null(action, "metaData") {
+            self.metadata = Some(metadata.clone());
+        }
+        if let Some(add) = non_null(action, "add") {
+            if non_null(add, "deletionVector").is_some() {
+                bail!(UnsupportedFormatError::new(
+                    "Delta tables with deletion vectors are not supported"
+                ));
+            }
+            self.files.insert(action_path(add)?, add.clone());
+        }
+        if let Some(remove) = non_null(action, "remove") {
+            self.files.remove(&action_path(remove)?);
+        }
+        Ok(())
+    }
+
+    fn into_snapshot(self, root: &Path, version: u64) -> Result<Snapshot> {
+        let Some(metadata) = self.metadata else {
+            bail!("Delta log has no table metadata at version {version}");
+        };
+        let mapping = metadata
+            .pointer("/configuration/delta.columnMapping.mode")
+            .and_then(JsonValue::as_str);
+        if mapping.is_some_and(|mode| mode != "none") {
+            bail!(UnsupportedFormatError::new(
+                "Delta tables with column mapping are not supported"
+            ));
+        }
+        let schema_string = metadata
+            .get("schemaString")
+            .and_then(JsonValue::as_str)
+            .context("Delta metadata has no schemaString")?;
+        let schema_json: JsonValue =
+            serde_json::from_str(schema_string).context("parse Delta table schema")?;
+        let schema = Schema {
+            fields: struct_fields(&schema_json)?,
+        };
+        let partition_columns: Vec<&str> = metadata
+            .get("partitionColumns")
+            .and_then(JsonValue::as_array)
+            .map(|columns| columns.iter().filter_map(JsonValue::as_str).collect())
+            .unwrap_or_default();
+        let partition_fields: Vec<&Field> = partition_columns
+            .iter()
+            .filter_map(|column| schema.field(column))
+            .collect();
+
+        let options = CsvOptions::default();
+        let mut files = Vec::with_capacity(self.files.len());
+        for (path, add) in self.files {
+            let mut partition_values = Record::new();
+            for field in &partition_fields {
+                let value = add
+                    .get("partitionValues")
+                    .and_then(|values| values.get(&field.name))
+                    .and_then(JsonValue::as_str)
+                    .map_or(JsonValue::Null, |value| {
+                        typed_value(value, &field.dtype, &options)
+                    });
+                partition_values.insert(field.name.clone(), value);
+            }
+            files.push(DataFile {
+                path: resolve_path(root, &path)?,
+                partition_values,
+            });
+        }
+        let partition_columns = partition_fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect();
+        Ok(Snapshot {
+            version,
+            schema,
+            partition_columns,
+            files,
+        })
+    }
+}
+
+fn non_null<'a>(value: &'a JsonValue, key: &str) -> Option<&'a JsonValue> {
+    value.get(key).filter(|value| !value.is_null())
+}
+
+fn action_path(action: &JsonValue) -> Result<String> {
+    action
+        .get("path")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string)
+        .context("Delta file action has no path")
+}
+
+fn check_protocol(protocol: &JsonValue) -> Result<()> {
+    let reader_version = protocol
+        .get("minReaderVersion")
+        .and_then(JsonValue::as_u64)
+        .unwrap_or(1);
+    if reader_version > 3 {
+        bail!(UnsupportedFormatError::new(format!(
+            "Delta reader version {reader_version} is not supported"
+        )));
+    }
+    let features = protocol
+        .get("readerFeatures")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(JsonValue::as_str);
+    let unsupported: BTreeSet<&str> = features
+        .filter(|feature| !SUPPORTED_READER_FEATURES.contains(feature))
+        .collect();
+    if !unsupported.is_empty() {
+        let names: Vec<&str> = unsupported.into_iter().collect();
+        bail!(UnsupportedFormatError::new(format!(
+            "Delta reader features are not supported: {}",
+            names.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+// Data file paths are relative URIs, or absolute `file:` URIs; both are percent-encoded.
+fn resolve_path(root: &Path, path: &str) -> Result<PathBuf> {
+    if let Some((scheme, rest)) = path.split_once(':')
+        && rest.starts_with('/')
+        && !scheme.contains('/')
+    {
+        if scheme != "file" {
+            bail!(UnsupportedFormatError::new(format!(
+                "Delta data file {path} is not on the local file system"
+            )));
+        }
+        let rest = rest.strip_prefix("//").unwrap_or(rest);
+        return Ok(PathBuf::from(percent_decode(rest)?));
+    }
+    Ok(root.join(percent_decode(path)?))
+}
+
+fn percent_decode(text: &str) -> Result<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = text
+                .get(idx + 1..idx + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .with_context(|| format!("invalid percent-encoding in `{text}`"))?;
+            out.push(hex);
+            idx += 3;
+        } else {
+            out.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(out).with_context(|| format!("invalid percent-encoding in `{text}`"))
+}
+
+fn struct_fields(schema: &JsonValue) -> Result<Vec<Field>> {
+    let fields = schema
+        .get("fields")
+        .and_then(JsonValue::as_array)
+        .context("Delta struct type has no fields")?;
+    fields
+        .iter()
+        .map(|field| {
+            let name = field
+                .get("name")
+                .and_then(JsonValue::as_str)
+                .context("Delta field has no name")?;
+            Ok(Field {
+                name: name.to_string(),
+                dtype: map_type(field.get("type").unwrap_or(&JsonValue::Null)),
+                nullable: field
+                    .get("nullable")
+                    .and_then(JsonValue::as_bool)
+                    .unwrap_or(true),
+            })
+        })
+        .collect()
+}
+
+// Maps follow the Parquet reader, which reads them as objects.
+fn map_type(dtype: &JsonValue) -> DataType {
+    match dtype {
+        JsonValue::String(name) => match name.as_str() {
+            "boolean" => DataType::Bool,
+            "byte" | "short" | "integer" | "long" => DataType::Int,
+            "float" | "double" => DataType::Float,
+            "string" => DataType::String,
+            "binary" => DataType::Bytes,
+            "date" => DataType::Date,
+            "timestamp" | "timestamp_ntz" => DataType::Timestamp,
+            _ => DataType::Unknown,
+        },
+        JsonValue::Object(object) => match object.get("type").and_then(JsonValue::as_str) {
+            Some("struct") | Some("map") => DataType::Struct,
+            Some("array") => DataType::List(Box::new(map_type(
+                object.get("elementType").unwrap_or(&JsonValue::Null),
+            ))),
+            _ => DataType::Unknown,
+        },
+        _ => DataType::Unknown,
+    }
+}
+
+/// Reads the current snapshot of a Delta table (or the version in [`DeltaOptions`]) through
+/// the Parquet reader, adding each file's partition values to its records.
+pub struct DeltaSource {
+    version: u64,
+    partition_columns: Vec<String>,
+    schema: Schema,
+    pending: VecDeque<DataFile>,
+    current: Option<(ParquetSource, Record)>,
+}
+
+impl DeltaSource {
+    pub fn open(path: &Path, options: &DeltaOptions) -> Result<Self> {
+        let snapshot = Snapshot::load(path, options)?;
+        Ok(Self {
+            version: snapshot.version,
+            partition_columns: snapshot.partition_columns,
+            schema: snapshot.schema,
+            pending: snapshot.files.into(),
+            current: None,
+        })
+    }
+
+    /// The table version being read.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn current(&mut self) -> Result<Option<&mut (ParquetSource, Record)>> {
+        if self.current.is_none() {
+            let Some(file) = self.pending.pop_front() else {
+                return Ok(None);
+            };
+            let source = ParquetSource::open(&file.path)
+                .with_context(|| format!("open Delta data file {}", file.path.display()))?;
+            self.current = Some((source, file.partition_values));
+        }
+        Ok(self.current.as_mut())
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        loop {
+            let Some((source, partition_values)) = self.current()? else {
+                return Ok(None);
+            };
+            match source.next() {
+                Some(record) => {
+                    let mut record = record?;
+                    for (key, value) in partition_values.iter() {
+                        record.insert(key.clone(), value.clone());
+                    }
+                    return Ok(Some(record));
+                }
+                None => self.current = None,
+            }
+        }
+    }
+}
+
+impl Iterator for DeltaSource {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.next_record();
+        if record.is_err() {
+            self.pending.clear();
+            self.current = None;
+        }
+        record.transpose()
+    }
+}
+
+impl RecordSource for DeltaSource {
+    fn format(&self) -> Format {
+        Format::Delta
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn table_info(&self) -> Option<TableInfo> {
+        Some(TableInfo {
+            version: Some(self.version),
+            partitioning: self.partition_columns.clone(),
+            ..TableInfo::default()
+        })
+    }
+
+    fn skip_records(&mut self, count: usize) -> Result<usize> {
+        let mut skipped = 0;
+        while skipped < count {
+            let Some((source, _)) = self.current()? else {
+                break;
+            };
+            let step = source.skip_records(count - skipped)?;
+            skipped += step;
+            if skipped < count {
+                self.current = None;
+            }
+        }
+        Ok(skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    use arrow::datatypes::{DataType as ArrowType, Field as ArrowField, Fields};
+    use parquet::arrow::ArrowWriter;
+
+    fn temp_table(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("megrez_delta_{name}_{}", std::process::id()));
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        fs::create_dir_all(root.join(LOG_DIR)).unwrap();
+        root
+    }
+
+    // Writes checkpoint rows given as JSON lines, with the `add` and `metaData` columns of a
+    // real checkpoint (partition values are a Parquet map).
+    fn write_checkpoint(path: &Path, rows: &str) {
+        let map = ArrowType::Map(
+            Arc::new(ArrowField::new(
+                "key_value",
+                ArrowType::Struct(Fields::from(vec![
+                    ArrowField::new("key", ArrowType::Utf8, false),
+                    ArrowField::new("value", ArrowType::Utf8, true),
+                ])),
+                false,
+            )),
+            false,
+        );
+        let add = ArrowType::Struct(Fields::from(vec![
+            ArrowField::new("path", ArrowType::Utf8, true),
+            ArrowField::new("partitionValues", map, true),
+        ]));
+        let metadata = ArrowType::Struct(Fields::from(vec![
+            ArrowField::new("schemaString", ArrowType::Utf8, true),
+            ArrowField::new(
+                "partitionColumns",
+                ArrowType::List(Arc::new(ArrowField::new("element", ArrowType::Utf8, true))),
+                true,
+            ),
+        ]));
+        let schema = Arc::new(arrow::datatypes::Schema::new(vec![
+            ArrowField::new("add", add, true),
+            ArrowField::new("metaData", metadata, true),
+        ]));
+        let batch = arrow::json::ReaderBuilder::new(schema.clone())
+            .build(Cursor::new(rows.as_bytes()))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let mut writer =
+            ArrowWriter::try_new(fs::File::create(path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn replays_commits_after_checkpoint() {
+        let root = temp_table("checkpoint");
+        let schema = r#"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"long\",\"nullable\":false,\"metadata\":{}},{\"name\":\"day\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}}]}"#;
+        write_checkpoint(
+            &root
+                .join(LOG_DIR)
+                .join("00000000000000000001.checkpoint.parquet"),
+            &format!(
+                "{{\"metaData\":{{\"schemaString\":\"{schema}\",\"partitionColumns\":[\"day\"]}}}}\n\
+                 {{\"add\":{{\"path\":\"day=1/a%20b.parquet\",\"partitionValues\":{{\"day\":\"1\"}}}}}}\n\
+                 {{\"add\":{{\"path\":\"day=2/c.parquet\",\"partitionValues\":{{\"day\":\"2\"}}}}}}\n"
+            ),
+        );
+        fs::write(
+            root.join(LOG_DIR).join("00000000000000000002.json"),
+            "{\"remove\":{\"path\":\"day=2/c.parquet\",\"dataChange\":true}}\n\
+             {\"commitInfo\":{\"operation\":\"DELETE\"}}\n",
+        )
+        .unwrap();
+
+        let snapshot = Snapshot::load(&root, &DeltaOptions::default()).unwrap();
+        assert_eq!(snapshot.version, 2);
+        assert_eq!(snapshot.schema.fields[1].dtype, DataType::Int);
+        assert_eq!(snapshot.files.len(), 1);
+        assert_eq!(snapshot.files[0].path, root.join("day=1/a b.parquet"));
+        assert_eq!(snapshot.files[0].partition_values["day"], 1);
+
+        let err = Snapshot::load(&root, &DeltaOptions { version: Some(0) }).unwrap_err();
+        assert!(err.to_string().contains("commit 0 is missing"));
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -1,99 +1,841 @@
+use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
+use encoding_rs::Encoding;
 
 use crate::formats::Format;
+use crate::formats::arrow_ipc::{
+    MAGIC_FILE as MAGIC_ARROW_FILE, MAGIC_STREAM as MAGIC_ARROW_STREAM,
+};
+use crate::formats::binary_json::{self, SNIFF_LEN};
+use crate::formats::spreadsheet::{self, MAGIC_ZIP};
+use crate::formats::{delta, iceberg};
+use crate::util::encoding;
 use crate::util::errors::UnsupportedFormatError;
-use crate::util::io::peek_first_non_ws;
 
 const MAGIC_PARQUET: &[u8; 4] = b"PAR1";
 const MAGIC_AVRO: &[u8; 4] = b"Obj\x01";
 const MAGIC_ORC: &[u8; 3] = b"ORC";
+/// The PostScript's `magic` field (number 8000, length-delimited) holding `ORC`.
+const ORC_POSTSCRIPT_MAGIC: &[u8; 7] = b"\x82\xf4\x03\x03ORC";
+/// Longer Avro metadata keys are taken as a sign of a corrupt or coincidental header.
+const AVRO_MAX_KEY_LEN: u64 = 1024;
+const AVRO_SYNC_LEN: u64 = 16;
+const MAGIC_GZIP: &[u8; 2] = b"\x1f\x8b";
+const MAGIC_PDF: &[u8; 5] = b"%PDF-";
+const MAGIC_SQLITE: &[u8; 16] = b"SQLite format 3\0";
+const MAGIC_HDF5: &[u8; 8] = b"\x89HDF\r\n\x1a\n";
+/// HDF5 files may start with a user block, so the signature is also looked for at these offsets.
+const HDF5_OFFSETS: [usize; 5] = [0, 512, 1024, 2048, 4096];
+/// Compound File Binary, the container of legacy Office documents.
+const MAGIC_CFB: &[u8; 8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+const MAGIC_ZIP_EMPTY: &[u8; 4] = b"PK\x05\x06";
+
+/// A detection rule, in the order [`detect_format`] applies them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The directory layout of a Delta Lake or Iceberg table.
+    Table,
+    /// The leading bytes of the file.
+    Magic,
+    /// The trailing bytes of the file (the Parquet footer).
+    Footer,
+    /// The file name's extension.
+    Extension,
+    /// A lightweight parse of the first bytes, for text formats.
+    Content,
+}
+
+impl Rule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rule::Table => "table",
+            Rule::Magic => "magic",
+            Rule::Footer => "footer",
+            Rule::Extension => "extension",
+            Rule::Content => "content",
+        }
+    }
+
+    // How the rule is named in the "tried: ..." list of a detection failure.
+    fn attempt(self) -> &'static str {
+        match self {
+            Rule::Table => "table layout",
+            Rule::Magic => "magic bytes",
+            Rule::Footer => "footer bytes",
+            Rule::Extension => "file extension",
+            Rule::Content => "content sniff",
+        }
+    }
+}
+
+/// How far a detection result can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+
+    fn lower(self) -> Self {
+        match self {
+            Confidence::High => Confidence::Medium,
+            _ => Confidence::Low,
+        }
+    }
+
+    fn raise(self) -> Self {
+        match self {
+            Confidence::Low => Confidence::Medium,
+            _ => Confidence::High,
+        }
+    }
+}
+
+/// A format that is recognized so that errors can name it, but that cannot be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unsupported {
+    Zip,
+    Gzip,
+    Pdf,
+    Sqlite,
+    Xml,
+    Yaml,
+    Hdf5,
+    /// A Compound File Binary container, as used by legacy `.xls` workbooks.
+    LegacyOffice,
+}
+
+impl Unsupported {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unsupported::Zip => "ZIP",
+            Unsupported::Gzip => "GZIP",
+            Unsupported::Pdf => "PDF",
+            Unsupported::Sqlite => "SQLITE",
+            Unsupported::Xml => "XML",
+            Unsupported::Yaml => "YAML",
+            Unsupported::Hdf5 => "HDF5",
+            Unsupported::LegacyOffice => "XLS",
+        }
+    }
+
+    /// Names the format in a sentence: "this looks like {description}".
+    pub fn description(self) -> &'static str {
+        match self {
+            Unsupported::Zip => "a ZIP archive",
+            Unsupported::Gzip => "gzip-compressed data",
+            Unsupported::Pdf => "a PDF document",
+            Unsupported::Sqlite => "an SQLite database",
+            Unsupported::Xml => "an XML document",
+            Unsupported::Yaml => "a YAML document",
+            Unsupported::Hdf5 => "an HDF5 file",
+            Unsupported::LegacyOffice => "a legacy Excel workbook (.xls) or other Office document",
+        }
+    }
+}
+
+/// What one rule found: a format, a recognized but unsupported format, or neither with the
+/// reason the rule did not match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evidence {
+    pub rule: Rule,
+    pub format: Option<Format>,
+    pub unsupported: Option<Unsupported>,
+    pub detail: String,
+}
+
+impl Evidence {
+    fn found(rule: Rule, format: Format, detail: impl Into<String>) -> Self {
+        Self {
+            rule,
+            format: Some(format),
+            unsupported: None,
+            detail: detail.into(),
+        }
+    }
+
+    fn missed(rule: Rule, detail: impl Into<String>) -> Self {
+        Self {
+            rule,
+            format: None,
+            unsupported: None,
+            detail: detail.into(),
+        }
+    }
+
+    fn recognized(rule: Rule, unsupported: Unsupported, detail: impl Into<String>) -> Self {
+        Self {
+            rule,
+            format: None,
+            unsupported: Some(unsupported),
+            detail: detail.into(),
+        }
+    }
+
+    /// Whether the rule decided the input: it found a format, or one it cannot read.
+    pub fn is_match(&self) -> bool {
+        self.format.is_some() || self.unsupported.is_some()
+    }
+
+    /// How reliable the rule's answer is on its own. Tables and magic numbers are
+    /// unambiguous; a Parquet footer, a decoding MessagePack/CBOR map, an extension and a
+    /// content sniff can each be coincidence.
+    pub fn confidence(&self) -> Confidence {
+        match (self.rule, self.format) {
+            (Rule::Table, _) => Confidence::High,
+            (Rule::Magic, Some(Format::MessagePack | Format::Cbor)) => Confidence::Medium,
+            (Rule::Magic, _) => Confidence::High,
+            (Rule::Footer | Rule::Extension | Rule::Content, _) => Confidence::Medium,
+        }
+    }
+}
+
+/// The evidence gathered by format detection, from which the format is chosen.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Detection {
+    /// Every rule that ran, in order.
+    pub evidence: Vec<Evidence>,
+}
+
+impl Detection {
+    /// The evidence that decided the format: the first rule that matched.
+    pub fn chosen(&self) -> Option<&Evidence> {
+        self.evidence.iter().find(|evidence| evidence.is_match())
+    }
+
+    pub fn format(&self) -> Option<Format> {
+        self.chosen().and_then(|evidence| evidence.format)
+    }
+
+    /// The format the input was recognized as when it cannot be read.
+    pub fn unsupported(&self) -> Option<Unsupported> {
+        self.chosen().and_then(|evidence| evidence.unsupported)
+    }
+
+    /// Evidence for a different format than the chosen one.
+    pub fn conflicts(&self) -> Vec<&Evidence> {
+        let (format, unsupported) = (self.format(), self.unsupported());
+        self.evidence
+            .iter()
+            .filter(|evidence| {
+                evidence.is_match()
+                    && (evidence.format != format || evidence.unsupported != unsupported)
+            })
+            .collect()
+    }
+
+    /// The chosen rule's confidence, lowered a level when a rule at least as reliable
+    /// disagrees, or raised a level when another rule agrees.
+    pub fn confidence(&self) -> Option<Confidence> {
+        let chosen = self.chosen()?;
+        let base = chosen.confidence();
+        let disagrees = self
+            .conflicts()
+            .iter()
+            .any(|evidence| evidence.confidence() >= base);
+        let agrees = self.evidence.iter().any(|evidence| {
+            evidence.rule != chosen.rule
+                && evidence.is_match()
+                && evidence.format == chosen.format
+                && evidence.unsupported == chosen.unsupported
+        });
+        Some(if disagrees {
+            base.lower()
+        } else if agrees {
+            base.raise()
+        } else {
+            base
+        })
+    }
+
+    /// The chosen format, or an [`UnsupportedFormatError`] naming the unsupported format the
+    /// input looks like, or else listing the rules tried.
+    pub fn into_format(self) -> Result<Format> {
+        if let Some(format) = self.format() {
+            return Ok(format);
+        }
+        if let Some(chosen) = self.chosen()
+            && let Some(unsupported) = chosen.unsupported
+        {
+            bail!(UnsupportedFormatError::new(format!(
+                "this looks like {} ({}), which megrez cannot read",
+                unsupported.description(),
+                chosen.detail
+            )));
+        }
+        let tried: Vec<&str> = self
+            .evidence
+            .iter()
+            .filter(|evidence| evidence.rule != Rule::Table)
+            .map(|evidence| evidence.rule.attempt())
+            .collect();
+        bail!(UnsupportedFormatError::new(format!(
+            "format detection failed (tried: {})",
+            tried.join(", ")
+        )))
+    }
+
+    /// Whether the extension of `path` names a different format than the content rules
+    /// (magic, footer, content sniff) find. Table layouts are not checked, since an Iceberg
+    /// `metadata.json` is JSON by extension; neither are files without a known extension.
+    pub fn mismatch(&self, path: &Path) -> Option<Mismatch> {
+        if self.chosen()?.rule == Rule::Table {
+            return None;
+        }
+        let expected = self.found_by(Rule::Extension)?;
+        let content = self.evidence.iter().find(|evidence| {
+            evidence.is_match()
+                && matches!(evidence.rule, Rule::Magic | Rule::Footer | Rule::Content)
+        });
+        // With nothing found, the content sniff says why (binary data, not CSV, ...).
+        let content = content.or_else(|| {
+            self.evidence
+                .iter()
+                .find(|evidence| evidence.rule == Rule::Content)
+        });
+        let found = content.and_then(|evidence| evidence.format);
+        if found == Some(expected) {
+            return None;
+        }
+        Some(Mismatch {
+            path: path.to_path_buf(),
+            extension: path.extension()?.to_string_lossy().into_owned(),
+            expected,
+            found,
+            unsupported: content.and_then(|evidence| evidence.unsupported),
+            detail: content.map(|evidence| evidence.detail.clone()),
+        })
+    }
+
+    fn found_by(&self, rule: Rule) -> Option<Format> {
+        self.evidence
+            .iter()
+            .find(|evidence| evidence.rule == rule)
+            .and_then(|evidence| evidence.format)
+    }
+
+    fn push(&mut self, evidence: Evidence) {
+        self.evidence.push(evidence);
+    }
+}
+
+/// A file whose extension names a different format than its content, such as a `.json` file
+/// holding CSV or a `.avro` file holding Parquet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub path: PathBuf,
+    /// The extension as written, without the dot.
+    pub extension: String,
+    /// The format the extension names.
+    pub expected: Format,
+    /// The format the content was detected as, if any.
+    pub found: Option<Format>,
+    /// The unsupported format the content was recognized as, if any.
+    pub unsupported: Option<Unsupported>,
+    /// What the deciding content rule found.
+    pub detail: Option<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: `.{}` extension but ",
+            self.path.display(),
+            self.extension
+        )?;
+        match (self.found, self.unsupported) {
+            (Some(format), _) => write!(f, "the content is {}", format.as_str())?,
+            (None, Some(unsupported)) => write!(f, "the content is {}", unsupported.description())?,
+            (None, None) => write!(f, "the content is not {}", self.expected.as_str())?,
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, " ({detail})")?;
+        }
+        Ok(())
+    }
+}
+
+/// What to do when a file's extension and content name different formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MismatchPolicy {
+    /// Trust detection as usual: magic bytes over the extension, the extension over content.
+    #[default]
+    Ignore,
+    /// Read the file as detected, and report the mismatch.
+    Warn,
+    /// Refuse to read the file.
+    Error,
+}
+
+impl MismatchPolicy {
+    /// Returns a mismatch to report, or an [`UnsupportedFormatError`] under [`Self::Error`].
+    pub fn apply(self, mismatch: Option<Mismatch>) -> Result<Option<Mismatch>> {
+        match (self, mismatch) {
+            (MismatchPolicy::Ignore, _) | (_, None) => Ok(None),
+            (MismatchPolicy::Warn, mismatch) => Ok(mismatch),
+            (MismatchPolicy::Error, Some(mismatch)) => bail!(UnsupportedFormatError::new(format!(
+                "format mismatch: {mismatch}"
+            ))),
+        }
+    }
+}
 
 pub fn detect_format(path: &Path) -> Result<Format> {
+    detect(path, false)?.into_format()
+}
+
+/// Like [`detect_format`], also checking the extension against the content unless `policy`
+/// ignores mismatches. Under [`MismatchPolicy::Warn`] the mismatch is returned for reporting.
+pub fn detect_format_checked(
+    path: &Path,
+    policy: MismatchPolicy,
+) -> Result<(Format, Option<Mismatch>)> {
+    if policy == MismatchPolicy::Ignore {
+        return Ok((detect_format(path)?, None));
+    }
+    let detection = explain_format(path)?;
+    let mismatch = policy.apply(detection.mismatch(path))?;
+    Ok((detection.into_format()?, mismatch))
+}
+
+/// Checks the extension of `path` against its content under `policy`, without detecting the
+/// format again.
+pub fn check_extension(path: &Path, policy: MismatchPolicy) -> Result<Option<Mismatch>> {
+    if policy == MismatchPolicy::Ignore {
+        return Ok(None);
+    }
+    policy.apply(explain_format(path)?.mismatch(path))
+}
+
+/// Runs every detection rule instead of stopping at the first match, so that evidence for
+/// other formats (such as a `.avro` extension on a Parquet file) is reported too.
+pub fn explain_format(path: &Path) -> Result<Detection> {
+    detect(path, true)
+}
+
+fn detect(path: &Path, exhaustive: bool) -> Result<Detection> {
     if !path.exists() {
         bail!("input file does not exist");
     }
-    let mut tried = Vec::new();
+    let rules: [fn(&Path) -> Evidence; 5] = [
+        table_evidence,
+        magic_evidence,
+        footer_evidence,
+        extension_evidence,
+        file_content_evidence,
+    ];
+    let mut detection = Detection::default();
+    for rule in rules {
+        if !exhaustive && detection.chosen().is_some() {
+            break;
+        }
+        detection.push(rule(path));
+    }
+    Ok(detection)
+}
 
-    if let Ok(format) = detect_by_magic(path) {
-        return Ok(format);
+/// Detects a table format from a directory's layout, or an Iceberg metadata file's name.
+pub fn detect_table_format(path: &Path) -> Option<Format> {
+    if delta::is_table(path) {
+        return Some(Format::Delta);
+    }
+    if iceberg::is_table(path) {
+        return Some(Format::Iceberg);
     }
+    None
+}
 
-    if let Some(format) = detect_by_extension(path) {
-        return Ok(format);
+pub fn detect_format_prefix(prefix: &[u8]) -> Result<Format> {
+    let detection = explain_format_prefix(prefix);
+    if detection.format().is_none() && prefix.iter().all(u8::is_ascii_whitespace) {
+        bail!(UnsupportedFormatError::new(
+            "stdin format detection failed (empty or whitespace-only input)"
+        ));
     }
+    detection.into_format()
+}
 
-    tried.push("magic bytes");
-    tried.push("file extension");
+/// Like [`explain_format`] for the first bytes of input that cannot be read twice, such as
+/// stdin: only the magic and content rules apply.
+pub fn explain_format_prefix(prefix: &[u8]) -> Detection {
+    let mut rejected = None;
+    let mut magic = None;
+    if let Some((bytes, format)) = leading_magic(prefix) {
+        let detail = format!("leading bytes `{}`", bytes.escape_ascii());
+        match prefix_problem(prefix, format) {
+            None => magic = Some(Evidence::found(Rule::Magic, format, detail)),
+            Some(problem) => rejected = Some(format!("{detail} but {problem}")),
+        }
+    }
+    let magic = magic
+        .or_else(|| unsupported_magic(prefix))
+        .or_else(|| binary_json_evidence(prefix))
+        .or_else(|| {
+            spreadsheet::is_workbook_prefix(prefix).then(|| {
+                Evidence::found(
+                    Rule::Magic,
+                    Format::Spreadsheet,
+                    "ZIP archive naming workbook entries",
+                )
+            })
+        })
+        .or_else(|| zip_evidence(prefix))
+        .unwrap_or_else(|| {
+            let detail = rejected.unwrap_or_else(|| "no known leading bytes".to_string());
+            Evidence::missed(Rule::Magic, detail)
+        });
+    Detection {
+        evidence: vec![magic, content_evidence(prefix)],
+    }
+}
 
-    tried.push("content sniff");
-    if let Ok(format) = detect_by_content(path) {
-        return Ok(format);
+fn table_evidence(path: &Path) -> Evidence {
+    match detect_table_format(path) {
+        Some(Format::Delta) => Evidence::found(
+            Rule::Table,
+            Format::Delta,
+            format!("directory has a `{}/` transaction log", delta::LOG_DIR),
+        ),
+        Some(format) => Evidence::found(Rule::Table, format, "Iceberg table metadata"),
+        None => Evidence::missed(Rule::Table, "not a Delta Lake or Iceberg table"),
     }
+}
 
-    let details = format!("format detection failed (tried: {})", tried.join(", "));
-    bail!(UnsupportedFormatError::new(details));
+fn magic_evidence(path: &Path) -> Evidence {
+    read_magic(path).unwrap_or_else(|err| Evidence::missed(Rule::Magic, format!("{err:#}")))
 }
 
-pub fn detect_format_prefix(prefix: &[u8]) -> Result<Format> {
-    if prefix.len() >= 4 {
-        if &prefix[..4] == MAGIC_PARQUET {
-            return Ok(Format::Parquet);
-        }
-        if &prefix[..4] == MAGIC_AVRO {
-            return Ok(Format::Avro);
+fn read_magic(path: &Path) -> Result<Evidence> {
+    let mut file = File::open(path).context("open file for format detection")?;
+    let mut prefix = vec![0u8; SNIFF_LEN];
+    let read = read_up_to(&mut file, &mut prefix).context("read magic bytes")?;
+    let prefix = &prefix[..read];
+    // A magic number that fails its structural check (a CSV file whose first column is
+    // `ORCHESTRA`, say) falls through to the other rules.
+    let mut rejected = None;
+    if let Some((bytes, format)) = leading_magic(prefix) {
+        let detail = format!("leading bytes `{}`", bytes.escape_ascii());
+        match file_problem(&mut file, format)? {
+            None => return Ok(Evidence::found(Rule::Magic, format, detail)),
+            Some(problem) => rejected = Some(format!("{detail} but {problem}")),
         }
     }
-    if prefix.len() >= 3 && &prefix[..3] == MAGIC_ORC {
-        return Ok(Format::Orc);
+    if let Some(evidence) = unsupported_magic(prefix) {
+        return Ok(evidence);
     }
-    if let Some(byte) = prefix.iter().copied().find(|b| !b.is_ascii_whitespace()) {
-        if byte == b'{' || byte == b'[' {
-            return Ok(Format::Json);
+    if let Some(evidence) = binary_json_evidence(prefix) {
+        // Decoding maps are weak evidence: text that happens to decode keeps the format its
+        // extension and content sniff agree on.
+        let text =
+            detect_by_extension(path).filter(|format| matches!(format, Format::Json | Format::Csv));
+        if let Some(format) = text
+            && content_evidence(prefix).format == Some(format)
+        {
+            let detail = format!(
+                "{} but the extension and content sniff find {}",
+                evidence.detail,
+                format.as_str()
+            );
+            return Ok(Evidence::missed(Rule::Magic, detail));
         }
-        return Ok(Format::Csv);
+        return Ok(evidence);
     }
-    bail!(UnsupportedFormatError::new(
-        "stdin format detection failed (empty or whitespace-only input)"
-    ))
+    // Other ZIP archives (such as .docx) share the magic, so the workbook entries are checked.
+    if prefix.starts_with(MAGIC_ZIP) {
+        file.seek(SeekFrom::Start(0))
+            .context("seek to start of file")?;
+        if spreadsheet::is_workbook(BufReader::new(&mut file)) {
+            let detail = "ZIP archive with workbook entries";
+            return Ok(Evidence::found(Rule::Magic, Format::Spreadsheet, detail));
+        }
+    }
+    if let Some(evidence) = zip_evidence(prefix) {
+        return Ok(evidence);
+    }
+    let detail = rejected.unwrap_or_else(|| "no known leading bytes".to_string());
+    Ok(Evidence::missed(Rule::Magic, detail))
 }
 
-fn detect_by_magic(path: &Path) -> Result<Format> {
-    let mut file = File::open(path).context("open file for format detection")?;
-    let mut buf = [0u8; 4];
-    let read = file.read(&mut buf).context("read magic bytes")?;
-    if read >= 4 {
-        if &buf == MAGIC_PARQUET {
-            return Ok(Format::Parquet);
+fn leading_magic(prefix: &[u8]) -> Option<(&'static [u8], Format)> {
+    let magic: [(&'static [u8], Format); 5] = [
+        (MAGIC_ARROW_FILE, Format::ArrowIpc),
+        (MAGIC_ARROW_STREAM, Format::ArrowIpc),
+        (MAGIC_PARQUET, Format::Parquet),
+        (MAGIC_AVRO, Format::Avro),
+        (MAGIC_ORC, Format::Orc),
+    ];
+    magic
+        .into_iter()
+        .find(|(bytes, _)| prefix.starts_with(bytes))
+}
+
+// Why a file with `format`'s magic number is not structurally that format, if it is not.
+// Arrow and Parquet magic numbers are long enough to trust on their own.
+fn file_problem(file: &mut File, format: Format) -> Result<Option<&'static str>> {
+    match format {
+        Format::Orc => {
+            let valid = has_orc_postscript(file).context("read ORC postscript")?;
+            Ok((!valid).then_some("no ORC postscript at the end"))
         }
-        if &buf == MAGIC_AVRO {
-            return Ok(Format::Avro);
+        Format::Avro => {
+            file.seek(SeekFrom::Start(MAGIC_AVRO.len() as u64))
+                .context("seek to Avro header")?;
+            let valid = match has_avro_header(&mut BufReader::new(file)) {
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => false,
+                header => header.context("read Avro header")?,
+            };
+            Ok((!valid).then_some("no Avro header metadata"))
         }
+        _ => Ok(None),
     }
-    if read >= 3 && &buf[..3] == MAGIC_ORC {
-        return Ok(Format::Orc);
+}
+
+// As `file_problem`, for input whose end cannot be read. Stdin cannot be read as ORC anyway,
+// so ORC magic is only trusted in binary data, which text starting with `ORC` is not.
+fn prefix_problem(prefix: &[u8], format: Format) -> Option<&'static str> {
+    match format {
+        Format::Orc if !prefix.contains(&0) => Some("the data is text"),
+        Format::Avro => {
+            let valid = match has_avro_header(&mut &prefix[MAGIC_AVRO.len()..]) {
+                Ok(valid) => valid,
+                // The header may run past a full prefix.
+                Err(_) => prefix.len() >= SNIFF_LEN,
+            };
+            (!valid).then_some("no Avro header metadata")
+        }
+        _ => None,
     }
+}
 
-    if has_parquet_footer(&mut file)? {
-        return Ok(Format::Parquet);
+// Magic numbers of formats that cannot be read, so that errors can name them. ZIP archives
+// are left to `zip_evidence`, as workbooks are ZIP archives too.
+fn unsupported_magic(prefix: &[u8]) -> Option<Evidence> {
+    let magic: [(&'static [u8], Unsupported); 4] = [
+        (MAGIC_GZIP, Unsupported::Gzip),
+        (MAGIC_PDF, Unsupported::Pdf),
+        (MAGIC_SQLITE, Unsupported::Sqlite),
+        (MAGIC_CFB, Unsupported::LegacyOffice),
+    ];
+    if let Some((bytes, unsupported)) = magic
+        .into_iter()
+        .find(|(bytes, _)| prefix.starts_with(bytes))
+    {
+        let detail = format!("leading bytes `{}`", bytes.escape_ascii());
+        return Some(Evidence::recognized(Rule::Magic, unsupported, detail));
     }
+    let offset = HDF5_OFFSETS.into_iter().find(|offset| {
+        prefix
+            .get(*offset..)
+            .is_some_and(|rest| rest.starts_with(MAGIC_HDF5))
+    })?;
+    let detail = format!("HDF5 signature at byte {offset}");
+    Some(Evidence::recognized(Rule::Magic, Unsupported::Hdf5, detail))
+}
+
+// A ZIP archive that is not a workbook.
+fn zip_evidence(prefix: &[u8]) -> Option<Evidence> {
+    let bytes = [MAGIC_ZIP, MAGIC_ZIP_EMPTY]
+        .into_iter()
+        .find(|bytes| prefix.starts_with(*bytes))?;
+    let detail = format!("leading bytes `{}`", bytes.escape_ascii());
+    Some(Evidence::recognized(Rule::Magic, Unsupported::Zip, detail))
+}
 
-    bail!("no magic match")
+// The leading map of a MessagePack or CBOR record stream.
+fn binary_json_evidence(prefix: &[u8]) -> Option<Evidence> {
+    let encoding = binary_json::detect_prefix(prefix)?;
+    let detail = format!("leading bytes decode as a {} map", encoding.name());
+    Some(Evidence::found(Rule::Magic, encoding.format(), detail))
 }
 
-fn has_parquet_footer(file: &mut File) -> Result<bool> {
+// An ORC file ends with its PostScript, then one byte giving the PostScript's length.
+fn has_orc_postscript(file: &mut File) -> Result<bool> {
     let len = file.metadata().context("read file metadata")?.len();
-    if len < 8 {
+    if len < MAGIC_ORC.len() as u64 + 1 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut ps_len = [0u8; 1];
+    file.read_exact(&mut ps_len)?;
+    let ps_len = u64::from(ps_len[0]);
+    if ps_len < ORC_POSTSCRIPT_MAGIC.len() as u64 || ps_len + 1 > len - MAGIC_ORC.len() as u64 {
         return Ok(false);
     }
-    file.seek(SeekFrom::End(-4))
+    file.seek(SeekFrom::End(-1 - ps_len as i64))?;
+    let mut postscript = vec![0u8; ps_len as usize];
+    file.read_exact(&mut postscript)?;
+    Ok(postscript
+        .windows(ORC_POSTSCRIPT_MAGIC.len())
+        .any(|window| window == ORC_POSTSCRIPT_MAGIC))
+}
+
+// After the magic, an Avro header is a metadata map (blocks of string keys and bytes values,
+// ending with an empty block) holding `avro.schema`, then a 16-byte sync marker. Running out
+// of input is an `UnexpectedEof` error.
+fn has_avro_header(reader: &mut impl Read) -> std::io::Result<bool> {
+    let mut has_schema = false;
+    loop {
+        let Some(count) = read_avro_long(reader)? else {
+            return Ok(false);
+        };
+        if count == 0 {
+            break;
+        }
+        // A negative count is followed by the block's size in bytes.
+        if count < 0 && read_avro_long(reader)?.is_none_or(|size| size < 0) {
+            return Ok(false);
+        }
+        for _ in 0..count.unsigned_abs() {
+            let Some(key_len) = read_avro_long(reader)? else {
+                return Ok(false);
+            };
+            let Ok(key_len) = u64::try_from(key_len) else {
+                return Ok(false);
+            };
+            if key_len > AVRO_MAX_KEY_LEN {
+                return Ok(false);
+            }
+            let mut key = vec![0u8; key_len as usize];
+            reader.read_exact(&mut key)?;
+            has_schema |= key == b"avro.schema";
+            let Some(value_len) = read_avro_long(reader)? else {
+                return Ok(false);
+            };
+            let Ok(value_len) = u64::try_from(value_len) else {
+                return Ok(false);
+            };
+            skip(reader, value_len)?;
+        }
+    }
+    skip(reader, AVRO_SYNC_LEN)?;
+    Ok(has_schema)
+}
+
+// A zigzag varint, or `None` if it runs past ten bytes.
+fn read_avro_long(reader: &mut impl Read) -> std::io::Result<Option<i64>> {
+    let mut value = 0u64;
+    for shift in (0..70).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some((value >> 1) as i64 ^ -((value & 1) as i64)));
+        }
+    }
+    Ok(None)
+}
+
+fn skip(reader: &mut impl Read, len: u64) -> std::io::Result<()> {
+    let skipped = std::io::copy(&mut reader.take(len), &mut std::io::sink())?;
+    if skipped < len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+fn footer_evidence(path: &Path) -> Evidence {
+    let footer = File::open(path)
+        .context("open file for format detection")
+        .and_then(|mut file| read_parquet_footer(&mut file));
+    match footer {
+        Ok(Footer::Valid) => {
+            Evidence::found(Rule::Footer, Format::Parquet, "trailing bytes `PAR1`")
+        }
+        Ok(Footer::Missing) => Evidence::missed(Rule::Footer, "no Parquet footer"),
+        Ok(Footer::BadLength(footer_len)) => Evidence::missed(
+            Rule::Footer,
+            format!("trailing bytes `PAR1` but footer length {footer_len} does not fit the file"),
+        ),
+        Err(err) => Evidence::missed(Rule::Footer, format!("{err:#}")),
+    }
+}
+
+// Fills as much of `buf` as the file allows; a single `read` may return less.
+fn read_up_to(file: &mut File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        let read = file.read(&mut buf[total..])?;
+        if read == 0 {
+            break;
+        }
+        total += read;
+    }
+    Ok(total)
+}
+
+enum Footer {
+    Valid,
+    Missing,
+    /// The footer length before the trailing magic: zero, or too long for the file.
+    BadLength(u32),
+}
+
+// A Parquet file ends with its footer, the footer's length (4 bytes, little-endian) and `PAR1`.
+fn read_parquet_footer(file: &mut File) -> Result<Footer> {
+    let len = file.metadata().context("read file metadata")?.len();
+    // Leading magic, footer length and trailing magic.
+    let overhead = 2 * MAGIC_PARQUET.len() as u64 + 4;
+    if len < overhead {
+        return Ok(Footer::Missing);
+    }
+    file.seek(SeekFrom::End(-8))
         .context("seek parquet footer")?;
-    let mut buf = [0u8; 4];
-    let read = file.read(&mut buf).context("read parquet footer")?;
-    Ok(read == 4 && &buf == MAGIC_PARQUET)
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf).context("read parquet footer")?;
+    if &buf[4..] != MAGIC_PARQUET {
+        return Ok(Footer::Missing);
+    }
+    let footer_len = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    if footer_len == 0 || u64::from(footer_len) > len - overhead {
+        return Ok(Footer::BadLength(footer_len));
+    }
+    Ok(Footer::Valid)
+}
+
+fn extension_evidence(path: &Path) -> Evidence {
+    let Some(ext) = path.extension() else {
+        return Evidence::missed(Rule::Extension, "no extension");
+    };
+    let ext = ext.to_string_lossy();
+    let detail = format!("`.{ext}` extension");
+    match detect_by_extension(path) {
+        Some(format) => Evidence::found(Rule::Extension, format, detail),
+        None => match unsupported_by_extension(&ext) {
+            Some(unsupported) => Evidence::recognized(Rule::Extension, unsupported, detail),
+            None => Evidence::missed(
+                Rule::Extension,
+                format!("`.{ext}` is not a known extension"),
+            ),
+        },
+    }
+}
+
+fn unsupported_by_extension(ext: &str) -> Option<Unsupported> {
+    match ext.to_ascii_lowercase().as_str() {
+        "zip" => Some(Unsupported::Zip),
+        "gz" | "gzip" => Some(Unsupported::Gzip),
+        "pdf" => Some(Unsupported::Pdf),
+        "sqlite" | "sqlite3" => Some(Unsupported::Sqlite),
+        "xml" => Some(Unsupported::Xml),
+        "yaml" | "yml" => Some(Unsupported::Yaml),
+        "h5" | "hdf5" | "he5" => Some(Unsupported::Hdf5),
+        "xls" => Some(Unsupported::LegacyOffice),
+        _ => None,
+    }
 }
 
 fn detect_by_extension(path: &Path) -> Option<Format> {
@@ -104,30 +846,112 @@ fn detect_by_extension(path: &Path) -> Option<Format> {
         "parquet" => Some(Format::Parquet),
         "avro" => Some(Format::Avro),
         "orc" => Some(Format::Orc),
+        "arrow" | "arrows" | "feather" | "ipc" => Some(Format::ArrowIpc),
+        "xlsx" | "xlsm" | "xlsb" | "ods" => Some(Format::Spreadsheet),
+        "msgpack" | "mpk" => Some(Format::MessagePack),
+        "cbor" => Some(Format::Cbor),
         _ => None,
     }
 }
 
-fn detect_by_content(path: &Path) -> Result<Format> {
-    let file = File::open(path).context("open file for content detection")?;
-    let mut reader = BufReader::new(file);
-    let first = peek_first_non_ws(&mut reader)?;
+fn file_content_evidence(path: &Path) -> Evidence {
+    let prefix = File::open(path)
+        .context("open file for content detection")
+        .and_then(|mut file| {
+            let mut prefix = vec![0u8; SNIFF_LEN];
+            let read = read_up_to(&mut file, &mut prefix).context("read leading bytes")?;
+            prefix.truncate(read);
+            Ok(prefix)
+        });
+    match prefix {
+        Ok(prefix) => content_evidence(&prefix),
+        Err(err) => Evidence::missed(Rule::Content, format!("{err:#}")),
+    }
+}
 
-    if let Some(byte) = first
-        && (byte == b'{' || byte == b'[')
-    {
-        return Ok(Format::Json);
+// Sniffs text after decoding UTF-16 and removing a byte order mark, noting the encoding.
+fn content_evidence(prefix: &[u8]) -> Evidence {
+    let Some((text, encoding)) = encoding::decode_prefix(prefix) else {
+        return text_evidence(prefix);
+    };
+    let mut evidence = text_evidence(&text);
+    if evidence.is_match() {
+        let bom = if Encoding::for_bom(prefix).is_some() {
+            " with BOM"
+        } else {
+            ""
+        };
+        evidence.detail = format!("{} ({}{bom})", evidence.detail, encoding.name());
     }
+    evidence
+}
 
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_reader(reader);
-    let mut record = csv::StringRecord::new();
-    if csv_reader.read_record(&mut record)? && !record.is_empty() {
-        return Ok(Format::Csv);
+// JSON if the first non-blank byte opens an object or array, else CSV if a first record
+// parses. Markup and YAML document markers are recognized first, since they would parse as
+// CSV too. NUL bytes do not occur in JSON or CSV text, so they mark binary data.
+fn text_evidence(prefix: &[u8]) -> Evidence {
+    let Some(start) = prefix.iter().position(|b| !b.is_ascii_whitespace()) else {
+        return Evidence::missed(Rule::Content, "empty or whitespace-only");
+    };
+    let first = prefix[start];
+    if prefix.contains(&0) {
+        return Evidence::missed(Rule::Content, "binary data");
+    }
+    if first == b'{' || first == b'[' {
+        let detail = format!("first non-blank character is `{}`", first as char);
+        return Evidence::found(Rule::Content, Format::Json, detail);
+    }
+    if let Some(evidence) = markup_evidence(&prefix[start..]) {
+        return evidence;
     }
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(prefix);
+    let mut record = csv::ByteRecord::new();
+    match reader.read_byte_record(&mut record) {
+        Ok(true) if !record.is_empty() => {
+            let detail = format!("first line parses as CSV with {} fields", record.len());
+            Evidence::found(Rule::Content, Format::Csv, detail)
+        }
+        Ok(_) => Evidence::missed(Rule::Content, "no CSV record"),
+        Err(err) => Evidence::missed(Rule::Content, format!("not CSV: {err}")),
+    }
+}
 
-    bail!("unrecognized content")
+// XML (a declaration, or a tag at the start) and YAML (a `---` or `%YAML` first line), from
+// the text after leading whitespace.
+fn markup_evidence(text: &[u8]) -> Option<Evidence> {
+    if text.starts_with(b"<?xml") {
+        let detail = "XML declaration `<?xml`";
+        return Some(Evidence::recognized(
+            Rule::Content,
+            Unsupported::Xml,
+            detail,
+        ));
+    }
+    if text.starts_with(b"<")
+        && text
+            .get(1)
+            .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'!')
+    {
+        let detail = "first non-blank character is `<`";
+        return Some(Evidence::recognized(
+            Rule::Content,
+            Unsupported::Xml,
+            detail,
+        ));
+    }
+    let line = text.split(|b| *b == b'\n').next().unwrap_or_default();
+    let line = line.trim_ascii_end();
+    if line == b"---" || line.starts_with(b"--- ") || line.starts_with(b"%YAML") {
+        let detail = format!("first line is `{}`", line.escape_ascii());
+        return Some(Evidence::recognized(
+            Rule::Content,
+            Unsupported::Yaml,
+            detail,
+        ));
+    }
+    None
 }
 
 #[cfg(test)]
@@ -168,20 +992,87 @@ mod tests {
     fn detect_by_footer_parquet() {
         let path = temp_path("parquet_footer");
         let mut file = File::create(&path).expect("create temp file");
-        file.write_all(b"xxxxPAR1").expect("write footer");
+        // Four bytes of footer metadata, then its length and the magic.
+        file.write_all(b"xxxxmeta\x04\0\0\0PAR1")
+            .expect("write footer");
         let format = detect_format(&path).expect("detect format");
         fs::remove_file(&path).ok();
         assert_eq!(format, Format::Parquet);
     }
 
+    #[test]
+    fn parquet_footer_length_must_fit() {
+        let path = temp_path("parquet_bad_footer");
+        fs::write(&path, b"id\n12\n\x01\x01\x01\x01PAR1").expect("write data");
+        let detection = explain_format(&path).expect("explain format");
+        fs::remove_file(&path).ok();
+        assert_eq!(detection.format(), Some(Format::Csv));
+        assert_eq!(
+            detection.evidence[2].detail,
+            "trailing bytes `PAR1` but footer length 16843009 does not fit the file"
+        );
+    }
+
     #[test]
     fn detect_by_magic_avro() {
         let path = temp_path("avro");
         let mut file = File::create(&path).expect("create temp file");
-        file.write_all(b"Obj\x01xxxx").expect("write magic");
+        file.write_all(&avro_header()).expect("write header");
         let format = detect_format(&path).expect("detect format");
         fs::remove_file(&path).ok();
         assert_eq!(format, Format::Avro);
+        assert_eq!(
+            explain_format_prefix(&avro_header()).format(),
+            Some(Format::Avro)
+        );
+
+        let path = temp_path("avro_no_header");
+        fs::write(&path, b"Obj\x01xxxx").expect("write magic");
+        let detection = explain_format(&path).expect("explain format");
+        fs::remove_file(&path).ok();
+        assert_eq!(
+            detection.evidence[1].detail,
+            "leading bytes `Obj\\x01` but no Avro header metadata"
+        );
+        assert_eq!(
+            explain_format_prefix(b"Obj\x01xxxx").format(),
+            Some(Format::Csv)
+        );
+    }
+
+    // One block with the schema entry, the end of the map and a sync marker.
+    fn avro_header() -> Vec<u8> {
+        let mut header = b"Obj\x01\x02\x16avro.schema\x0c\"null\"\0".to_vec();
+        header.extend([0xAB; 16]);
+        header
+    }
+
+    #[test]
+    fn orc_magic_needs_a_postscript() {
+        let path = temp_path("orc_text");
+        fs::write(&path, b"ORCHESTRA,SEATS\nberlin,100\n").expect("write csv");
+        let detection = explain_format(&path).expect("explain format");
+        fs::remove_file(&path).ok();
+        assert_eq!(detection.format(), Some(Format::Csv));
+        assert_eq!(
+            detection.evidence[1].detail,
+            "leading bytes `ORC` but no ORC postscript at the end"
+        );
+        assert_eq!(
+            explain_format_prefix(b"ORCHESTRA,SEATS\n").format(),
+            Some(Format::Csv)
+        );
+
+        // Footer length 0, no compression, then the magic field; the last byte is its length.
+        let path = temp_path("orc");
+        let postscript = b"\x08\x00\x10\x00\x82\xf4\x03\x03ORC";
+        let mut data = b"ORC\0stripes".to_vec();
+        data.extend(postscript);
+        data.push(postscript.len() as u8);
+        fs::write(&path, data).expect("write orc");
+        let format = detect_format(&path).expect("detect format");
+        fs::remove_file(&path).ok();
+        assert_eq!(format, Format::Orc);
     }
 
     #[test]
@@ -194,6 +1085,128 @@ mod tests {
         assert_eq!(format, Format::Csv);
     }
 
+    #[test]
+    fn explain_reports_conflicts_and_confidence() {
+        let path = temp_path_with_ext("mislabeled", "json");
+        fs::write(&path, b"id,name\n1,a\n").expect("write csv");
+        let detection = explain_format(&path).expect("explain format");
+        fs::remove_file(&path).ok();
+        assert_eq!(detection.format(), Some(Format::Json));
+        assert_eq!(
+            detection.chosen().map(|chosen| chosen.rule),
+            Some(Rule::Extension)
+        );
+        let conflicts = detection.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rule, Rule::Content);
+        assert_eq!(conflicts[0].format, Some(Format::Csv));
+        assert_eq!(detection.confidence(), Some(Confidence::Low));
+
+        let detection = explain_format_prefix(b"\0\x01binary");
+        assert_eq!(detection.format(), None);
+        assert_eq!(detection.evidence[1].detail, "binary data");
+    }
+
+    #[test]
+    fn strict_detection_checks_extension_against_content() {
+        let path = temp_path_with_ext("mislabeled", "json");
+        fs::write(&path, b"id,name\n1,a\n").expect("write csv");
+        let (format, mismatch) =
+            detect_format_checked(&path, MismatchPolicy::Warn).expect("detect format");
+        let error = detect_format_checked(&path, MismatchPolicy::Error).unwrap_err();
+        let ignored = detect_format_checked(&path, MismatchPolicy::Ignore).expect("detect");
+        fs::remove_file(&path).ok();
+        assert_eq!(format, Format::Json);
+        let mismatch = mismatch.expect("mismatch");
+        assert_eq!(mismatch.expected, Format::Json);
+        assert_eq!(mismatch.found, Some(Format::Csv));
+        assert!(mismatch.to_string().ends_with(
+            "`.json` extension but the content is CSV (first line parses as CSV with 2 fields)"
+        ));
+        assert!(error.downcast_ref::<UnsupportedFormatError>().is_some());
+        assert_eq!(ignored, (Format::Json, None));
+
+        let path = temp_path_with_ext("binary", "msgpack");
+        fs::write(&path, b"\0\0\x01").expect("write binary");
+        let mismatch = check_extension(&path, MismatchPolicy::Warn).expect("check");
+        fs::remove_file(&path).ok();
+        let mismatch = mismatch.expect("mismatch");
+        assert_eq!(mismatch.found, None);
+        assert!(
+            mismatch
+                .to_string()
+                .ends_with("the content is not MSGPACK (binary data)")
+        );
+
+        let path = temp_path_with_ext("labeled", "csv");
+        fs::write(&path, b"id,name\n1,a\n").expect("write csv");
+        let mismatch = check_extension(&path, MismatchPolicy::Error).expect("check");
+        fs::remove_file(&path).ok();
+        assert_eq!(mismatch, None);
+    }
+
+    #[test]
+    fn text_that_decodes_as_msgpack_keeps_its_extension() {
+        // `€uro,amount` in windows-1252 starts with an empty MessagePack map.
+        let path = temp_path_with_ext("cp", "csv");
+        fs::write(&path, b"\x80uro,amount\r\n\x805,10\r\n").expect("write csv");
+        let format = detect_format(&path).expect("detect format");
+        fs::remove_file(&path).ok();
+        assert_eq!(format, Format::Csv);
+
+        // Two maps that decode, in text that parses as CSV.
+        let path = temp_path_with_ext("maps", "csv");
+        fs::write(&path, b"\x81\xa1a1\x81\xa1b2,x\n").expect("write csv");
+        let detection = explain_format(&path).expect("explain format");
+        fs::remove_file(&path).ok();
+        assert_eq!(detection.format(), Some(Format::Csv));
+        assert!(!detection.evidence[1].is_match());
+        assert!(
+            detection.evidence[1]
+                .detail
+                .ends_with("but the extension and content sniff find CSV")
+        );
+        assert_eq!(detection.mismatch(Path::new("maps.csv")), None);
+    }
+
+    #[test]
+    fn recognizes_unsupported_formats() {
+        let mut hdf5 = vec![b'u'; 512];
+        hdf5.extend(MAGIC_HDF5);
+        let cases: [(&[u8], Unsupported); 6] = [
+            (b"SQLite format 3\0page", Unsupported::Sqlite),
+            (b"%PDF-1.7\n", Unsupported::Pdf),
+            (b"PK\x03\x04word/document.xml", Unsupported::Zip),
+            (&hdf5, Unsupported::Hdf5),
+            (b"  <?xml version=\"1.0\"?>\n<rows/>", Unsupported::Xml),
+            (b"---\nname: megrez\n", Unsupported::Yaml),
+        ];
+        for (prefix, unsupported) in cases {
+            let detection = explain_format_prefix(prefix);
+            assert_eq!(detection.unsupported(), Some(unsupported));
+            assert_eq!(detection.format(), None);
+        }
+        let err = detect_format_prefix(b"SQLite format 3\0page").unwrap_err();
+        assert!(err.downcast_ref::<UnsupportedFormatError>().is_some());
+        assert_eq!(
+            err.to_string(),
+            "this looks like an SQLite database (leading bytes `SQLite format 3\\x00`), \
+             which megrez cannot read"
+        );
+
+        // The extension names a format that text content would otherwise be guessed as.
+        let path = temp_path_with_ext("config", "yaml");
+        fs::write(&path, b"name: megrez\n").expect("write yaml");
+        let detection = explain_format(&path).expect("explain format");
+        fs::remove_file(&path).ok();
+        assert_eq!(detection.unsupported(), Some(Unsupported::Yaml));
+        assert_eq!(
+            detection.chosen().map(|chosen| chosen.rule),
+            Some(Rule::Extension)
+        );
+        assert_eq!(detection.conflicts().len(), 1);
+    }
+
     #[test]
     fn detect_by_content_json() {
         let path = temp_path("content");
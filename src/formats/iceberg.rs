@@ -0,0 +1,594 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value as JsonValue;
+
+use crate::formats::Format;
+use crate::formats::avro::AvroSource;
+use crate::formats::parquet::ParquetSource;
+use crate::model::schema::{DataType, Field, Schema};
+use crate::model::table::{TableInfo, TableSnapshot};
+use crate::source::{Record, RecordSource};
+use crate::util::errors::UnsupportedFormatError;
+
+/// Directory holding an Iceberg table's metadata files, manifest lists and manifests.
+pub const METADATA_DIR: &str = "metadata";
+
+const METADATA_SUFFIX: &str = ".metadata.json";
+const VERSION_HINT: &str = "version-hint.text";
+
+/// Parquet metadata key holding the Iceberg field ID of a column.
+const PARQUET_FIELD_ID: &str = "PARQUET:field_id";
+
+/// Whether `path` is an Iceberg table directory or one of its `*.metadata.json` files.
+pub fn is_table(path: &Path) -> bool {
+    if path.is_file() {
+        return is_metadata_file(path);
+    }
+    let Ok(entries) = fs::read_dir(path.join(METADATA_DIR)) else {
+        return false;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .any(|entry| is_metadata_file(&entry.path()))
+}
+
+pub fn infer_schema(path: &Path) -> Result<Schema> {
+    Ok(Table::load(path)?.schema)
+}
+
+fn is_metadata_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(METADATA_SUFFIX))
+}
+
+/// An Iceberg table as described by one metadata file: its current schema and partition
+/// spec, its snapshots and the data files of the current snapshot.
+#[derive(Debug)]
+struct Table {
+    root: PathBuf,
+    location: String,
+    schema: Schema,
+    info: TableInfo,
+    manifest_list: Option<String>,
+    /// Manifests listed directly in a format v1 snapshot that has no manifest list.
+    manifests: Vec<String>,
+}
+
+impl Table {
+    fn load(path: &Path) -> Result<Self> {
+        let (root, metadata_path) = if path.is_file() {
+            // `<root>/metadata/vN.metadata.json`
+            let root = path
+                .parent()
+                .and_then(Path::parent)
+                .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+            (root, path.to_path_buf())
+        } else {
+            (path.to_path_buf(), current_metadata_file(path)?)
+        };
+        let text = fs::read_to_string(&metadata_path)
+            .with_context(|| format!("read Iceberg metadata {}", metadata_path.display()))?;
+        let metadata: JsonValue = serde_json::from_str(&text)
+            .with_context(|| format!("parse Iceberg metadata {}", metadata_path.display()))?;
+        Self::from_metadata(root, &metadata)
+    }
+
+    fn from_metadata(root: PathBuf, metadata: &JsonValue) -> Result<Self> {
+        let location = metadata
+            .get("location")
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        // Format v1 has a single `schema` and `partition-spec`; v2 lists them by ID.
+        let schema_json = current_entry(metadata, "schemas", "current-schema-id", "schema-id")
+            .or_else(|| metadata.get("schema"))
+            .context("Iceberg metadata has no current schema")?;
+        let (fields, field_ids) = struct_fields(schema_json)?;
+        let names_by_id: HashMap<i64, &str> = field_ids
+            .iter()
+            .map(|(name, id)| (*id, name.as_str()))
+            .collect();
+        let spec_fields = current_entry(metadata, "partition-specs", "default-spec-id", "spec-id")
+            .and_then(|spec| spec.get("fields"))
+            .or_else(|| metadata.get("partition-spec"))
+            .and_then(JsonValue::as_array);
+        let partitioning = spec_fields
+            .into_iter()
+            .flatten()
+            .map(|field| partition_field(field, &names_by_id))
+            .collect();
+
+        let snapshots: Vec<&JsonValue> = metadata
+            .get("snapshots")
+            .and_then(JsonValue::as_array)
+            .map(|snapshots| snapshots.iter().collect())
+            .unwrap_or_default();
+        let snapshot_id = metadata
+            .get("current-snapshot-id")
+            .and_then(JsonValue::as_i64)
+            .filter(|id| *id != -1);
+        let current = match snapshot_id {
+            Some(id) => Some(
+                snapshots
+                    .iter()
+                    .find(|snapshot| snapshot_id_of(snapshot) == Some(id))
+                    .with_context(|| format!("Iceberg snapshot {id} is not in the metadata"))?,
+            ),
+            None => None,
+        };
+        let manifest_list = current
+            .and_then(|snapshot| snapshot.get("manifest-list"))
+            .and_then(JsonValue::as_str)
+            .map(str::to_string);
+        let manifests = current
+            .and_then(|snapshot| snapshot.get("manifests"))
+            .and_then(JsonValue::as_array)
+            .map(|manifests| {
+                manifests
+                    .iter()
+                    .filter_map(JsonValue::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut history: Vec<TableSnapshot> = snapshots
+            .iter()
+            .filter_map(|snapshot| {
+                Some(TableSnapshot {
+                    id: snapshot_id_of(snapshot)?,
+                    parent_id: snapshot
+                        .get("parent-snapshot-id")
+                        .and_then(JsonValue::as_i64),
+                    timestamp_ms: snapshot
+                        .get("timestamp-ms")
+                        .and_then(JsonValue::as_i64)
+                        .unwrap_or_default(),
+                    operation: snapshot
+                        .pointer("/summary/operation")
+                        .and_then(JsonValue::as_str)
+                        .map(str::to_string),
+                })
+            })
+            .collect();
+        history.sort_by_key(|snapshot| snapshot.timestamp_ms);
+
+        Ok(Self {
+            root,
+            location,
+            schema: Schema { fields },
+            info: TableInfo {
+                snapshot_id,
+                partitioning,
+                field_ids,
+                snapshots: history,
+                ..TableInfo::default()
+            },
+            manifest_list,
+            manifests,
+        })
+    }
+
+    /// The live data files of the current snapshot, in manifest order.
+    fn data_files(&self) -> Result<Vec<PathBuf>> {
+        let mut manifests = Vec::new();
+        if let Some(list) = &self.manifest_list {
+            let path = self.local_path(list)?;
+            let source = AvroSource::open(&path)
+                .with_context(|| format!("read Iceberg manifest list {}", path.display()))?;
+            for entry in source {
+                let entry = entry?;
+                let manifest = entry
+                    .get("manifest_path")
+                    .and_then(JsonValue::as_str)
+                    .context("Iceberg manifest list entry has no manifest_path")?;
+                manifests.push(manifest.to_string());
+            }
+        }
+        manifests.extend(self.manifests.iter().cloned());
+
+        let mut files = Vec::new();
+        for manifest in &manifests {
+            let path = self.local_path(manifest)?;
+            let source = AvroSource::open(&path)
+                .with_context(|| format!("read Iceberg manifest {}", path.display()))?;
+            for entry in source {
+                let entry = entry?;
+                // Status 2 marks a file deleted in this snapshot.
+                if entry.get("status").and_then(JsonValue::as_i64) == Some(2) {
+                    continue;
+                }
+                let data_file = entry
+                    .get("data_file")
+                    .context("Iceberg manifest entry has no data_file")?;
+                if data_file
+                    .get("content")
+                    .and_then(JsonValue::as_i64)
+                    .unwrap_or(0)
+                    != 0
+                {
+                    bail!(UnsupportedFormatError::new(
+                        "Iceberg tables with delete files are not supported"
+                    ));
+                }
+                let file_format = data_file
+                    .get("file_format")
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or("PARQUET");
+                if !file_format.eq_ignore_ascii_case("parquet") {
+                    bail!(UnsupportedFormatError::new(format!(
+                        "Iceberg data files in {file_format} are not supported"
+                    )));
+                }
+                let file_path = data_file
+                    .get("file_path")
+                    .and_then(JsonValue::as_str)
+                    .context("Iceberg data file has no file_path")?;
+                files.push(self.local_path(file_path)?);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Maps a path recorded in the metadata to the local file system. Paths are absolute
+    /// URIs under the table's `location`, which may no longer match where the table is (for
+    /// example after copying it from object storage), so they are resolved against the root
+    /// of the table that was opened.
+    fn local_path(&self, uri: &str) -> Result<PathBuf> {
+        let location = self.location.trim_end_matches('/');
+        if !location.is_empty()
+            && let Some(rest) = uri.strip_prefix(location)
+            && rest.starts_with('/')
+        {
+            return Ok(self.root.join(rest.trim_start_matches('/')));
+        }
+        let path = uri
+            .strip_prefix("file://")
+            .or_else(|| uri.strip_prefix("file:"))
+            .unwrap_or(uri);
+        if path.contains("://") {
+            bail!(UnsupportedFormatError::new(format!(
+                "Iceberg file {uri} is not on the local file system"
+            )));
+        }
+        Ok(PathBuf::from(path))
+    }
+}
+
+// The metadata file named by `version-hint.text`, or else the one with the highest version.
+fn current_metadata_file(root: &Path) -> Result<PathBuf> {
+    let dir = root.join(METADATA_DIR);
+    if let Ok(hint) = fs::read_to_string(dir.join(VERSION_HINT)) {
+        let hint = hint.trim();
+        let path = if hint.parse::<u64>().is_ok() {
+            dir.join(format!("v{hint}{METADATA_SUFFIX}"))
+        } else {
+            dir.join(hint)
+        };
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+    let entries = fs::read_dir(&dir)
+        .with_context(|| format!("read Iceberg metadata directory {}", dir.display()))?;
+    let mut latest: Option<(u64, PathBuf)> = None;
+    for entry in entries {
+        let path = entry.context("read Iceberg metadata entry")?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(stem) = name.strip_suffix(METADATA_SUFFIX) else {
+            continue;
+        };
+        // `v3.metadata.json` (Hadoop tables) or `00003-<uuid>.metadata.json` (catalogs).
+        let digits: String = stem
+            .trim_start_matches('v')
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        let version = digits.parse::<u64>().unwrap_or(0);
+        if latest.as_ref().is_none_or(|(best, _)| version > *best) {
+            latest = Some((version, path));
+        }
+    }
+    latest
+        .map(|(_, path)| path)
+        .with_context(|| format!("no Iceberg metadata files in {}", dir.display()))
+}
+
+// Finds the entry of `list` whose `id_key` equals the metadata's `current_key`.
+fn current_entry<'a>(
+    metadata: &'a JsonValue,
+    list: &str,
+    current_key: &str,
+    id_key: &str,
+) -> Option<&'a JsonValue> {
+    let current = metadata.get(current_key)?.as_i64()?;
+    metadata
+        .get(list)?
+        .as_array()?
+        .iter()
+        .find(|entry| entry.get(id_key).and_then(JsonValue::as_i64) == Some(current))
+}
+
+fn snapshot_id_of(snapshot: &JsonValue) -> Option<i64> {
+    snapshot.get("snapshot-id").and_then(JsonValue::as_i64)
+}
+
+/// Top-level field IDs by name.
+type FieldIds = Vec<(String, i64)>;
+
+fn struct_fields(schema: &JsonValue) -> Result<(Vec<Field>, FieldIds)> {
+    let fields = schema
+        .get("fields")
+        .and_then(JsonValue::as_array)
+        .context("Iceberg struct type has no fields")?;
+    let mut out = Vec::with_capacity(fields.len());
+    let mut ids = Vec::with_capacity(fields.len());
+    for field in fields {
+        let name = field
+            .get("name")
+            .and_then(JsonValue::as_str)
+            .context("Iceberg field has no name")?;
+        if let Some(id) = field.get("id").and_then(JsonValue::as_i64) {
+            ids.push((name.to_string(), id));
+        }
+        out.push(Field {
+            name: name.to_string(),
+            dtype: map_type(field.get("type").unwrap_or(&JsonValue::Null)),
+            nullable: !field
+                .get("required")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(false),
+        });
+    }
+    Ok((out, ids))
+}
+
+// Types follow what the Parquet reader returns for the columns Iceberg writes: decimals and
+// times are not decoded, UUIDs are fixed-length binary and maps are objects.
+fn map_type(dtype: &JsonValue) -> DataType {
+    match dtype {
+        JsonValue::String(name) => match name.as_str() {
+            "boolean" => DataType::Bool,
+            "int" | "long" => DataType::Int,
+            "float" | "double" => DataType::Float,
+            "string" => DataType::String,
+            "binary" | "uuid" => DataType::Bytes,
+            "date" => DataType::Date,
+            "timestamp" | "timestamptz" | "timestamp_ns" | "timestamptz_ns" => DataType::Timestamp,
+            name if name.starts_with("fixed[") => DataType::Bytes,
+            _ => DataType::Unknown,
+        },
+        JsonValue::Object(object) => match object.get("type").and_then(JsonValue::as_str) {
+            Some("struct") | Some("map") => DataType::Struct,
+            Some("list") => DataType::List(Box::new(map_type(
+                object.get("element").unwrap_or(&JsonValue::Null),
+            ))),
+            _ => DataType::Unknown,
+        },
+        _ => DataType::Unknown,
+    }
+}
+
+// Renders a partition field as its source column for identity partitions, otherwise as
+// `transform(column)`, e.g. `day(ts)` or `bucket[16](id)`.
+fn partition_field(field: &JsonValue, names_by_id: &HashMap<i64, &str>) -> String {
+    let source = field
+        .get("source-id")
+        .and_then(JsonValue::as_i64)
+        .and_then(|id| names_by_id.get(&id).copied())
+        .or_else(|| field.get("name").and_then(JsonValue::as_str))
+        .unwrap_or("?");
+    match field.get("transform").and_then(JsonValue::as_str) {
+        None | Some("identity") => source.to_string(),
+        Some(transform) => format!("{transform}({source})"),
+    }
+}
+
+/// Reads the current snapshot of an Iceberg table through the Parquet reader.
+///
+/// Columns are matched to the current schema by field ID where the data files record one,
+/// so renamed columns read under their current name and dropped columns are left out;
+/// columns added after a file was written read as null.
+pub struct IcebergSource {
+    schema: Schema,
+    info: TableInfo,
+    pending: VecDeque<PathBuf>,
+    current: Option<(ParquetSource, Vec<Option<String>>)>,
+}
+
+impl IcebergSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let table = Table::load(path)?;
+        let files = table.data_files()?;
+        Ok(Self {
+            schema: table.schema,
+            info: table.info,
+            pending: files.into(),
+            current: None,
+        })
+    }
+
+    /// Opens the next data file if none is open; false once every file has been read.
+    fn open_next(&mut self) -> Result<bool> {
+        if self.current.is_none() {
+            let Some(path) = self.pending.pop_front() else {
+                return Ok(false);
+            };
+            let source = ParquetSource::open(&path)
+                .with_context(|| format!("open Iceberg data file {}", path.display()))?;
+            let columns = self.file_columns(&source);
+            self.current = Some((source, columns));
+        }
+        Ok(true)
+    }
+
+    // For each field of the table schema, the data file column holding it, if any.
+    fn file_columns(&self, source: &ParquetSource) -> Vec<Option<String>> {
+        let arrow_schema = source.arrow_schema();
+        let by_id: HashMap<i64, &str> = arrow_schema
+            .fields()
+            .iter()
+            .filter_map(|field| {
+                let id = field.metadata().get(PARQUET_FIELD_ID)?.parse().ok()?;
+                Some((id, field.name().as_str()))
+            })
+            .collect();
+        self.schema
+            .fields
+            .iter()
+            .map(|field| {
+                let column = match self.info.field_id(&field.name) {
+                    Some(id) if !by_id.is_empty() => by_id.get(&id).copied(),
+                    _ => arrow_schema
+                        .fields()
+                        .iter()
+                        .find(|column| column.name() == &field.name)
+                        .map(|column| column.name().as_str()),
+                };
+                column.map(str::to_string)
+            })
+            .collect()
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        loop {
+            if !self.open_next()? {
+                return Ok(None);
+            }
+            let Some((source, columns)) = self.current.as_mut() else {
+                continue;
+            };
+            let Some(record) = source.next() else {
+                self.current = None;
+                continue;
+            };
+            let mut record = record?;
+            return Ok(Some(
+                self.schema
+                    .fields
+                    .iter()
+                    .zip(columns.iter())
+                    .map(|(field, column)| {
+                        let value = column
+                            .as_ref()
+                            .and_then(|column| record.remove(column))
+                            .unwrap_or(JsonValue::Null);
+                        (field.name.clone(), value)
+                    })
+                    .collect(),
+            ));
+        }
+    }
+}
+
+impl Iterator for IcebergSource {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.next_record();
+        if record.is_err() {
+            self.pending.clear();
+            self.current = None;
+        }
+        record.transpose()
+    }
+}
+
+impl RecordSource for IcebergSource {
+    fn format(&self) -> Format {
+        Format::Iceberg
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn table_info(&self) -> Option<TableInfo> {
+        Some(self.info.clone())
+    }
+
+    fn skip_records(&mut self, count: usize) -> Result<usize> {
+        let mut skipped = 0;
+        while skipped < count {
+            if !self.open_next()? {
+                break;
+            }
+            let Some((source, _)) = self.current.as_mut() else {
+                break;
+            };
+            skipped += source.skip_records(count - skipped)?;
+            if skipped < count {
+                self.current = None;
+            }
+        }
+        Ok(skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_v2_metadata() {
+        let metadata = serde_json::json!({
+            "format-version": 2,
+            "location": "s3://bucket/db/events",
+            "current-schema-id": 1,
+            "schemas": [
+                {"schema-id": 0, "type": "struct", "fields": [
+                    {"id": 1, "name": "id", "required": true, "type": "long"}
+                ]},
+                {"schema-id": 1, "type": "struct", "fields": [
+                    {"id": 1, "name": "id", "required": true, "type": "long"},
+                    {"id": 2, "name": "ts", "required": false, "type": "timestamptz"},
+                    {"id": 3, "name": "tags", "required": false,
+                     "type": {"type": "list", "element-id": 4, "element": "string", "element-required": false}}
+                ]}
+            ],
+            "default-spec-id": 0,
+            "partition-specs": [{"spec-id": 0, "fields": [
+                {"source-id": 2, "field-id": 1000, "name": "ts_day", "transform": "day"},
+                {"source-id": 1, "field-id": 1001, "name": "id_bucket", "transform": "bucket[16]"}
+            ]}],
+            "current-snapshot-id": 7,
+            "snapshots": [
+                {"snapshot-id": 7, "parent-snapshot-id": 5, "timestamp-ms": 20,
+                 "manifest-list": "s3://bucket/db/events/metadata/snap-7.avro",
+                 "summary": {"operation": "overwrite"}},
+                {"snapshot-id": 5, "timestamp-ms": 10,
+                 "manifest-list": "s3://bucket/db/events/metadata/snap-5.avro",
+                 "summary": {"operation": "append"}}
+            ]
+        });
+        let table = Table::from_metadata(PathBuf::from("/data/events"), &metadata).unwrap();
+        let names: Vec<&str> = table
+            .schema
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(names, ["id", "ts", "tags"]);
+        assert_eq!(
+            table.schema.fields[2].dtype,
+            DataType::List(Box::new(DataType::String))
+        );
+        assert_eq!(table.info.field_id("ts"), Some(2));
+        assert_eq!(table.info.partitioning, ["day(ts)", "bucket[16](id)"]);
+        assert_eq!(table.info.snapshot_id, Some(7));
+        assert_eq!(table.info.snapshots[0].id, 5);
+        assert_eq!(
+            table
+                .local_path(table.manifest_list.as_deref().unwrap())
+                .unwrap(),
+            PathBuf::from("/data/events/metadata/snap-7.avro")
+        );
+    }
+}
@@ -1,16 +1,23 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read};
 use std::path::Path;
+use std::str::FromStr;
 
-use anyhow::{Context, Result};
-use serde::de::{DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
-use serde_json::Value;
+use anyhow::{Context, Result, bail};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde_json::de::IoRead;
+use serde_json::{StreamDeserializer, Value};
 
+use crate::formats::Format;
 use crate::model::schema::{DataType, Field, Schema};
+use crate::source::{Record, RecordSource, into_record};
 use crate::util::io::peek_first_non_ws;
+use crate::util::sample::{SampleOptions, Sampler};
 
-const SAMPLE_LIMIT: usize = 1000;
+/// How many elements of each array are inspected for its element type, unless every record
+/// is (`--full-scan`), when every element is too.
 const ARRAY_SAMPLE_LIMIT: usize = 1000;
 const MAX_DEPTH: usize = 8;
 
@@ -20,98 +27,556 @@ struct FieldInfo {
     nullable: bool,
 }
 
-pub fn infer_schema(path: &Path) -> Result<Schema> {
+/// How records are laid out in JSON input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// A top-level array whose elements are the records.
+    Array,
+    /// Whitespace-separated values, usually one per line (NDJSON / JSON Lines).
+    Lines,
+    /// A single document, read as one record unless `--records-path` selects records inside it.
+    Document,
+}
+
+impl JsonLayout {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JsonLayout::Array => "array",
+            JsonLayout::Lines => "ndjson",
+            JsonLayout::Document => "document",
+        }
+    }
+}
+
+/// Options used when reading JSON input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Location of the records inside an enveloping document, such as `$.data.items`.
+    pub records_path: Option<RecordsPath>,
+}
+
+/// A path to the records inside a JSON document, written `$.data.items` or `$.pages[0].rows`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordsPath {
+    text: String,
+    segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl FromStr for RecordsPath {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid records path `{text}`; expected e.g. `$.data.items`");
+        let mut rest = text.strip_prefix('$').unwrap_or(text);
+        if !rest.is_empty() && !rest.starts_with(['.', '[']) {
+            rest = text;
+            if text.starts_with('$') {
+                return Err(invalid());
+            }
+        }
+        let mut segments = Vec::new();
+        let mut chars = rest.char_indices().peekable();
+        let mut key = None::<String>;
+        while let Some((idx, ch)) = chars.next() {
+            match ch {
+                '.' => {
+                    if let Some(key) = key.take() {
+                        segments.push(PathSegment::Key(key));
+                    }
+                    key = Some(String::new());
+                }
+                '[' => {
+                    if let Some(key) = key.take() {
+                        segments.push(PathSegment::Key(key));
+                    }
+                    let close = rest[idx..].find(']').ok_or_else(invalid)? + idx;
+                    let inner = &rest[idx + 1..close];
+                    let quoted = inner
+                        .strip_prefix('\'')
+                        .and_then(|inner| inner.strip_suffix('\''))
+                        .or_else(|| inner.strip_prefix('"')?.strip_suffix('"'));
+                    segments.push(match quoted {
+                        Some(name) => PathSegment::Key(name.to_string()),
+                        None => PathSegment::Index(inner.parse().map_err(|_| invalid())?),
+                    });
+                    while chars.next_if(|(pos, _)| *pos <= close).is_some() {}
+                }
+                ch => key.get_or_insert_with(String::new).push(ch),
+            }
+        }
+        if let Some(key) = key {
+            segments.push(PathSegment::Key(key));
+        }
+        if segments
+            .iter()
+            .any(|segment| matches!(segment, PathSegment::Key(key) if key.is_empty()))
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            text: text.to_string(),
+            segments,
+        })
+    }
+}
+
+impl fmt::Display for RecordsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+pub fn infer_schema(path: &Path, sample: &SampleOptions, options: &JsonOptions) -> Result<Schema> {
     let file = File::open(path).context("open JSON file")?;
-    infer_schema_reader(file)
+    infer_schema_reader(file, sample, options)
 }
 
-pub fn infer_schema_reader<R: Read>(reader: R) -> Result<Schema> {
-    let mut reader = BufReader::new(reader);
-    let first = peek_first_non_ws(&mut reader)?;
+pub fn infer_schema_reader<R: Read>(
+    reader: R,
+    sample: &SampleOptions,
+    options: &JsonOptions,
+) -> Result<Schema> {
+    Ok(infer_reader(reader, sample, options)?.0)
+}
 
-    let mut state = InferState::new();
+/// Infers the schema of JSON input and reports its layout.
+pub fn infer_reader<R: Read>(
+    reader: R,
+    sample: &SampleOptions,
+    options: &JsonOptions,
+) -> Result<(Schema, JsonLayout)> {
+    let values = JsonValues::new(reader, options)?;
+    let layout = values.layout;
+    Ok((infer_values(values, sample)?, layout))
+}
 
-    if matches!(first, Some(b'[')) {
-        sample_json_array(reader, &mut state)?;
-    } else {
-        let deser = serde_json::Deserializer::from_reader(reader);
-        for value in deser.into_iter::<Value>().take(SAMPLE_LIMIT) {
-            let value = value.context("parse JSON value")?;
+/// Infers a schema from a sample of decoded values, each read as one record. Formats that
+/// decode into JSON values, such as MessagePack, share JSON's inference this way.
+pub(crate) fn infer_values<I>(values: I, sample: &SampleOptions) -> Result<Schema>
+where
+    I: Iterator<Item = Result<Value>>,
+{
+    let mut state = InferState::new(sample);
+    let mut sampler = Sampler::new(sample);
+    for value in values {
+        if sampler.is_done() {
+            break;
+        }
+        if let Some(value) = sampler.offer(value?) {
             state.process_record(&value);
         }
     }
+    for value in sampler.finish() {
+        state.process_record(&value);
+    }
+    Ok(state.into_schema())
+}
 
-    let mut fields: Vec<Field> = state
-        .fields
-        .into_iter()
-        .map(|(name, info)| Field {
-            name,
-            dtype: info.dtype,
-            nullable: info.nullable,
+/// Infers a schema from the first `sample.rows` values, which are returned so input that
+/// cannot be read twice still yields them as records.
+pub(crate) fn infer_leading_values<I>(
+    values: &mut I,
+    sample: &SampleOptions,
+) -> Result<(Schema, VecDeque<Value>)>
+where
+    I: Iterator<Item = Result<Value>>,
+{
+    let mut state = InferState::new(sample);
+    let mut buffered = VecDeque::new();
+    for value in values.take(sample.rows.unwrap_or(usize::MAX)) {
+        let value = value?;
+        state.process_record(&value);
+        buffered.push_back(value);
+    }
+    Ok((state.into_schema(), buffered))
+}
+
+/// Detects the layout of JSON input without keeping it: only the first top-level value is
+/// skipped over, to see whether another one follows.
+pub fn detect_layout<R: Read>(reader: R, options: &JsonOptions) -> Result<JsonLayout> {
+    if options.records_path.is_some() {
+        return Ok(JsonLayout::Document);
+    }
+    let mut reader = JsonReader::new(reader);
+    Ok(match peek_first_non_ws(&mut reader)? {
+        Some(b'[') => JsonLayout::Array,
+        None => JsonLayout::Lines,
+        Some(_) => {
+            reader.value::<IgnoredAny>()?;
+            match peek_first_non_ws(&mut reader)? {
+                Some(_) => JsonLayout::Lines,
+                None => JsonLayout::Document,
+            }
+        }
+    })
+}
+
+/// Reads JSON input as records: elements of a top-level array, or each top-level value of a
+/// document stream. Values that are not objects are returned as `{"value": ...}`.
+pub struct JsonSource<R: Read> {
+    values: JsonValues<R>,
+    buffered: VecDeque<Value>,
+    schema: Schema,
+}
+
+impl JsonSource<File> {
+    pub fn open(path: &Path, sample: &SampleOptions, options: &JsonOptions) -> Result<Self> {
+        let schema = infer_schema(path, sample, options)?;
+        let file = File::open(path).context("open JSON file")?;
+        Self::with_schema(file, schema, options)
+    }
+}
+
+impl<R: Read> JsonSource<R> {
+    pub fn with_schema(reader: R, schema: Schema, options: &JsonOptions) -> Result<Self> {
+        Ok(Self {
+            values: JsonValues::new(reader, options)?,
+            buffered: VecDeque::new(),
+            schema,
         })
-        .collect();
-    fields.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(Schema { fields })
+    }
+
+    /// For input that cannot be read twice: the first `sample.rows` values are buffered for
+    /// inference before any record is returned.
+    pub fn from_reader(reader: R, sample: &SampleOptions, options: &JsonOptions) -> Result<Self> {
+        let mut values = JsonValues::new(reader, options)?;
+        let (schema, buffered) = infer_leading_values(&mut values, sample)?;
+        Ok(Self {
+            values,
+            buffered,
+            schema,
+        })
+    }
+
+    pub fn layout(&self) -> JsonLayout {
+        self.values.layout
+    }
 }
 
-fn sample_json_array<R: Read>(reader: BufReader<R>, state: &mut InferState) -> Result<()> {
-    let mut deser = serde_json::Deserializer::from_reader(reader);
-    let seed = ArraySeed { state };
-    seed.deserialize(&mut deser).context("parse JSON array")?;
-    Ok(())
+impl<R: Read> Iterator for JsonSource<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_value()?.map(into_record))
+    }
+}
+
+impl<R: Read> RecordSource for JsonSource<R> {
+    fn format(&self) -> Format {
+        Format::Json
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn json_layout(&self) -> Option<JsonLayout> {
+        Some(self.layout())
+    }
+
+    fn next_value(&mut self) -> Option<Result<Value>> {
+        match self.buffered.pop_front() {
+            Some(value) => Some(Ok(value)),
+            None => self.values.next(),
+        }
+    }
+}
+
+/// The top-level values of JSON input, read one at a time so memory use does not grow with
+/// the input: the elements of an array, or each document of a whitespace-separated stream.
+///
+/// The first value of a stream is read up front to tell NDJSON from a single document.
+struct JsonValues<R: Read> {
+    layout: JsonLayout,
+    first: Option<Value>,
+    rest: RestValues<R>,
+}
+
+enum RestValues<R: Read> {
+    Array(ArrayElements<R>),
+    Stream(StreamDeserializer<'static, IoRead<JsonReader<R>>, Value>),
+    Done,
+}
+
+impl<R: Read> JsonValues<R> {
+    fn new(reader: R, options: &JsonOptions) -> Result<Self> {
+        let mut reader = JsonReader::new(reader);
+        if let Some(path) = &options.records_path {
+            seek_path(&mut reader, path)?;
+            let (first, rest) = match peek_first_non_ws(&mut reader)? {
+                Some(b'[') => (None, RestValues::Array(ArrayElements::new(reader))),
+                _ => (Some(reader.value()?), RestValues::Done),
+            };
+            return Ok(Self {
+                layout: JsonLayout::Document,
+                first,
+                rest,
+            });
+        }
+
+        Ok(match peek_first_non_ws(&mut reader)? {
+            Some(b'[') => Self {
+                layout: JsonLayout::Array,
+                first: None,
+                rest: RestValues::Array(ArrayElements::new(reader)),
+            },
+            None => Self {
+                layout: JsonLayout::Lines,
+                first: None,
+                rest: RestValues::Done,
+            },
+            Some(_) => {
+                let first = reader.value()?;
+                match peek_first_non_ws(&mut reader)? {
+                    Some(_) => Self {
+                        layout: JsonLayout::Lines,
+                        first: Some(first),
+                        rest: RestValues::Stream(
+                            serde_json::Deserializer::from_reader(reader).into_iter(),
+                        ),
+                    },
+                    None => Self {
+                        layout: JsonLayout::Document,
+                        first: Some(first),
+                        rest: RestValues::Done,
+                    },
+                }
+            }
+        })
+    }
+}
+
+impl<R: Read> Iterator for JsonValues<R> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(first) = self.first.take() {
+            return Some(Ok(first));
+        }
+        match &mut self.rest {
+            RestValues::Array(elements) => elements.next(),
+            RestValues::Stream(values) => Some(values.next()?.context("parse JSON value")),
+            RestValues::Done => None,
+        }
+    }
 }
 
-struct ArraySeed<'a> {
-    state: &'a mut InferState,
+/// Reads the elements of a JSON array one at a time, without parsing the whole array.
+struct ArrayElements<R> {
+    reader: JsonReader<R>,
+    started: bool,
+    finished: bool,
 }
 
-impl<'de> DeserializeSeed<'de> for ArraySeed<'_> {
-    type Value = ();
+impl<R: Read> ArrayElements<R> {
+    fn new(reader: JsonReader<R>) -> Self {
+        Self {
+            reader,
+            started: false,
+            finished: false,
+        }
+    }
 
-    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_seq(ArrayVisitor { state: self.state })
+    fn next_element(&mut self) -> Result<Option<Value>> {
+        if !self.started {
+            self.started = true;
+            if peek_first_non_ws(&mut self.reader)? != Some(b'[') {
+                bail!("parse JSON array: expected `[`");
+            }
+            self.reader.consume(1);
+            if peek_first_non_ws(&mut self.reader)? == Some(b']') {
+                self.reader.consume(1);
+                return Ok(None);
+            }
+        } else if !next_item(&mut self.reader, b']')? {
+            return Ok(None);
+        }
+        self.reader
+            .value()
+            .context("parse JSON array element")
+            .map(Some)
     }
 }
 
-struct ArrayVisitor<'a> {
-    state: &'a mut InferState,
+impl<R: Read> Iterator for ArrayElements<R> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let element = self.next_element();
+        if !matches!(element, Ok(Some(_))) {
+            self.finished = true;
+        }
+        element.transpose()
+    }
 }
 
-impl<'de> Visitor<'de> for ArrayVisitor<'_> {
-    type Value = ();
+// Consumes the `,` between items of an array or object, returning false (after consuming
+// `close`) at its end.
+fn next_item<R: BufRead>(reader: &mut R, close: u8) -> Result<bool> {
+    match peek_first_non_ws(reader)? {
+        Some(b',') => {
+            reader.consume(1);
+            Ok(true)
+        }
+        Some(byte) if byte == close => {
+            reader.consume(1);
+            Ok(false)
+        }
+        Some(other) => bail!(
+            "parse JSON: expected `,` or `{}`, found `{}`",
+            char::from(close),
+            char::from(other)
+        ),
+        None => bail!("parse JSON: unexpected end of input"),
+    }
+}
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a JSON array")
+// Positions `reader` at the first byte of the value `path` points to, skipping everything
+// before it without parsing.
+fn seek_path<R: Read>(reader: &mut JsonReader<R>, path: &RecordsPath) -> Result<()> {
+    let not_found = || anyhow::anyhow!("records path `{path}` not found");
+    for segment in &path.segments {
+        match (segment, peek_first_non_ws(reader)?) {
+            (PathSegment::Key(key), Some(b'{')) => {
+                reader.consume(1);
+                if peek_first_non_ws(reader)? == Some(b'}') {
+                    return Err(not_found());
+                }
+                loop {
+                    if peek_first_non_ws(reader)? != Some(b'"') {
+                        bail!("parse JSON: expected an object key");
+                    }
+                    let name: String = reader.value()?;
+                    if peek_first_non_ws(reader)? != Some(b':') {
+                        bail!("parse JSON: expected `:` after object key");
+                    }
+                    reader.consume(1);
+                    if &name == key {
+                        break;
+                    }
+                    reader.value::<IgnoredAny>()?;
+                    if !next_item(reader, b'}')? {
+                        return Err(not_found());
+                    }
+                }
+            }
+            (PathSegment::Index(index), Some(b'[')) => {
+                reader.consume(1);
+                if peek_first_non_ws(reader)? == Some(b']') {
+                    return Err(not_found());
+                }
+                for _ in 0..*index {
+                    reader.value::<IgnoredAny>()?;
+                    if !next_item(reader, b']')? {
+                        return Err(not_found());
+                    }
+                }
+            }
+            _ => return Err(not_found()),
+        }
+    }
+    Ok(())
+}
+
+/// JSON input read one value at a time with serde_json, with the `[`, `,`, `:` and other bytes
+/// between values consumed directly, so no more than one element is held in memory.
+///
+/// serde_json reads one byte past a number to find where it ends. That byte is kept and
+/// returned again before the rest of the input, so the delimiter after a number is not lost.
+struct JsonReader<R> {
+    inner: BufReader<R>,
+    unread: Option<u8>,
+    last: Option<u8>,
+}
+
+impl<R: Read> JsonReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            inner: BufReader::new(reader),
+            unread: None,
+            last: None,
+        }
     }
 
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: SeqAccess<'de>,
-    {
-        while let Some(value) = seq.next_element::<Value>()? {
-            self.state.process_record(&value);
-            if self.state.samples >= SAMPLE_LIMIT {
-                while (seq.next_element::<IgnoredAny>()?).is_some() {}
-                break;
+    /// Deserializes the value that starts at the next non-whitespace byte. Skipped values are
+    /// read as [`IgnoredAny`], so they cost no memory.
+    fn value<T: DeserializeOwned>(&mut self) -> Result<T> {
+        let first = peek_first_non_ws(self)?;
+        self.last = None;
+        let value = T::deserialize(&mut serde_json::Deserializer::from_reader(&mut *self))
+            .context("parse JSON value")?;
+        // A number read to the end of input has no extra byte after it.
+        if matches!(first, Some(b'-' | b'0'..=b'9'))
+            && let Some(byte) = self.last
+            && !matches!(byte, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')
+        {
+            self.unread = Some(byte);
+        }
+        Ok(value)
+    }
+}
+
+impl<R: Read> Read for JsonReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let read = match self.unread.take() {
+            Some(byte) => {
+                buf[0] = byte;
+                1
             }
+            None => self.inner.read(buf)?,
+        };
+        if read > 0 {
+            self.last = Some(buf[read - 1]);
+        }
+        Ok(read)
+    }
+}
+
+impl<R: Read> BufRead for JsonReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match &self.unread {
+            Some(byte) => Ok(std::slice::from_ref(byte)),
+            None => self.inner.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if amount > 0 && self.unread.take().is_some() {
+            self.inner.consume(amount - 1);
+        } else {
+            self.inner.consume(amount);
         }
-        Ok(())
     }
 }
 
 struct InferState {
     fields: BTreeMap<String, FieldInfo>,
     samples: usize,
+    array_limit: usize,
 }
 
 impl InferState {
-    fn new() -> Self {
+    fn new(sample: &SampleOptions) -> Self {
+        let array_limit = match sample.rows {
+            Some(_) => ARRAY_SAMPLE_LIMIT,
+            None => usize::MAX,
+        };
         Self {
             fields: BTreeMap::new(),
             samples: 0,
+            array_limit,
         }
     }
 
@@ -129,6 +594,20 @@ impl InferState {
         self.samples += 1;
     }
 
+    fn into_schema(self) -> Schema {
+        let mut fields: Vec<Field> = self
+            .fields
+            .into_iter()
+            .map(|(name, info)| Field {
+                name,
+                dtype: info.dtype,
+                nullable: info.nullable,
+            })
+            .collect();
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+        Schema { fields }
+    }
+
     fn infer_value(
         &mut self,
         value: &Value,
@@ -168,11 +647,8 @@ impl InferState {
             Value::Array(items) => {
                 let name = field_name(path);
                 let mut element_type = DataType::Null;
-                for (idx, item) in items.iter().enumerate() {
-                    if idx >= ARRAY_SAMPLE_LIMIT {
-                        break;
-                    }
-                    let inferred = Self::value_dtype(item, depth + 1);
+                for item in items.iter().take(self.array_limit) {
+                    let inferred = self.value_dtype(item, depth + 1);
                     element_type = DataType::merge(&element_type, &inferred);
                     if let Value::Object(map) = item {
                         for (key, child) in map {
@@ -199,7 +675,7 @@ impl InferState {
         }
     }
 
-    fn value_dtype(value: &Value, depth: usize) -> DataType {
+    fn value_dtype(&self, value: &Value, depth: usize) -> DataType {
         if depth > MAX_DEPTH {
             return DataType::Unknown;
         }
@@ -216,11 +692,8 @@ impl InferState {
             Value::String(_) => DataType::String,
             Value::Array(items) => {
                 let mut inner = DataType::Null;
-                for (idx, item) in items.iter().enumerate() {
-                    if idx >= ARRAY_SAMPLE_LIMIT {
-                        break;
-                    }
-                    let inferred = Self::value_dtype(item, depth + 1);
+                for item in items.iter().take(self.array_limit) {
+                    let inferred = self.value_dtype(item, depth + 1);
                     inner = DataType::merge(&inner, &inferred);
                 }
                 DataType::List(Box::new(inner))
@@ -290,7 +763,8 @@ mod tests {
         writeln!(file, "{{\"a\":2,\"c\":{{\"d\":\"y\",\"e\":true}}}}").unwrap();
         drop(file);
 
-        let schema = infer_schema(&path).expect("infer schema");
+        let schema = infer_schema(&path, &SampleOptions::default(), &JsonOptions::default())
+            .expect("infer schema");
         fs::remove_file(&path).ok();
 
         let mut fields = schema
@@ -308,4 +782,64 @@ mod tests {
         ];
         assert_eq!(fields, expected);
     }
+
+    #[test]
+    fn array_elements_are_split_without_parsing_the_array() {
+        let input = br#" [ {"a": "x,]}\"y"}, 12 ,[1, [2]] ,null]"#;
+        let values: Vec<Value> = JsonValues::new(&input[..], &JsonOptions::default())
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                serde_json::json!({"a": "x,]}\"y"}),
+                serde_json::json!(12),
+                serde_json::json!([1, [2]]),
+                Value::Null,
+            ]
+        );
+
+        let truncated = JsonValues::new(&b"[1, 2"[..], &JsonOptions::default())
+            .unwrap()
+            .collect::<Result<Vec<_>>>();
+        assert!(truncated.is_err());
+    }
+
+    #[test]
+    fn layout_is_detected_from_the_first_value() {
+        let options = JsonOptions::default();
+        let layout = |input: &str| detect_layout(input.as_bytes(), &options).unwrap();
+        assert_eq!(layout(" [{\"a\": 1}]"), JsonLayout::Array);
+        assert_eq!(layout("{\"a\": 1}\n{\"a\": 2}\n"), JsonLayout::Lines);
+        assert_eq!(layout("{\"a\": {\"b\": [1, 2]}}\n"), JsonLayout::Document);
+        assert_eq!(layout(""), JsonLayout::Lines);
+
+        let values = JsonValues::new(&b"{\"a\": 1} {\"a\": 2}"[..], &options).unwrap();
+        assert_eq!(values.layout, JsonLayout::Lines);
+        assert_eq!(values.count(), 2);
+    }
+
+    #[test]
+    fn records_path_selects_nested_records() {
+        let input = br#"{"meta": {"items": 0}, "pages": [{"rows": []}, {"rows": [{"id": 1}, 2]}]}"#;
+        let read = |path: &str| {
+            let options = JsonOptions {
+                records_path: Some(path.parse().unwrap()),
+            };
+            JsonValues::new(&input[..], &options)?.collect::<Result<Vec<_>>>()
+        };
+        assert_eq!(
+            read("$.pages[1].rows").unwrap(),
+            vec![serde_json::json!({"id": 1}), serde_json::json!(2)]
+        );
+        assert_eq!(read("pages[0]['rows']").unwrap(), Vec::<Value>::new());
+        assert_eq!(read("$.meta.items").unwrap(), vec![serde_json::json!(0)]);
+        assert_eq!(
+            read("$.pages[2]").unwrap_err().to_string(),
+            "records path `$.pages[2]` not found"
+        );
+        assert!("$.data..items".parse::<RecordsPath>().is_err());
+        assert!("$data".parse::<RecordsPath>().is_err());
+    }
 }
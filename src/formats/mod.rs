@@ -1,9 +1,37 @@
+pub mod arrow_ipc;
 pub mod avro;
+pub mod binary_json;
 pub mod csv;
+pub mod delta;
 pub mod detect;
+pub mod iceberg;
 pub mod json;
 pub mod orc;
 pub mod parquet;
+pub mod protobuf;
+pub mod spreadsheet;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arrow::datatypes::SchemaRef;
+
+use crate::batch;
+use crate::formats::binary_json::{BinaryJsonSource, Encoding};
+
+use crate::formats::csv::CsvOptions;
+use crate::formats::delta::DeltaOptions;
+use crate::formats::json::JsonOptions;
+use crate::formats::protobuf::ProtobufOptions;
+use crate::formats::spreadsheet::SpreadsheetOptions;
+use crate::model::schema::Schema;
+use crate::source::RecordSource;
+use crate::util::encoding::{self, TextEncoding};
+use crate::util::errors::UnsupportedFormatError;
+use crate::util::sample::SampleOptions;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
@@ -12,6 +40,19 @@ pub enum Format {
     Parquet,
     Avro,
     Orc,
+    ArrowIpc,
+    /// Excel (xlsx, xlsm, xlsb) or OpenDocument (ods) workbook.
+    Spreadsheet,
+    /// Protobuf messages, decoded with a descriptor given in [`ProtobufOptions`].
+    Protobuf,
+    /// Concatenated MessagePack values, one record each.
+    MessagePack,
+    /// Concatenated CBOR values, one record each.
+    Cbor,
+    /// A Delta Lake table directory, read as its current Parquet data files.
+    Delta,
+    /// An Apache Iceberg table directory or metadata file, read as its current Parquet data files.
+    Iceberg,
 }
 
 impl Format {
@@ -22,6 +63,188 @@ impl Format {
             Format::Parquet => "PARQUET",
             Format::Avro => "AVRO",
             Format::Orc => "ORC",
+            Format::ArrowIpc => "ARROW_IPC",
+            Format::Spreadsheet => "SPREADSHEET",
+            Format::Protobuf => "PROTOBUF",
+            Format::MessagePack => "MSGPACK",
+            Format::Cbor => "CBOR",
+            Format::Delta => "DELTA",
+            Format::Iceberg => "ICEBERG",
         }
     }
+
+    /// Whether this is a table format, read from a directory's metadata rather than as files.
+    pub fn is_table(&self) -> bool {
+        matches!(self, Format::Delta | Format::Iceberg)
+    }
+}
+
+/// Options for reading each format, and how text formats are sampled and decoded.
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    pub sample: SampleOptions,
+    pub encoding: TextEncoding,
+    pub csv: CsvOptions,
+    pub json: JsonOptions,
+    pub spreadsheet: SpreadsheetOptions,
+    pub protobuf: ProtobufOptions,
+    pub delta: DeltaOptions,
+}
+
+/// Infers the schema of one file in a known format.
+pub fn infer_schema(path: &Path, format: Format, options: &FormatOptions) -> Result<Schema> {
+    let sample = &options.sample;
+    match format {
+        Format::Json => {
+            json::infer_schema_reader(open_text(path, options.encoding)?, sample, &options.json)
+        }
+        Format::Csv => {
+            csv::infer_schema_reader(open_text(path, options.encoding)?, sample, &options.csv)
+        }
+        Format::Parquet => parquet::infer_schema(path),
+        Format::Avro => avro::infer_schema(path),
+        Format::Orc => orc::infer_schema(path),
+        Format::ArrowIpc => arrow_ipc::infer_schema(path),
+        Format::Spreadsheet => spreadsheet::infer_schema(path, sample, &options.spreadsheet),
+        Format::Protobuf => protobuf::infer_schema(&options.protobuf),
+        Format::MessagePack => binary_json::infer_schema(path, Encoding::MessagePack, sample),
+        Format::Cbor => binary_json::infer_schema(path, Encoding::Cbor, sample),
+        Format::Delta => delta::infer_schema(path, &options.delta),
+        Format::Iceberg => iceberg::infer_schema(path),
+    }
+}
+
+/// Opens one file whose schema is already known, so text formats are not sampled twice.
+pub(crate) fn open_with_schema(
+    path: &Path,
+    format: Format,
+    schema: Schema,
+    options: &FormatOptions,
+) -> Result<Box<dyn RecordSource>> {
+    Ok(match format {
+        Format::Json => Box::new(json::JsonSource::with_schema(
+            open_text(path, options.encoding)?,
+            schema,
+            &options.json,
+        )?),
+        Format::Csv => Box::new(csv::CsvSource::with_schema(
+            open_text(path, options.encoding)?,
+            schema,
+            &options.csv,
+        )?),
+        Format::Parquet => Box::new(parquet::ParquetSource::open(path)?),
+        Format::Avro => Box::new(avro::AvroSource::open(path)?),
+        Format::Orc => orc::open(path)?,
+        Format::ArrowIpc => Box::new(arrow_ipc::ArrowIpcSource::open(path)?),
+        Format::Spreadsheet => Box::new(spreadsheet::SpreadsheetSource::with_schema(
+            path,
+            schema,
+            &options.spreadsheet,
+        )?),
+        Format::Protobuf => Box::new(protobuf::ProtobufSource::open(path, &options.protobuf)?),
+        Format::MessagePack => Box::new(BinaryJsonSource::with_schema(
+            open_file(path)?,
+            Encoding::MessagePack,
+            schema,
+        )),
+        Format::Cbor => Box::new(BinaryJsonSource::with_schema(
+            open_file(path)?,
+            Encoding::Cbor,
+            schema,
+        )),
+        Format::Delta | Format::Iceberg => open_table(path, format, options)?,
+    })
+}
+
+/// The Arrow schema of one file's batches: the file's own for formats decoded into Arrow
+/// natively (Parquet, Arrow IPC), otherwise [`batch::arrow_schema`] of its inferred schema.
+pub(crate) fn arrow_schema(
+    path: &Path,
+    format: Format,
+    schema: &Schema,
+    options: &FormatOptions,
+) -> Result<SchemaRef> {
+    Ok(match format {
+        Format::Parquet | Format::ArrowIpc => {
+            open_with_schema(path, format, schema.clone(), options)?.arrow_schema()
+        }
+        _ => Arc::new(batch::arrow_schema(schema)),
+    })
+}
+
+/// Opens a table format from its metadata; the schema comes from the metadata too.
+pub(crate) fn open_table(
+    path: &Path,
+    format: Format,
+    options: &FormatOptions,
+) -> Result<Box<dyn RecordSource>> {
+    Ok(match format {
+        Format::Delta => Box::new(delta::DeltaSource::open(path, &options.delta)?),
+        Format::Iceberg => Box::new(iceberg::IcebergSource::open(path)?),
+        other => {
+            return Err(UnsupportedFormatError::new(format!(
+                "{} is not a table format",
+                other.as_str()
+            ))
+            .into());
+        }
+    })
+}
+
+/// Opens input that can only be read once, such as stdin: every format but Parquet, Avro, ORC
+/// and table formats.
+pub(crate) fn open_reader<R: Read + 'static>(
+    reader: R,
+    format: Format,
+    options: &FormatOptions,
+) -> Result<Box<dyn RecordSource>> {
+    let sample = &options.sample;
+    Ok(match format {
+        Format::Json => Box::new(json::JsonSource::from_reader(
+            encoding::decode(reader, options.encoding)?,
+            sample,
+            &options.json,
+        )?),
+        Format::Csv => Box::new(csv::CsvSource::from_reader(
+            encoding::decode(reader, options.encoding)?,
+            sample,
+            &options.csv,
+        )?),
+        Format::ArrowIpc => Box::new(arrow_ipc::ArrowIpcSource::from_reader(reader)?),
+        Format::Spreadsheet => Box::new(spreadsheet::SpreadsheetSource::from_reader(
+            reader,
+            sample,
+            &options.spreadsheet,
+        )?),
+        Format::Protobuf => Box::new(protobuf::ProtobufSource::from_reader(
+            reader,
+            &options.protobuf,
+        )?),
+        Format::MessagePack => Box::new(BinaryJsonSource::from_reader(
+            reader,
+            Encoding::MessagePack,
+            sample,
+        )?),
+        Format::Cbor => Box::new(BinaryJsonSource::from_reader(
+            reader,
+            Encoding::Cbor,
+            sample,
+        )?),
+        Format::Parquet | Format::Avro | Format::Orc | Format::Delta | Format::Iceberg => {
+            return Err(UnsupportedFormatError::new(STDIN_UNSUPPORTED).into());
+        }
+    })
+}
+
+/// Why Parquet, Avro, ORC and table formats cannot be read from stdin.
+pub(crate) const STDIN_UNSUPPORTED: &str =
+    "stdin input is not supported for Parquet, Avro, ORC, Delta or Iceberg; pass a path";
+
+fn open_file(path: &Path) -> Result<File> {
+    File::open(path).with_context(|| format!("open {}", path.display()))
+}
+
+/// Opens a CSV or JSON file transcoded to UTF-8.
+pub(crate) fn open_text(path: &Path, encoding: TextEncoding) -> Result<encoding::TextReader<File>> {
+    encoding::decode(open_file(path)?, encoding)
 }
@@ -1,9 +1,9 @@
 use std::path::Path;
 
-use anyhow::{Result, bail};
-
 use crate::model::schema::Schema;
+use crate::source::RecordSource;
 use crate::util::errors::UnsupportedFormatError;
+use anyhow::{Result, bail};
 
 #[cfg(feature = "orc")]
 pub fn infer_schema(_path: &Path) -> Result<Schema> {
@@ -20,14 +20,14 @@ pub fn infer_schema(_path: &Path) -> Result<Schema> {
 }
 
 #[cfg(feature = "orc")]
-pub fn cat(_path: &Path, _limit: Option<usize>) -> Result<()> {
+pub fn open(_path: &Path) -> Result<Box<dyn RecordSource>> {
     bail!(UnsupportedFormatError::new(
         "ORC support is not implemented in this build"
     ))
 }
 
 #[cfg(not(feature = "orc"))]
-pub fn cat(_path: &Path, _limit: Option<usize>) -> Result<()> {
+pub fn open(_path: &Path) -> Result<Box<dyn RecordSource>> {
     bail!(UnsupportedFormatError::new(
         "ORC support is disabled; rebuild with --features orc"
     ))
@@ -2,61 +2,217 @@ use std::fs::File;
 use std::path::Path;
 use std::sync::Arc;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use arrow::array::{
     Array, BinaryArray, BooleanArray, Date32Array, Date64Array, FixedSizeBinaryArray, Float32Array,
     Float64Array, Int8Array, Int16Array, Int32Array, Int64Array, LargeBinaryArray, LargeListArray,
-    LargeStringArray, ListArray, StringArray, StructArray, TimestampMicrosecondArray,
+    LargeStringArray, ListArray, MapArray, StringArray, StructArray, TimestampMicrosecondArray,
     TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray, UInt8Array,
     UInt16Array, UInt32Array, UInt64Array,
 };
-use arrow::datatypes::{DataType as ArrowType, Field as ArrowField, Schema as ArrowSchema};
+use arrow::datatypes::{
+    DataType as ArrowType, Field as ArrowField, Schema as ArrowSchema, SchemaRef,
+};
 use arrow::record_batch::RecordBatch;
-use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::arrow_reader::{
+    ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder, RowSelection, RowSelector,
+};
 use serde_json::Value as JsonValue;
 
+use crate::formats::Format;
 use crate::model::schema::{DataType, Field, Schema};
-use crate::render::jsonl;
+use crate::source::{Record, RecordSource, skip_by_reading};
+use crate::util::hex;
 
 pub fn infer_schema(path: &Path) -> Result<Schema> {
     let file = File::open(path).context("open Parquet file")?;
     let builder =
         ParquetRecordBatchReaderBuilder::try_new(file).context("read Parquet metadata")?;
-    let arrow_schema = builder.schema();
+    Ok(schema_from_arrow(builder.schema()))
+}
 
-    let fields = arrow_schema
-        .fields()
-        .iter()
-        .map(|field| map_field(field))
-        .collect();
+/// Reads Parquet rows as records, decoding one batch at a time.
+pub struct ParquetSource {
+    builder: Option<ParquetRecordBatchReaderBuilder<File>>,
+    reader: Option<ParquetRecordBatchReader>,
+    batch: Option<RecordBatch>,
+    row: usize,
+    schema: Schema,
+    arrow_schema: SchemaRef,
+}
+
+impl ParquetSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).context("open Parquet file")?;
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(file).context("read Parquet metadata")?;
+        let arrow_schema = builder.schema().clone();
+        let schema = schema_from_arrow(&arrow_schema);
+        Ok(Self {
+            builder: Some(builder),
+            reader: None,
+            batch: None,
+            row: 0,
+            schema,
+            arrow_schema,
+        })
+    }
+
+    /// The number of rows in the file, from its footer.
+    pub fn num_rows(&self) -> u64 {
+        match &self.builder {
+            Some(builder) => builder.metadata().file_metadata().num_rows().max(0) as u64,
+            None => 0,
+        }
+    }
 
-    Ok(Schema { fields })
+    /// Restricts reading to the given zero-based rows, which must be in ascending order.
+    ///
+    /// Row groups without a selected row are never read, so a small sample of a large file
+    /// only decodes the row groups it touches. Must be called before any record is read.
+    pub fn select_rows(&mut self, rows: &[u64]) -> Result<()> {
+        let Some(builder) = self.builder.take() else {
+            bail!("rows must be selected before reading the Parquet file");
+        };
+        let mut groups = Vec::new();
+        let mut selectors = Vec::new();
+        let mut rows = rows.iter().copied().peekable();
+        let mut start = 0u64;
+        for (idx, group) in builder.metadata().row_groups().iter().enumerate() {
+            let end = start + group.num_rows().max(0) as u64;
+            let mut pos = start;
+            let first = selectors.len();
+            while let Some(&row) = rows.peek()
+                && row < end
+            {
+                selectors.push(RowSelector::skip((row - pos) as usize));
+                selectors.push(RowSelector::select(1));
+                pos = row + 1;
+                rows.next();
+            }
+            if selectors.len() > first {
+                selectors.push(RowSelector::skip((end - pos) as usize));
+                groups.push(idx);
+            }
+            start = end;
+        }
+        self.builder = Some(
+            builder
+                .with_row_groups(groups)
+                .with_row_selection(RowSelection::from(selectors)),
+        );
+        Ok(())
+    }
+
+    fn start(&mut self, offset: usize) -> Result<()> {
+        if let Some(builder) = self.builder.take() {
+            let reader = builder
+                .with_batch_size(1024)
+                .with_offset(offset)
+                .build()
+                .context("build Parquet reader")?;
+            self.reader = Some(reader);
+        }
+        Ok(())
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        self.start(0)?;
+        loop {
+            if let Some(batch) = &self.batch
+                && self.row < batch.num_rows()
+            {
+                let record = batch_row_to_json(batch, self.row);
+                self.row += 1;
+                return Ok(Some(record));
+            }
+            if !self.read_batch()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Loads the next decoded batch, returning false at the end of the file.
+    fn read_batch(&mut self) -> Result<bool> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(false);
+        };
+        match reader.next() {
+            Some(batch) => {
+                self.batch = Some(batch.context("read Parquet record batch")?);
+                self.row = 0;
+                Ok(true)
+            }
+            None => {
+                self.reader = None;
+                Ok(false)
+            }
+        }
+    }
 }
 
-pub fn cat(path: &Path, limit: Option<usize>) -> Result<()> {
-    let file = File::open(path).context("open Parquet file")?;
-    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
-        .context("read Parquet metadata")?
-        .with_batch_size(1024);
-    let reader = builder.build().context("build Parquet reader")?;
-    let mut out = std::io::stdout();
-    let mut count = 0usize;
+impl Iterator for ParquetSource {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+impl RecordSource for ParquetSource {
+    fn format(&self) -> Format {
+        Format::Parquet
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    // Before reading starts, skipped rows are passed to the reader as an offset so whole
+    // pages can be skipped without decoding.
+    fn skip_records(&mut self, count: usize) -> Result<usize> {
+        match &self.builder {
+            Some(builder) => {
+                let total_rows = builder.metadata().file_metadata().num_rows().max(0) as usize;
+                let skipped = count.min(total_rows);
+                self.start(skipped)?;
+                Ok(skipped)
+            }
+            None => skip_by_reading(self, count),
+        }
+    }
+
+    fn arrow_schema(&self) -> SchemaRef {
+        self.arrow_schema.clone()
+    }
 
-    for batch in reader {
-        let batch = batch.context("read Parquet record batch")?;
-        for row in 0..batch.num_rows() {
-            let json = batch_row_to_json(&batch, row);
-            jsonl::write_line(&mut out, &json)?;
-            count += 1;
-            if let Some(limit) = limit
-                && count >= limit
+    // Returns the decoded batches themselves, sliced to `max_rows`, continuing from any
+    // rows already read as records.
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RecordBatch>> {
+        self.start(0)?;
+        loop {
+            if let Some(batch) = &self.batch
+                && self.row < batch.num_rows()
             {
-                return Ok(());
+                let len = max_rows.max(1).min(batch.num_rows() - self.row);
+                let slice = batch.slice(self.row, len);
+                self.row += len;
+                return Ok(Some(slice));
+            }
+            if !self.read_batch()? {
+                return Ok(None);
             }
         }
     }
+}
 
-    Ok(())
+pub(crate) fn schema_from_arrow(arrow_schema: &ArrowSchema) -> Schema {
+    let fields = arrow_schema
+        .fields()
+        .iter()
+        .map(|field| map_field(field))
+        .collect();
+    Schema { fields }
 }
 
 fn map_field(field: &ArrowField) -> Field {
@@ -86,7 +242,7 @@ fn map_arrow_type(arrow: &ArrowType) -> DataType {
         }
         ArrowType::Timestamp(_, _) => DataType::Timestamp,
         ArrowType::Date32 | ArrowType::Date64 => DataType::Date,
-        ArrowType::Struct(_) => DataType::Struct,
+        ArrowType::Struct(_) | ArrowType::Map(_, _) => DataType::Struct,
         ArrowType::List(field) | ArrowType::LargeList(field) => {
             let inner = map_arrow_type(field.data_type());
             DataType::List(Box::new(inner))
@@ -95,15 +251,15 @@ fn map_arrow_type(arrow: &ArrowType) -> DataType {
     }
 }
 
-fn batch_row_to_json(batch: &RecordBatch, row: usize) -> JsonValue {
-    let mut map = serde_json::Map::new();
+pub(crate) fn batch_row_to_json(batch: &RecordBatch, row: usize) -> Record {
+    let mut map = Record::new();
     let schema: Arc<ArrowSchema> = batch.schema();
     for (idx, field) in schema.fields().iter().enumerate() {
         let array = batch.column(idx).as_ref();
         let value = array_value(array, row);
         map.insert(field.name().clone(), value);
     }
-    JsonValue::Object(map)
+    map
 }
 
 fn array_value(array: &dyn Array, row: usize) -> JsonValue {
@@ -166,18 +322,18 @@ fn array_value(array: &dyn Array, row: usize) -> JsonValue {
         }
         ArrowType::Binary => {
             let array = array.as_any().downcast_ref::<BinaryArray>().unwrap();
-            JsonValue::String(bytes_to_hex(array.value(row)))
+            JsonValue::String(hex::encode(array.value(row)))
         }
         ArrowType::LargeBinary => {
             let array = array.as_any().downcast_ref::<LargeBinaryArray>().unwrap();
-            JsonValue::String(bytes_to_hex(array.value(row)))
+            JsonValue::String(hex::encode(array.value(row)))
         }
         ArrowType::FixedSizeBinary(_) => {
             let array = array
                 .as_any()
                 .downcast_ref::<FixedSizeBinaryArray>()
                 .unwrap();
-            JsonValue::String(bytes_to_hex(array.value(row)))
+            JsonValue::String(hex::encode(array.value(row)))
         }
         ArrowType::Timestamp(arrow::datatypes::TimeUnit::Second, _) => {
             let array = array
@@ -235,6 +391,19 @@ fn array_value(array: &dyn Array, row: usize) -> JsonValue {
             let child = array.value(row);
             list_array_to_json(child.as_ref())
         }
+        ArrowType::Map(_, _) => {
+            let array = array.as_any().downcast_ref::<MapArray>().unwrap();
+            let entries = array.value(row);
+            let mut map = serde_json::Map::new();
+            for idx in 0..entries.len() {
+                let key = match array_value(entries.column(0).as_ref(), idx) {
+                    JsonValue::String(key) => key,
+                    other => other.to_string(),
+                };
+                map.insert(key, array_value(entries.column(1).as_ref(), idx));
+            }
+            JsonValue::Object(map)
+        }
         _ => JsonValue::Null,
     }
 }
@@ -246,11 +415,3 @@ fn list_array_to_json(array: &dyn Array) -> JsonValue {
     }
     JsonValue::Array(values)
 }
-
-fn bytes_to_hex(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        out.push_str(&format!("{:02x}", byte));
-    }
-    out
-}
@@ -0,0 +1,515 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use protobuf::Message;
+use protobuf::descriptor::FileDescriptorSet;
+use protobuf::reflect::{
+    FieldDescriptor, FileDescriptor, MessageDescriptor, ReflectFieldRef, ReflectValueRef,
+    RuntimeFieldType, RuntimeType,
+};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::formats::Format;
+use crate::model::schema::{DataType, Field, Schema};
+use crate::source::{Record, RecordSource};
+use crate::util::hex;
+
+/// Nested messages deeper than this (such as recursive types) are kept as JSON text.
+const MAX_DEPTH: usize = 8;
+
+/// Messages larger than this are treated as a framing error rather than allocated.
+const MAX_MESSAGE_LEN: u64 = 256 * 1024 * 1024;
+
+/// How many leading frames must decode for [`ProtoFraming::Auto`] to pick length-delimited.
+const FRAMING_CHECK_MESSAGES: usize = 8;
+
+/// How messages are laid out in a protobuf file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProtoFraming {
+    /// Length-delimited if the whole input splits into varint-prefixed messages, otherwise a
+    /// single message.
+    #[default]
+    Auto,
+    /// Each message is prefixed with its length as a varint (`writeDelimitedTo`).
+    Delimited,
+    /// The whole input is one message.
+    Single,
+}
+
+/// Options used when decoding protobuf messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtobufOptions {
+    /// Compiled descriptor sets (`protoc --descriptor_set_out`) or `.proto` files.
+    pub descriptors: Vec<PathBuf>,
+    /// Fully qualified message type, such as `pkg.Type`.
+    pub message: Option<String>,
+    pub framing: ProtoFraming,
+}
+
+impl ProtobufOptions {
+    /// Resolves the message type from the descriptors.
+    pub fn message_descriptor(&self) -> Result<MessageDescriptor> {
+        let Some(name) = &self.message else {
+            bail!("reading protobuf needs a message type (--message) and its descriptor");
+        };
+        if self.descriptors.is_empty() {
+            bail!("reading protobuf needs a descriptor set or .proto file (--proto-descriptor)");
+        }
+        let mut protos = Vec::new();
+        for path in &self.descriptors {
+            protos.extend(load_descriptor(path)?.file);
+        }
+        // The same dependency may come from several inputs.
+        let mut seen = std::collections::HashSet::new();
+        protos.retain(|proto| seen.insert(proto.name().to_string()));
+        let files = FileDescriptor::new_dynamic_fds(protos, &[]).context("build descriptors")?;
+
+        let name = name.trim_start_matches('.');
+        files
+            .iter()
+            .find_map(|file| file.message_by_full_name(&format!(".{name}")))
+            .ok_or_else(|| {
+                let known: Vec<String> = files
+                    .iter()
+                    .flat_map(|file| file.messages().map(|m| m.full_name().to_string()))
+                    .collect();
+                anyhow!(
+                    "message `{name}` not found in descriptor (messages: {})",
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+fn load_descriptor(path: &Path) -> Result<FileDescriptorSet> {
+    if path.extension().is_some_and(|ext| ext == "proto") {
+        let include = path.parent().unwrap_or(Path::new("."));
+        return protobuf_parse::Parser::new()
+            .pure()
+            .include(include)
+            .input(path)
+            .file_descriptor_set()
+            .with_context(|| format!("parse {}", path.display()));
+    }
+    let bytes = std::fs::read(path).with_context(|| format!("open {}", path.display()))?;
+    FileDescriptorSet::parse_from_bytes(&bytes)
+        .with_context(|| format!("read descriptor set {}", path.display()))
+}
+
+pub fn infer_schema(options: &ProtobufOptions) -> Result<Schema> {
+    Ok(schema_from_message(&options.message_descriptor()?))
+}
+
+/// Maps a message type to a schema: nested messages are flattened into dotted names (as for
+/// JSON), repeated fields are lists, enums are strings and maps are structs.
+pub fn schema_from_message(message: &MessageDescriptor) -> Schema {
+    let mut fields = Vec::new();
+    message_fields(message, "", false, 0, &mut fields);
+    Schema { fields }
+}
+
+fn message_fields(
+    message: &MessageDescriptor,
+    prefix: &str,
+    nullable: bool,
+    depth: usize,
+    out: &mut Vec<Field>,
+) {
+    for field in message.fields() {
+        let name = format!("{prefix}{}", field.name());
+        let nullable = nullable || has_presence(&field);
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(child)) if depth < MAX_DEPTH => {
+                message_fields(&child, &format!("{name}."), nullable, depth + 1, out);
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(child)) if depth < MAX_DEPTH => {
+                out.push(Field {
+                    name: name.clone(),
+                    dtype: DataType::List(Box::new(DataType::Struct)),
+                    nullable,
+                });
+                message_fields(&child, &format!("{name}."), true, depth + 1, out);
+            }
+            RuntimeFieldType::Singular(dtype) => out.push(Field {
+                name,
+                dtype: scalar_type(&dtype),
+                nullable,
+            }),
+            RuntimeFieldType::Repeated(dtype) => out.push(Field {
+                name,
+                dtype: DataType::List(Box::new(scalar_type(&dtype))),
+                nullable,
+            }),
+            RuntimeFieldType::Map(..) => out.push(Field {
+                name,
+                dtype: DataType::Struct,
+                nullable,
+            }),
+        }
+    }
+}
+
+fn scalar_type(dtype: &RuntimeType) -> DataType {
+    match dtype {
+        RuntimeType::I32 | RuntimeType::I64 | RuntimeType::U32 | RuntimeType::U64 => DataType::Int,
+        RuntimeType::F32 | RuntimeType::F64 => DataType::Float,
+        RuntimeType::Bool => DataType::Bool,
+        RuntimeType::String | RuntimeType::Enum(_) => DataType::String,
+        RuntimeType::VecU8 => DataType::Bytes,
+        RuntimeType::Message(_) => DataType::Struct,
+    }
+}
+
+// Fields that can be unset: messages, oneof members and explicit `optional` fields. Other
+// proto3 scalars always read as their default value.
+fn has_presence(field: &FieldDescriptor) -> bool {
+    if matches!(
+        field.runtime_field_type(),
+        RuntimeFieldType::Singular(RuntimeType::Message(_))
+    ) || field.containing_oneof_including_synthetic().is_some()
+    {
+        return true;
+    }
+    let proto3 = field
+        .containing_message()
+        .file_descriptor()
+        .proto()
+        .syntax()
+        == "proto3";
+    field.is_singular() && !proto3 && !field.is_required()
+}
+
+/// Reads protobuf messages of one type as records.
+pub struct ProtobufSource<R> {
+    reader: R,
+    message: MessageDescriptor,
+    framing: ProtoFraming,
+    done: bool,
+    schema: Schema,
+}
+
+impl ProtobufSource<BufReader<File>> {
+    pub fn open(path: &Path, options: &ProtobufOptions) -> Result<Self> {
+        let file = File::open(path).context("open protobuf file")?;
+        Self::from_seekable(BufReader::new(file), options)
+    }
+}
+
+impl ProtobufSource<Cursor<Vec<u8>>> {
+    /// For input that cannot be read twice; it is buffered in memory so the framing can be
+    /// detected.
+    pub fn from_reader<R: Read>(mut reader: R, options: &ProtobufOptions) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .context("read protobuf input")?;
+        Self::from_seekable(Cursor::new(bytes), options)
+    }
+}
+
+impl<R: Read + Seek> ProtobufSource<R> {
+    fn from_seekable(mut reader: R, options: &ProtobufOptions) -> Result<Self> {
+        let message = options.message_descriptor()?;
+        let framing = match options.framing {
+            ProtoFraming::Auto if is_delimited(&mut reader, &message)? => ProtoFraming::Delimited,
+            ProtoFraming::Auto => ProtoFraming::Single,
+            framing => framing,
+        };
+        reader.rewind().context("read protobuf input")?;
+        Ok(Self {
+            reader,
+            schema: schema_from_message(&message),
+            message,
+            framing,
+            done: false,
+        })
+    }
+
+    fn next_message(&mut self) -> Result<Option<Record>> {
+        let bytes = match self.framing {
+            ProtoFraming::Single => {
+                self.done = true;
+                let mut bytes = Vec::new();
+                self.reader
+                    .read_to_end(&mut bytes)
+                    .context("read protobuf message")?;
+                bytes
+            }
+            _ => {
+                let Some(len) = read_varint(&mut self.reader)? else {
+                    self.done = true;
+                    return Ok(None);
+                };
+                if len > MAX_MESSAGE_LEN {
+                    bail!("protobuf message length {len} is too large; is the input delimited?");
+                }
+                let mut bytes = vec![0; len as usize];
+                self.reader
+                    .read_exact(&mut bytes)
+                    .context("read protobuf message: truncated input")?;
+                bytes
+            }
+        };
+        let message = self
+            .message
+            .parse_from_bytes(&bytes)
+            .with_context(|| format!("decode {} message", self.message.full_name()))?;
+        Ok(Some(message_to_json(&*message, 0)))
+    }
+}
+
+impl<R: Read + Seek> Iterator for ProtobufSource<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.next_message();
+        if record.is_err() {
+            self.done = true;
+        }
+        record.transpose()
+    }
+}
+
+impl<R: Read + Seek> RecordSource for ProtobufSource<R> {
+    fn format(&self) -> Format {
+        Format::Protobuf
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
+
+// Checks that the input splits exactly into varint-prefixed messages. A single message can
+// happen to split that way too, so the first few frames must also decode; the rest are
+// skipped over without reading them.
+fn is_delimited<R: Read + Seek>(reader: &mut R, message: &MessageDescriptor) -> Result<bool> {
+    let len = reader
+        .seek(SeekFrom::End(0))
+        .context("read protobuf input")?;
+    reader.rewind().context("read protobuf input")?;
+    let mut pos = 0u64;
+    let mut frames = 0usize;
+    while pos < len {
+        let Ok(Some(size)) = read_varint(reader) else {
+            return Ok(false);
+        };
+        pos = reader.stream_position().context("read protobuf input")?;
+        let end = match pos.checked_add(size) {
+            Some(end) if end <= len => end,
+            _ => return Ok(false),
+        };
+        if frames < FRAMING_CHECK_MESSAGES {
+            let mut bytes = vec![0; size as usize];
+            reader
+                .read_exact(&mut bytes)
+                .context("read protobuf input")?;
+            if message.parse_from_bytes(&bytes).is_err() {
+                return Ok(false);
+            }
+        }
+        frames += 1;
+        pos = reader
+            .seek(SeekFrom::Start(end))
+            .context("read protobuf input")?;
+    }
+    Ok(true)
+}
+
+// Reads a base-128 varint, or `None` at a clean end of input.
+fn read_varint<R: Read>(reader: &mut R) -> Result<Option<u64>> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        if reader.read(&mut byte).context("read protobuf input")? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            bail!("read protobuf message length: truncated varint");
+        }
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    bail!("read protobuf message length: varint is too long")
+}
+
+fn message_to_json(message: &dyn protobuf::MessageDyn, depth: usize) -> Map<String, JsonValue> {
+    let mut map = Map::new();
+    for field in message.descriptor_dyn().fields() {
+        let value = match field.get_reflect(message) {
+            ReflectFieldRef::Optional(value) if has_presence(&field) => value
+                .value()
+                .map_or(JsonValue::Null, |value| value_to_json(value, depth)),
+            ReflectFieldRef::Optional(_) => {
+                value_to_json(field.get_singular_field_or_default(message), depth)
+            }
+            ReflectFieldRef::Repeated(values) => JsonValue::Array(
+                values
+                    .into_iter()
+                    .map(|value| value_to_json(value, depth))
+                    .collect(),
+            ),
+            ReflectFieldRef::Map(entries) => JsonValue::Object(
+                (&entries)
+                    .into_iter()
+                    .map(|(key, value)| (map_key(key), value_to_json(value, depth)))
+                    .collect(),
+            ),
+        };
+        map.insert(field.name().to_string(), value);
+    }
+    map
+}
+
+fn value_to_json(value: ReflectValueRef<'_>, depth: usize) -> JsonValue {
+    match value {
+        ReflectValueRef::U32(v) => JsonValue::from(v),
+        ReflectValueRef::U64(v) => JsonValue::from(v),
+        ReflectValueRef::I32(v) => JsonValue::from(v),
+        ReflectValueRef::I64(v) => JsonValue::from(v),
+        ReflectValueRef::F32(v) => float(f64::from(v)),
+        ReflectValueRef::F64(v) => float(v),
+        ReflectValueRef::Bool(v) => JsonValue::from(v),
+        ReflectValueRef::String(v) => JsonValue::from(v),
+        ReflectValueRef::Bytes(v) => JsonValue::from(hex::encode(v)),
+        ReflectValueRef::Enum(descriptor, number) => match descriptor.value_by_number(number) {
+            Some(value) => JsonValue::from(value.name()),
+            None => JsonValue::from(number),
+        },
+        ReflectValueRef::Message(message) if depth < MAX_DEPTH => {
+            JsonValue::Object(message_to_json(&*message, depth + 1))
+        }
+        ReflectValueRef::Message(message) => JsonValue::from(message.to_string()),
+    }
+}
+
+fn map_key(key: ReflectValueRef<'_>) -> String {
+    match key {
+        ReflectValueRef::String(key) => key.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn float(value: f64) -> JsonValue {
+    serde_json::Number::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_proto(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("megrez_proto_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("event.proto");
+        std::fs::write(
+            &path,
+            r#"
+            syntax = "proto3";
+            package shop;
+            enum Kind { KIND_UNKNOWN = 0; KIND_ORDER = 1; }
+            message Item { string sku = 1; int32 qty = 2; }
+            message Event {
+              int64 id = 1;
+              Kind kind = 2;
+              repeated string tags = 3;
+              Item first = 4;
+              repeated Item items = 5;
+              optional double score = 6;
+            }
+            "#,
+        )
+        .unwrap();
+        path
+    }
+
+    fn options(path: PathBuf, framing: ProtoFraming) -> ProtobufOptions {
+        ProtobufOptions {
+            descriptors: vec![path],
+            message: Some("shop.Event".to_string()),
+            framing,
+        }
+    }
+
+    fn event(message: &MessageDescriptor, id: i64) -> Vec<u8> {
+        let mut event = message.new_instance();
+        let field = |name: &str| message.field_by_name(name).unwrap();
+        field("id").set_singular_field(&mut *event, id.into());
+        field("kind").set_singular_field(
+            &mut *event,
+            protobuf::reflect::ReflectValueBox::Enum(
+                message.file_descriptor().enums().next().unwrap(),
+                1,
+            ),
+        );
+        field("tags")
+            .mut_repeated(&mut *event)
+            .push("new".to_string().into());
+        event.write_to_bytes_dyn().unwrap()
+    }
+
+    #[test]
+    fn descriptor_maps_to_flattened_schema() {
+        let options = options(write_proto("schema"), ProtoFraming::Auto);
+        let schema = infer_schema(&options).unwrap();
+        let fields: Vec<(String, String, bool)> = schema
+            .fields
+            .iter()
+            .map(|f| (f.name.clone(), f.dtype.to_string(), f.nullable))
+            .collect();
+        let expected = [
+            ("id", "int", false),
+            ("kind", "string", false),
+            ("tags", "list<string>", false),
+            ("first.sku", "string", true),
+            ("first.qty", "int", true),
+            ("items", "list<struct>", false),
+            ("items.sku", "string", true),
+            ("items.qty", "int", true),
+            ("score", "float", true),
+        ];
+        assert_eq!(
+            fields,
+            expected
+                .iter()
+                .map(|(n, t, null)| (n.to_string(), t.to_string(), *null))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn delimited_and_single_messages_decode() {
+        let options = options(write_proto("decode"), ProtoFraming::Auto);
+        let message = options.message_descriptor().unwrap();
+
+        let mut stream = Vec::new();
+        for id in [1, 300] {
+            let bytes = event(&message, id);
+            stream.push(bytes.len() as u8);
+            stream.extend(bytes);
+        }
+        let records: Vec<JsonValue> = ProtobufSource::from_reader(&stream[..], &options)
+            .unwrap()
+            .map(|record| JsonValue::Object(record.unwrap()))
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[1],
+            serde_json::json!({
+                "id": 300, "kind": "KIND_ORDER", "tags": ["new"],
+                "first": null, "items": [], "score": null
+            })
+        );
+
+        let single = ProtobufSource::from_reader(&event(&message, 7)[..], &options).unwrap();
+        assert_eq!(single.framing, ProtoFraming::Single);
+        assert_eq!(single.count(), 1);
+    }
+}
@@ -0,0 +1,435 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use calamine::{Data, DataType as _, Ods, Range, Reader, Xlsb, Xlsx};
+use serde_json::Value as JsonValue;
+
+use crate::formats::Format;
+use crate::model::schema::{DataType, Field, Schema};
+use crate::source::{Record, RecordSource};
+use crate::util::sample::{SampleOptions, Sampler};
+
+pub const MAGIC_ZIP: &[u8; 4] = b"PK\x03\x04";
+
+const ODS_MIMETYPE: &[u8] = b"application/vnd.oasis.opendocument.spreadsheet";
+
+/// Which row of a sheet holds the column names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SheetHeader {
+    /// The first non-empty row of the sheet.
+    #[default]
+    FirstRow,
+    /// This 1-based sheet row; rows above it (such as a title) are skipped.
+    Row(usize),
+    /// Every row is data; names are generated as `column_1..N`.
+    Absent,
+}
+
+/// Options used when reading a workbook.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpreadsheetOptions {
+    /// Sheet name, or 1-based position if no sheet has that name; defaults to the first sheet.
+    pub sheet: Option<String>,
+    pub header: SheetHeader,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorkbookKind {
+    Xlsx,
+    Xlsb,
+    Ods,
+}
+
+/// Returns true if `reader` is a ZIP archive laid out as an Excel or OpenDocument workbook.
+pub fn is_workbook<R: Read + Seek>(reader: R) -> bool {
+    workbook_kind(reader).is_some()
+}
+
+/// Like [`is_workbook`], for the first bytes of a one-pass input: the workbook entries must
+/// be named in the local headers within the prefix.
+pub fn is_workbook_prefix(prefix: &[u8]) -> bool {
+    let contains = |needle: &[u8]| prefix.windows(needle.len()).any(|window| window == needle);
+    prefix.starts_with(MAGIC_ZIP) && (contains(b"xl/workbook.") || contains(ODS_MIMETYPE))
+}
+
+// Excel workbooks have an `xl/workbook.xml` (or `.bin`) entry; OpenDocument spreadsheets
+// declare themselves in a `mimetype` entry.
+fn workbook_kind<R: Read + Seek>(reader: R) -> Option<WorkbookKind> {
+    let mut archive = zip::ZipArchive::new(reader).ok()?;
+    if archive.index_for_name("xl/workbook.xml").is_some() {
+        return Some(WorkbookKind::Xlsx);
+    }
+    if archive.index_for_name("xl/workbook.bin").is_some() {
+        return Some(WorkbookKind::Xlsb);
+    }
+    let mut mimetype = Vec::new();
+    archive
+        .by_name("mimetype")
+        .ok()?
+        .take(ODS_MIMETYPE.len() as u64 + 1)
+        .read_to_end(&mut mimetype)
+        .ok()?;
+    (mimetype == ODS_MIMETYPE).then_some(WorkbookKind::Ods)
+}
+
+pub fn infer_schema(
+    path: &Path,
+    sample: &SampleOptions,
+    options: &SpreadsheetOptions,
+) -> Result<Schema> {
+    Ok(Sheet::open(path, options)?.infer_schema(sample))
+}
+
+/// Reads the rows of one worksheet as records, keyed by the header row.
+///
+/// Fully empty rows are skipped. Numbers without a fractional part are integers, and date
+/// cells are timestamps in milliseconds since the Unix epoch.
+pub struct SpreadsheetSource {
+    sheet: Sheet,
+    row: usize,
+    schema: Schema,
+}
+
+impl SpreadsheetSource {
+    pub fn open(path: &Path, sample: &SampleOptions, options: &SpreadsheetOptions) -> Result<Self> {
+        let sheet = Sheet::open(path, options)?;
+        let schema = sheet.infer_schema(sample);
+        Ok(Self::new(sheet, schema))
+    }
+
+    pub fn with_schema(path: &Path, schema: Schema, options: &SpreadsheetOptions) -> Result<Self> {
+        Ok(Self::new(Sheet::open(path, options)?, schema))
+    }
+
+    /// For input that cannot be read twice; the whole workbook is buffered in memory.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        sample: &SampleOptions,
+        options: &SpreadsheetOptions,
+    ) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).context("read spreadsheet")?;
+        let sheet = Sheet::load(Cursor::new(bytes), options)?;
+        let schema = sheet.infer_schema(sample);
+        Ok(Self::new(sheet, schema))
+    }
+
+    fn new(sheet: Sheet, schema: Schema) -> Self {
+        Self {
+            row: sheet.first_row,
+            sheet,
+            schema,
+        }
+    }
+}
+
+impl Iterator for SpreadsheetSource {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.sheet.next_row(self.row)?;
+        self.row = row + 1;
+        let record = self
+            .sheet
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(col, name)| (name.clone(), self.sheet.cell(row, col).1))
+            .collect();
+        Some(Ok(record))
+    }
+}
+
+impl RecordSource for SpreadsheetSource {
+    fn format(&self) -> Format {
+        Format::Spreadsheet
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
+
+// The used range of the selected worksheet, with column names resolved and the index of the
+// first data row (relative to the range).
+struct Sheet {
+    range: Range<Data>,
+    columns: Vec<String>,
+    first_row: usize,
+}
+
+impl Sheet {
+    fn open(path: &Path, options: &SpreadsheetOptions) -> Result<Self> {
+        let file = File::open(path).context("open spreadsheet")?;
+        Self::load(BufReader::new(file), options)
+    }
+
+    fn load<R: Read + Seek>(mut reader: R, options: &SpreadsheetOptions) -> Result<Self> {
+        let kind = workbook_kind(&mut reader).context("not an Excel or OpenDocument workbook")?;
+        reader.rewind().context("read spreadsheet")?;
+        let range = match kind {
+            WorkbookKind::Xlsx => read_sheet(Xlsx::new(reader)?, options)?,
+            WorkbookKind::Xlsb => read_sheet(Xlsb::new(reader)?, options)?,
+            WorkbookKind::Ods => read_sheet(Ods::new(reader)?, options)?,
+        };
+
+        let start_row = range.start().map_or(0, |(row, _)| row as usize);
+        // A header row above the used range is empty, so names are generated.
+        let (header, first_row) = match options.header {
+            SheetHeader::FirstRow => (Some(0), 1),
+            SheetHeader::Row(row) => match row.saturating_sub(1).checked_sub(start_row) {
+                Some(row) => (Some(row), row + 1),
+                None => (None, 0),
+            },
+            SheetHeader::Absent => (None, 0),
+        };
+        let mut columns: Vec<String> = Vec::with_capacity(range.width());
+        for col in 0..range.width() {
+            let name = match header.and_then(|row| range.get((row, col))) {
+                Some(Data::Empty) | None => format!("column_{}", col + 1),
+                Some(cell) => cell.to_string(),
+            };
+            let mut unique = name.clone();
+            let mut suffix = 2;
+            while columns.contains(&unique) {
+                unique = format!("{name}_{suffix}");
+                suffix += 1;
+            }
+            columns.push(unique);
+        }
+        Ok(Self {
+            range,
+            columns,
+            first_row,
+        })
+    }
+
+    // The first row at or after `row` with any non-empty cell.
+    fn next_row(&self, row: usize) -> Option<usize> {
+        (row..self.range.height()).find(|&row| {
+            (0..self.columns.len()).any(|col| {
+                self.range
+                    .get((row, col))
+                    .is_some_and(|cell| !cell.is_empty())
+            })
+        })
+    }
+
+    fn cell(&self, row: usize, col: usize) -> (DataType, JsonValue) {
+        self.range
+            .get((row, col))
+            .map_or((DataType::Null, JsonValue::Null), cell_value)
+    }
+
+    fn infer_schema(&self, sample: &SampleOptions) -> Schema {
+        let mut types = vec![DataType::Null; self.columns.len()];
+        let mut nullable = vec![false; self.columns.len()];
+        let mut sampler = Sampler::new(sample);
+        let mut visit = |row: usize| {
+            for col in 0..self.columns.len() {
+                let (dtype, _) = self.cell(row, col);
+                if dtype == DataType::Null {
+                    nullable[col] = true;
+                }
+                types[col] = DataType::merge(&types[col], &dtype);
+            }
+        };
+        let mut row = self.first_row;
+        while let Some(next) = self.next_row(row) {
+            if sampler.is_done() {
+                break;
+            }
+            if let Some(next) = sampler.offer(next) {
+                visit(next);
+            }
+            row = next + 1;
+        }
+        for row in sampler.finish() {
+            visit(row);
+        }
+        let fields = self
+            .columns
+            .iter()
+            .zip(types.into_iter().zip(nullable))
+            .map(|(name, (dtype, nullable))| Field {
+                name: name.clone(),
+                nullable: nullable || dtype == DataType::Null,
+                dtype,
+            })
+            .collect();
+        Schema { fields }
+    }
+}
+
+fn read_sheet<R, W>(mut workbook: W, options: &SpreadsheetOptions) -> Result<Range<Data>>
+where
+    R: Read + Seek,
+    W: Reader<R>,
+    W::Error: std::error::Error + Send + Sync + 'static,
+{
+    let names = workbook.sheet_names();
+    let name = match &options.sheet {
+        None => names
+            .first()
+            .ok_or_else(|| anyhow!("workbook has no sheets"))?,
+        Some(sheet) => names
+            .iter()
+            .find(|name| *name == sheet)
+            .or_else(|| {
+                let position: usize = sheet.parse().ok()?;
+                names.get(position.checked_sub(1)?)
+            })
+            .ok_or_else(|| anyhow!("sheet `{sheet}` not found (sheets: {})", names.join(", ")))?,
+    }
+    .clone();
+    workbook
+        .worksheet_range(&name)
+        .with_context(|| format!("read sheet `{name}`"))
+}
+
+// Maps a cell to its inferred type and the JSON value `cat` prints for it.
+fn cell_value(cell: &Data) -> (DataType, JsonValue) {
+    match cell {
+        Data::Empty => (DataType::Null, JsonValue::Null),
+        Data::Bool(value) => (DataType::Bool, JsonValue::from(*value)),
+        Data::Int(value) => (DataType::Int, JsonValue::from(*value)),
+        // Spreadsheets store every number as a float; whole numbers are read as integers.
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 9_007_199_254_740_992.0 => {
+            (DataType::Int, JsonValue::from(*value as i64))
+        }
+        Data::Float(value) => match serde_json::Number::from_f64(*value) {
+            Some(number) => (DataType::Float, JsonValue::Number(number)),
+            None => (DataType::Null, JsonValue::Null),
+        },
+        Data::String(value) => (DataType::String, JsonValue::from(value.as_str())),
+        Data::DateTime(value) if value.is_duration() => (
+            DataType::Float,
+            serde_json::Number::from_f64(value.as_f64()).map_or(JsonValue::Null, JsonValue::Number),
+        ),
+        Data::DateTime(_) | Data::DateTimeIso(_) => {
+            let datetime = cell
+                .as_datetime()
+                .or_else(|| cell.as_date()?.and_hms_opt(0, 0, 0));
+            match datetime {
+                Some(datetime) => (
+                    DataType::Timestamp,
+                    JsonValue::from(datetime.and_utc().timestamp_millis()),
+                ),
+                None => (DataType::String, JsonValue::from(cell.to_string())),
+            }
+        }
+        Data::DurationIso(_) | Data::Error(_) => {
+            (DataType::String, JsonValue::from(cell.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn xlsx(rows: &str) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let entries = [
+            (
+                "[Content_Types].xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string(),
+            ),
+            (
+                "xl/workbook.xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Data" sheetId="1" r:id="rId1"/></sheets></workbook>"#.to_string(),
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string(),
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{rows}</sheetData></worksheet>"#
+                ),
+            ),
+        ];
+        for (name, contents) in entries {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn read(
+        bytes: Vec<u8>,
+        options: &SpreadsheetOptions,
+    ) -> (Vec<(String, String, bool)>, Vec<JsonValue>) {
+        let source =
+            SpreadsheetSource::from_reader(&bytes[..], &SampleOptions::default(), options).unwrap();
+        let fields = source
+            .schema()
+            .fields
+            .iter()
+            .map(|f| (f.name.clone(), f.dtype.to_string(), f.nullable))
+            .collect();
+        let records = source
+            .map(|record| JsonValue::Object(record.unwrap()))
+            .collect();
+        (fields, records)
+    }
+
+    #[test]
+    fn cells_infer_types_and_skip_empty_rows() {
+        let bytes = xlsx(concat!(
+            r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Report</t></is></c></row>"#,
+            r#"<row r="2"><c r="A2" t="inlineStr"><is><t>id</t></is></c><c r="B2" t="inlineStr"><is><t>score</t></is></c><c r="C2" t="inlineStr"><is><t>ok</t></is></c></row>"#,
+            r#"<row r="3"><c r="A3"><v>1</v></c><c r="B3"><v>0.5</v></c><c r="C3" t="b"><v>1</v></c></row>"#,
+            r#"<row r="5"><c r="A5"><v>2</v></c><c r="B5"><v>3</v></c></row>"#,
+        ));
+        assert!(is_workbook(Cursor::new(&bytes)));
+        assert!(is_workbook_prefix(&bytes));
+
+        let options = SpreadsheetOptions {
+            sheet: Some("1".to_string()),
+            header: SheetHeader::Row(2),
+        };
+        let (fields, records) = read(bytes.clone(), &options);
+        assert_eq!(
+            fields,
+            vec![
+                ("id".to_string(), "int".to_string(), false),
+                ("score".to_string(), "float".to_string(), false),
+                ("ok".to_string(), "bool".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            records,
+            vec![
+                serde_json::json!({"id": 1, "score": 0.5, "ok": true}),
+                serde_json::json!({"id": 2, "score": 3, "ok": null}),
+            ]
+        );
+
+        let options = SpreadsheetOptions {
+            header: SheetHeader::Absent,
+            ..SpreadsheetOptions::default()
+        };
+        let (fields, records) = read(bytes, &options);
+        assert_eq!(fields[0].0, "column_1");
+        assert_eq!(records.len(), 4);
+    }
+
+    #[test]
+    fn unknown_sheet_lists_available_sheets() {
+        let options = SpreadsheetOptions {
+            sheet: Some("Summary".to_string()),
+            ..SpreadsheetOptions::default()
+        };
+        let err =
+            SpreadsheetSource::from_reader(&xlsx("")[..], &SampleOptions::default(), &options)
+                .err()
+                .unwrap();
+        assert_eq!(err.to_string(), "sheet `Summary` not found (sheets: Data)");
+    }
+}
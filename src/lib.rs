@@ -1,6 +1,13 @@
+pub mod batch;
 pub mod cli;
 pub mod commands;
+pub mod dataset;
 pub mod formats;
 pub mod model;
 pub mod render;
+pub mod source;
 pub mod util;
+pub mod view;
+
+pub use dataset::{Dataset, OpenOptions, open};
+pub use source::{Record, RecordSource};
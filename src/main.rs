@@ -1,3 +1,4 @@
+use std::io::{BufWriter, Write};
 use std::process;
 
 use anyhow::Result;
@@ -5,13 +6,24 @@ use clap::Parser;
 
 use megrez::cli::Cli;
 use megrez::commands;
-use megrez::util::errors::UnsupportedFormatError;
+use megrez::commands::cat::CatOptions;
+use megrez::commands::detect::DetectOptions;
+use megrez::commands::sample::SampleCommandOptions;
+use megrez::commands::schema::SchemaOptions;
+use megrez::commands::view::ViewOptions;
+use megrez::util::errors::{UnsupportedFormatError, is_broken_pipe};
 
 fn main() {
     let cli = Cli::parse();
-    let result = run(cli);
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let result = run(cli, &mut out);
+    let flushed = out.flush();
+    let result = result.and_then(|()| flushed.map_err(Into::into));
     match result {
         Ok(()) => process::exit(0),
+        // The reader went away (e.g. `megrez cat x | head`); nothing is left to do.
+        Err(err) if is_broken_pipe(&err) => process::exit(0),
         Err(err) => {
             if err.downcast_ref::<UnsupportedFormatError>().is_some() {
                 eprintln!("{err}");
@@ -23,23 +35,77 @@ fn main() {
     }
 }
 
-fn run(cli: Cli) -> Result<()> {
+fn run(cli: Cli, out: &mut impl Write) -> Result<()> {
     match cli.command {
         megrez::cli::Command::Schema {
             file,
-            format,
+            input,
             show_format_name,
             show_columns,
         } => commands::schema::run(
             &file,
-            format.map(|format| format.to_format()),
-            show_format_name,
-            show_columns,
+            &SchemaOptions {
+                show_format_name,
+                show_columns,
+                input: input.to_options(),
+            },
+            out,
         ),
         megrez::cli::Command::Cat {
             file,
-            format,
+            input,
             limit,
-        } => commands::cat::run(&file, format.map(|format| format.to_format()), limit),
+            offset,
+            columns,
+            output,
+        } => commands::cat::run(
+            &file,
+            &CatOptions {
+                offset,
+                limit,
+                columns,
+                output: output.map(|output| output.to_output()),
+                input: input.to_options(),
+            },
+            out,
+        ),
+        megrez::cli::Command::Detect {
+            file,
+            explain,
+            output,
+            strict,
+        } => commands::detect::run(
+            &file,
+            &DetectOptions {
+                explain,
+                output: output.to_output(),
+                mismatch_policy: megrez::cli::mismatch_policy(strict),
+            },
+            out,
+        ),
+        megrez::cli::Command::Sample {
+            file,
+            input,
+            rows,
+            fraction,
+            columns,
+            output,
+        } => commands::sample::run(
+            &file,
+            &SampleCommandOptions {
+                size: megrez::cli::sample_size(rows, fraction),
+                seed: input.sample.seed,
+                columns,
+                output: output.map(|output| output.to_output()),
+                input: input.to_options(),
+            },
+            out,
+        ),
+        megrez::cli::Command::View { file, input } => commands::view::run(
+            &file,
+            &ViewOptions {
+                input: input.to_options(),
+            },
+        ),
     }
 }
@@ -1 +1,2 @@
 pub mod schema;
+pub mod table;
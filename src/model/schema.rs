@@ -85,4 +85,37 @@ impl Schema {
     pub fn sort_by_name(&mut self) {
         self.fields.sort_by(|a, b| a.name.cmp(&b.name));
     }
+
+    /// Merges two schemas by field name, keeping first-seen field order. Field types are
+    /// combined with [`DataType::merge`], and fields missing from either side become nullable.
+    pub fn merge(&self, other: &Schema) -> Schema {
+        let mut fields = Vec::with_capacity(self.fields.len().max(other.fields.len()));
+        for field in &self.fields {
+            let merged = match other.field(&field.name) {
+                Some(theirs) => Field {
+                    name: field.name.clone(),
+                    dtype: DataType::merge(&field.dtype, &theirs.dtype),
+                    nullable: field.nullable || theirs.nullable,
+                },
+                None => Field {
+                    nullable: true,
+                    ..field.clone()
+                },
+            };
+            fields.push(merged);
+        }
+        for field in &other.fields {
+            if self.field(&field.name).is_none() {
+                fields.push(Field {
+                    nullable: true,
+                    ..field.clone()
+                });
+            }
+        }
+        Schema { fields }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
 }
@@ -0,0 +1,33 @@
+/// Table-level details of a table format (Delta Lake, Iceberg), reported by `schema`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableInfo {
+    /// The Delta table version being read.
+    pub version: Option<u64>,
+    /// The Iceberg snapshot being read; `None` for a table without snapshots.
+    pub snapshot_id: Option<i64>,
+    /// Partition fields: the column for identity partitions, otherwise `transform(column)`.
+    pub partitioning: Vec<String>,
+    /// IDs of the top-level fields, by name, for formats that assign them.
+    pub field_ids: Vec<(String, i64)>,
+    /// Every snapshot of the table, oldest first.
+    pub snapshots: Vec<TableSnapshot>,
+}
+
+impl TableInfo {
+    pub fn field_id(&self, name: &str) -> Option<i64> {
+        self.field_ids
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, id)| *id)
+    }
+}
+
+/// One snapshot (commit) in a table's history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSnapshot {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub timestamp_ms: i64,
+    /// The operation that produced the snapshot, such as `append`.
+    pub operation: Option<String>,
+}
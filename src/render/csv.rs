@@ -0,0 +1,61 @@
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::source::Record;
+
+/// Writes records as CSV with a header row.
+///
+/// The columns are every key seen, in order of first appearance. Missing and null values are
+/// empty cells and nested values are written as JSON text.
+pub fn write_records<W: Write>(writer: &mut W, records: &[Record]) -> Result<()> {
+    let mut columns: Vec<&str> = Vec::new();
+    for record in records {
+        for key in record.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key.as_str());
+            }
+        }
+    }
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(&columns)?;
+    for record in records {
+        csv.write_record(columns.iter().map(|column| cell(record.get(*column))))?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_union_of_columns() {
+        let records: Vec<Record> = [
+            serde_json::json!({"id": 1, "name": "a, b"}),
+            serde_json::json!({"id": 2, "tags": ["x"], "name": null}),
+        ]
+        .into_iter()
+        .map(|value| value.as_object().unwrap().clone())
+        .collect();
+        let mut out = Vec::new();
+        write_records(&mut out, &records).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id,name,tags\n1,\"a, b\",\n2,,\"[\"\"x\"\"]\"\n"
+        );
+    }
+}
@@ -1,2 +1,4 @@
+pub mod csv;
 pub mod jsonl;
 pub mod schema_text;
+pub mod table;
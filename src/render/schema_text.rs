@@ -3,27 +3,89 @@ use std::io::Write;
 use anyhow::Result;
 
 use crate::formats::Format;
+use crate::formats::json::JsonLayout;
 use crate::model::schema::Schema;
+use crate::model::table::TableInfo;
 
 pub fn render<W: Write>(
     schema: &Schema,
     format: Format,
+    json_layout: Option<JsonLayout>,
+    table: Option<&TableInfo>,
     show_format_name: bool,
     show_columns: bool,
     writer: &mut W,
 ) -> Result<()> {
     if show_format_name {
         writeln!(writer, "format: {}", format.as_str())?;
+        if let Some(layout) = json_layout {
+            writeln!(writer, "layout: {}", layout.as_str())?;
+        }
+        if let Some(table) = table {
+            render_table_header(table, writer)?;
+        }
     }
+    // Field IDs get their own column, for table formats that assign them (Iceberg).
+    let field_ids = table.filter(|table| !table.field_ids.is_empty());
     if show_columns {
-        writeln!(writer, "name\ttype\tnullable")?;
+        match field_ids {
+            Some(_) => writeln!(writer, "name\ttype\tnullable\tfield_id")?,
+            None => writeln!(writer, "name\ttype\tnullable")?,
+        }
     }
     for field in &schema.fields {
-        writeln!(
+        write!(
             writer,
             "{}\t{}\t{}",
             field.name, field.dtype, field.nullable
         )?;
+        if let Some(table) = field_ids {
+            match table.field_id(&field.name) {
+                Some(id) => write!(writer, "\t{id}")?,
+                None => write!(writer, "\t-")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    if let Some(table) = table
+        && !table.snapshots.is_empty()
+    {
+        render_snapshots(table, show_columns, writer)?;
+    }
+    Ok(())
+}
+
+fn render_table_header<W: Write>(table: &TableInfo, writer: &mut W) -> Result<()> {
+    if let Some(version) = table.version {
+        writeln!(writer, "version: {version}")?;
+    }
+    if let Some(snapshot) = table.snapshot_id {
+        writeln!(writer, "snapshot: {snapshot}")?;
+    }
+    if !table.partitioning.is_empty() {
+        writeln!(writer, "partitioning: {}", table.partitioning.join(", "))?;
+    }
+    Ok(())
+}
+
+// Snapshots follow the fields after a blank line, as a second table.
+fn render_snapshots<W: Write>(table: &TableInfo, show_columns: bool, writer: &mut W) -> Result<()> {
+    writeln!(writer)?;
+    if show_columns {
+        writeln!(writer, "snapshot_id\tparent_id\ttimestamp_ms\toperation")?;
+    }
+    for snapshot in &table.snapshots {
+        let parent = snapshot
+            .parent_id
+            .map_or_else(|| "-".to_string(), |id| id.to_string());
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            snapshot.id,
+            parent,
+            snapshot.timestamp_ms,
+            snapshot.operation.as_deref().unwrap_or("-")
+        )?;
     }
     Ok(())
 }
@@ -0,0 +1,234 @@
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::source::Record;
+use crate::util::text;
+
+/// Records measured to choose column widths; later values are truncated to fit.
+pub const WIDTH_SAMPLE_ROWS: usize = 100;
+const MAX_COLUMN_WIDTH: usize = 40;
+const MIN_COLUMN_WIDTH: usize = 4;
+const SEPARATOR: &str = "  ";
+
+struct Column {
+    name: String,
+    width: usize,
+    /// Every sampled value is a number, so the column is right-aligned.
+    numeric: bool,
+}
+
+/// Writes records as an aligned text table with a header row.
+///
+/// Nested objects are flattened into dotted columns (`user.id`) and lists are shown as compact
+/// JSON, cut short when too wide. Columns and widths come from the first
+/// [`WIDTH_SAMPLE_ROWS`] records; keys first seen after them are not shown. With `max_width`,
+/// the widest columns are narrowed until each line fits.
+pub fn write_records<W, I>(writer: &mut W, records: I, max_width: Option<usize>) -> Result<()>
+where
+    W: Write,
+    I: Iterator<Item = Result<Record>>,
+{
+    let mut records = records;
+    let mut sample = Vec::new();
+    for record in records.by_ref().take(WIDTH_SAMPLE_ROWS) {
+        sample.push(flatten(record?));
+    }
+    let mut columns = measure(&sample);
+    if columns.is_empty() {
+        return Ok(());
+    }
+    if let Some(max_width) = max_width {
+        narrow(&mut columns, max_width);
+    }
+
+    let header: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+    write_line(
+        writer,
+        &columns,
+        header.iter().map(String::as_str),
+        false,
+        max_width,
+    )?;
+    let rule: Vec<String> = columns.iter().map(|c| "-".repeat(c.width)).collect();
+    write_line(
+        writer,
+        &columns,
+        rule.iter().map(String::as_str),
+        false,
+        max_width,
+    )?;
+    for record in sample {
+        write_record(writer, &columns, &record, max_width)?;
+    }
+    for record in records {
+        write_record(writer, &columns, &flatten(record?), max_width)?;
+    }
+    Ok(())
+}
+
+fn write_record<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    record: &Record,
+    max_width: Option<usize>,
+) -> Result<()> {
+    let cells: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            record
+                .get(&column.name)
+                .map(text::cell_text)
+                .unwrap_or_default()
+        })
+        .collect();
+    write_line(
+        writer,
+        columns,
+        cells.iter().map(String::as_str),
+        true,
+        max_width,
+    )
+}
+
+fn write_line<'a, W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    cells: impl Iterator<Item = &'a str>,
+    align_numbers: bool,
+    max_width: Option<usize>,
+) -> Result<()> {
+    let mut line = String::new();
+    for (idx, (column, cell)) in columns.iter().zip(cells).enumerate() {
+        if idx > 0 {
+            line.push_str(SEPARATOR);
+        }
+        let cell = text::truncate(cell, column.width);
+        let pad = " ".repeat(column.width - text::width(&cell));
+        if align_numbers && column.numeric {
+            line.push_str(&pad);
+            line.push_str(&cell);
+        } else {
+            line.push_str(&cell);
+            line.push_str(&pad);
+        }
+    }
+    let line = line.trim_end();
+    let line = match max_width {
+        Some(max_width) => text::truncate(line, max_width),
+        None => line.to_string(),
+    };
+    writeln!(writer, "{line}")?;
+    Ok(())
+}
+
+// Columns in order of first appearance, each as wide as its header or widest sampled value.
+fn measure(sample: &[Record]) -> Vec<Column> {
+    let mut columns: Vec<Column> = Vec::new();
+    for record in sample {
+        for (key, value) in record {
+            let idx = match columns.iter().position(|column| &column.name == key) {
+                Some(idx) => idx,
+                None => {
+                    columns.push(Column {
+                        name: key.clone(),
+                        width: text::width(key),
+                        numeric: true,
+                    });
+                    columns.len() - 1
+                }
+            };
+            let column = &mut columns[idx];
+            column.width = column.width.max(text::width(&text::cell_text(value)));
+            column.numeric &= value.is_number() || value.is_null();
+        }
+    }
+    for column in &mut columns {
+        column.width = column.width.min(MAX_COLUMN_WIDTH);
+    }
+    columns
+}
+
+// Narrows the widest column one step at a time until the line fits or every column is at
+// the minimum width; anything still too long is cut off at the end of the line.
+fn narrow(columns: &mut [Column], max_width: usize) {
+    let spacing = SEPARATOR.len() * columns.len().saturating_sub(1);
+    let mut total: usize = spacing + columns.iter().map(|c| c.width).sum::<usize>();
+    while total > max_width {
+        let Some(widest) = columns
+            .iter_mut()
+            .filter(|column| column.width > MIN_COLUMN_WIDTH)
+            .max_by_key(|column| column.width)
+        else {
+            break;
+        };
+        widest.width -= 1;
+        total -= 1;
+    }
+}
+
+// Nested objects become dotted keys; an empty object stays as it is.
+fn flatten(record: Record) -> Record {
+    let mut flat = Map::new();
+    flatten_into(&mut flat, None, record);
+    flat
+}
+
+fn flatten_into(flat: &mut Record, prefix: Option<&str>, record: Record) {
+    for (key, value) in record {
+        let key = match prefix {
+            Some(prefix) => format!("{prefix}.{key}"),
+            None => key,
+        };
+        match value {
+            Value::Object(nested) if !nested.is_empty() => {
+                flatten_into(flat, Some(&key), nested);
+            }
+            other => {
+                flat.insert(key, other);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(values: Vec<Value>, max_width: Option<usize>) -> String {
+        let records = values
+            .into_iter()
+            .map(|value| Ok(value.as_object().unwrap().clone()));
+        let mut out = Vec::new();
+        write_records(&mut out, records, max_width).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn aligns_and_flattens() {
+        let table = render(
+            vec![
+                serde_json::json!({"id": 1, "user": {"name": "alice"}, "tags": ["a", "b"]}),
+                serde_json::json!({"id": 200, "user": {"name": null}, "tags": []}),
+            ],
+            None,
+        );
+        assert_eq!(
+            table,
+            "id   user.name  tags\n\
+             ---  ---------  ---------\n  \
+               1  alice      [\"a\",\"b\"]\n\
+             200             []\n"
+        );
+    }
+
+    #[test]
+    fn narrows_widest_columns_to_fit() {
+        let long = "x".repeat(30);
+        let table = render(vec![serde_json::json!({"id": 1, "note": long})], Some(20));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[2], " 1  xxxxxxxxxxxxxxx…");
+        assert!(lines.iter().all(|line| text::width(line) <= 20));
+    }
+}
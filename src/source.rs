@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use serde_json::{Map, Value};
+
+use crate::batch::{self, Batches};
+use crate::formats::Format;
+use crate::formats::json::JsonLayout;
+use crate::model::schema::Schema;
+use crate::model::table::TableInfo;
+
+/// One record as a JSON object, rendered the same way `cat` prints it: binary values are hex
+/// strings and dates/timestamps are numbers.
+pub type Record = Map<String, Value>;
+
+/// A stream of records with a known schema.
+///
+/// Implemented by every format reader and by [`crate::Dataset`], which combines the files of a
+/// directory or glob into a single source.
+pub trait RecordSource: Iterator<Item = Result<Record>> {
+    fn format(&self) -> Format;
+
+    fn schema(&self) -> &Schema;
+
+    /// How the records are laid out, for JSON input.
+    fn json_layout(&self) -> Option<JsonLayout> {
+        None
+    }
+
+    /// Version, partitioning and history, for table formats such as Delta Lake.
+    fn table_info(&self) -> Option<TableInfo> {
+        None
+    }
+
+    /// Reads the next record as the value it was read from. JSON values that are not objects
+    /// are returned as they are instead of as `{"value": ...}`, so JSON Lines output can
+    /// copy them unchanged.
+    fn next_value(&mut self) -> Option<Result<Value>> {
+        self.next().map(|record| record.map(Value::Object))
+    }
+
+    /// Skips up to `count` records, returning how many were skipped. Readers that can seek
+    /// (such as Parquet) override this to avoid decoding the skipped rows.
+    fn skip_records(&mut self, count: usize) -> Result<usize> {
+        skip_by_reading(self, count)
+    }
+
+    /// The Arrow schema of the batches returned by [`RecordSource::next_batch`]; by default
+    /// [`batch::arrow_schema`] of [`RecordSource::schema`].
+    fn arrow_schema(&self) -> SchemaRef {
+        Arc::new(batch::arrow_schema(self.schema()))
+    }
+
+    /// Reads up to `max_rows` records as one Arrow batch, or `None` at the end of the input.
+    /// Readers that decode Arrow natively (such as Parquet) override this.
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RecordBatch>> {
+        batch::read_batch(self, max_rows)
+    }
+
+    /// Converts the source into an iterator of Arrow batches of up to `batch_size` rows.
+    fn into_batches(self, batch_size: usize) -> Batches<Self>
+    where
+        Self: Sized,
+    {
+        Batches::new(self, batch_size)
+    }
+}
+
+impl<S: RecordSource + ?Sized> RecordSource for Box<S> {
+    fn format(&self) -> Format {
+        (**self).format()
+    }
+
+    fn schema(&self) -> &Schema {
+        (**self).schema()
+    }
+
+    fn json_layout(&self) -> Option<JsonLayout> {
+        (**self).json_layout()
+    }
+
+    fn table_info(&self) -> Option<TableInfo> {
+        (**self).table_info()
+    }
+
+    fn next_value(&mut self) -> Option<Result<Value>> {
+        (**self).next_value()
+    }
+
+    fn skip_records(&mut self, count: usize) -> Result<usize> {
+        (**self).skip_records(count)
+    }
+
+    fn arrow_schema(&self) -> SchemaRef {
+        (**self).arrow_schema()
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RecordBatch>> {
+        (**self).next_batch(max_rows)
+    }
+}
+
+pub(crate) fn skip_by_reading<S>(source: &mut S, count: usize) -> Result<usize>
+where
+    S: Iterator<Item = Result<Record>> + ?Sized,
+{
+    let mut skipped = 0;
+    while skipped < count {
+        match source.next() {
+            Some(record) => {
+                record?;
+                skipped += 1;
+            }
+            None => break,
+        }
+    }
+    Ok(skipped)
+}
+
+/// Wraps a non-object value (such as a scalar JSON document) as `{"value": ...}`, matching the
+/// field name schema inference uses for it.
+pub(crate) fn into_record(value: Value) -> Record {
+    match value {
+        Value::Object(map) => map,
+        other => {
+            let mut map = Map::new();
+            map.insert("value".to_string(), other);
+            map
+        }
+    }
+}
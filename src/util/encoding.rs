@@ -0,0 +1,267 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Chain, Cursor, Read};
+use std::str::FromStr;
+
+use anyhow::Result;
+use chardetng::EncodingDetector;
+use encoding_rs::{DecoderResult, EUC_JP, Encoding, SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE};
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+
+use crate::util::io::read_prefix;
+
+/// How much leading input is examined to guess its encoding.
+pub const SNIFF_LEN: usize = 64 * 1024;
+/// How many leading bytes are examined for the NUL bytes of UTF-16 without a BOM.
+const UTF16_SNIFF_LEN: usize = 1024;
+
+/// The encoding of text input (CSV and JSON), which is transcoded to UTF-8 before it is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// A byte order mark, else UTF-16 by the NUL bytes of its ASCII characters, else UTF-8
+    /// if the input is valid UTF-8, else a guess from the byte statistics (such as
+    /// windows-1252 or Shift_JIS).
+    #[default]
+    Auto,
+    /// This encoding, unless a byte order mark says otherwise.
+    Fixed(&'static Encoding),
+}
+
+impl FromStr for TextEncoding {
+    type Err = String;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        if label.eq_ignore_ascii_case("auto") {
+            return Ok(TextEncoding::Auto);
+        }
+        Encoding::for_label(label.as_bytes())
+            .map(TextEncoding::Fixed)
+            .ok_or_else(|| {
+                format!(
+                    "unknown encoding `{label}` (e.g. utf-8, utf-16le, windows-1252, shift_jis)"
+                )
+            })
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextEncoding::Auto => f.write_str("auto"),
+            TextEncoding::Fixed(encoding) => f.write_str(encoding.name()),
+        }
+    }
+}
+
+/// Input transcoded to UTF-8, with any byte order mark removed.
+pub type TextReader<R> = DecodeReaderBytes<Chain<Cursor<Vec<u8>>, R>, Vec<u8>>;
+
+/// Transcodes `reader` to UTF-8. UTF-8 input is passed through unchanged apart from its byte
+/// order mark, so invalid bytes are left for the parser to report.
+pub fn decode<R: Read>(mut reader: R, encoding: TextEncoding) -> Result<TextReader<R>> {
+    let (encoding, prefix) = match encoding {
+        TextEncoding::Auto => {
+            let prefix = read_prefix(&mut reader, SNIFF_LEN)?;
+            (sniff(&prefix), prefix)
+        }
+        TextEncoding::Fixed(encoding) => (encoding, Vec::new()),
+    };
+    let mut builder = DecodeReaderBytesBuilder::new();
+    builder
+        .utf8_passthru(true)
+        .strip_bom(true)
+        .bom_override(true);
+    if encoding != UTF_8 {
+        builder.encoding(Some(encoding));
+    }
+    Ok(builder.build(Cursor::new(prefix).chain(reader)))
+}
+
+/// Guesses the encoding of input from its first bytes.
+pub fn sniff(prefix: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(prefix) {
+        return encoding;
+    }
+    if let Some(encoding) = utf16_without_bom(prefix) {
+        return encoding;
+    }
+    match std::str::from_utf8(prefix) {
+        Ok(_) => UTF_8,
+        // A character cut off at the end of the prefix.
+        Err(err) if err.error_len().is_none() => UTF_8,
+        Err(_) => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(prefix, false);
+            let guess = detector.guess(None, false);
+            if guess.is_single_byte() {
+                // A few lines of Japanese text are too little for the byte statistics, which
+                // then favour a single-byte encoding.
+                let japanese = detector.guess(Some(b"jp"), false);
+                if reads_as_japanese(japanese, prefix) {
+                    return japanese;
+                }
+            }
+            guess
+        }
+    }
+}
+
+// Whether `prefix` is Shift_JIS or EUC-JP text in `encoding`: it decodes without errors, and
+// most of its other characters are kana or common (JIS level 1) kanji. Accented Latin
+// letters, such as `é` followed by `l`, can form valid pairs too, but they decode as rare
+// level 2 kanji or half-width katakana.
+fn reads_as_japanese(encoding: &'static Encoding, prefix: &[u8]) -> bool {
+    if !(encoding == SHIFT_JIS || encoding == EUC_JP) || !decodes(encoding, prefix) {
+        return false;
+    }
+    let (mut common, mut total) = (0usize, 0usize);
+    let mut bytes = prefix.iter();
+    while let Some(&lead) = bytes.next() {
+        if lead.is_ascii() {
+            continue;
+        }
+        let (len, is_common) = if encoding == SHIFT_JIS {
+            match lead {
+                0x81..=0x9f | 0xe0..=0xfc => (2, lead <= 0x98),
+                _ => (1, false),
+            }
+        } else {
+            match lead {
+                0x8f => (3, false),
+                0x8e => (2, false),
+                _ => (2, (0xa1..=0xcf).contains(&lead)),
+            }
+        };
+        for _ in 1..len {
+            bytes.next();
+        }
+        total += 1;
+        common += usize::from(is_common);
+    }
+    common * 2 > total
+}
+
+// Whether `prefix` is valid in `encoding`, allowing a character cut off at its end.
+fn decodes(encoding: &'static Encoding, prefix: &[u8]) -> bool {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let Some(len) = decoder.max_utf8_buffer_length_without_replacement(prefix.len()) else {
+        return false;
+    };
+    let mut text = String::with_capacity(len);
+    let (result, _) = decoder.decode_to_string_without_replacement(prefix, &mut text, false);
+    result == DecoderResult::InputEmpty
+}
+
+/// The first bytes as UTF-8 when they start with a byte order mark or look like UTF-16, for
+/// content sniffing, with the encoding found. Other input is left alone: CSV and JSON syntax
+/// is ASCII, which every other supported encoding shares.
+pub fn decode_prefix(prefix: &[u8]) -> Option<(Cow<'_, [u8]>, &'static Encoding)> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(prefix) {
+        let text = &prefix[bom_len..];
+        if encoding == UTF_8 {
+            return Some((Cow::Borrowed(text), encoding));
+        }
+        let (decoded, _) = encoding.decode_without_bom_handling(text);
+        return Some((Cow::Owned(decoded.into_owned().into_bytes()), encoding));
+    }
+    let encoding = utf16_without_bom(prefix)?;
+    let (decoded, _) = encoding.decode_without_bom_handling(prefix);
+    Some((Cow::Owned(decoded.into_owned().into_bytes()), encoding))
+}
+
+// ASCII text in UTF-16 has a NUL in every other byte: the odd ones in little-endian order,
+// the even ones in big-endian order.
+fn utf16_without_bom(prefix: &[u8]) -> Option<&'static Encoding> {
+    let units: Vec<&[u8]> = prefix[..prefix.len().min(UTF16_SNIFF_LEN)]
+        .chunks_exact(2)
+        .collect();
+    if units.is_empty() {
+        return None;
+    }
+    let share = |zeros: usize| zeros * 10 >= units.len() * 4;
+    let rare = |zeros: usize| zeros * 10 < units.len();
+    let high = units
+        .iter()
+        .filter(|unit| unit[1] == 0 && unit[0] != 0)
+        .count();
+    let low = units
+        .iter()
+        .filter(|unit| unit[0] == 0 && unit[1] != 0)
+        .count();
+    if share(high) && rare(low) {
+        Some(UTF_16LE)
+    } else if share(low) && rare(high) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(input: &[u8], encoding: TextEncoding) -> String {
+        let mut text = String::new();
+        decode(input, encoding)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn sniffs_and_transcodes_to_utf8() {
+        let utf16: Vec<u8> = "id,名前\n1,a\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(sniff(&utf16), UTF_16LE);
+        assert_eq!(decoded(&utf16, TextEncoding::Auto), "id,名前\n1,a\n");
+
+        let bom = b"\xEF\xBB\xBFid,name\n";
+        assert_eq!(decoded(bom, TextEncoding::Auto), "id,name\n");
+        let (text, encoding) = decode_prefix(bom).unwrap();
+        assert_eq!((text.as_ref(), encoding), (&b"id,name\n"[..], UTF_8));
+        assert!(decode_prefix(b"id,name\n").is_none());
+
+        let latin1 = b"city\nK\xF8benhavn\nM\xE4lm\xF6\n";
+        assert_eq!(
+            decoded(latin1, TextEncoding::Auto),
+            "city\nKøbenhavn\nMälmö\n"
+        );
+
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("都市,人口\n東京,1400\n大阪,880\n");
+        let fixed: TextEncoding = "shift_jis".parse().unwrap();
+        assert_eq!(
+            decoded(&shift_jis, fixed),
+            "都市,人口\n東京,1400\n大阪,880\n"
+        );
+        assert!("klingon".parse::<TextEncoding>().is_err());
+    }
+
+    #[test]
+    fn sniffs_short_japanese_input() {
+        for text in ["名前,年齢\n山田,30\n鈴木,40\n", "あい,う\nかき,1\n"] {
+            let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode(text);
+            assert_eq!(sniff(&shift_jis), SHIFT_JIS);
+            assert_eq!(decoded(&shift_jis, TextEncoding::Auto), text);
+            let (euc_jp, _, _) = encoding_rs::EUC_JP.encode(text);
+            assert_eq!(decoded(&euc_jp, TextEncoding::Auto), text);
+        }
+        // Accented Latin pairs that are valid Shift_JIS too keep the single-byte guess.
+        for text in [
+            "city\nZürich,München\n",
+            "prenom,ville\nHélène,Genève\n",
+            "€uro,amount\r\n€5,10\r\n",
+        ] {
+            let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode(text);
+            assert!(sniff(&latin1).is_single_byte(), "{text:?}");
+        }
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode("city\nZürich,München\n");
+        assert_eq!(
+            decoded(&latin1, TextEncoding::Auto),
+            "city\nZürich,München\n"
+        );
+    }
+}
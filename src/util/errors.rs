@@ -1,3 +1,5 @@
+use std::io;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -13,3 +15,18 @@ impl UnsupportedFormatError {
         }
     }
 }
+
+/// Returns true if `err` was caused by writing to a closed pipe, as when output is piped
+/// into `head`.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        let kind = if let Some(err) = cause.downcast_ref::<io::Error>() {
+            Some(err.kind())
+        } else if let Some(err) = cause.downcast_ref::<serde_json::Error>() {
+            err.io_error_kind()
+        } else {
+            None
+        };
+        kind == Some(io::ErrorKind::BrokenPipe)
+    })
+}
@@ -0,0 +1,4 @@
+/// Lower-case hex digits of `bytes`, used to show binary values in JSON output.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
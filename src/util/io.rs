@@ -1,14 +1,18 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Write};
+use std::io::{self, BufRead, IsTerminal, Read};
 use std::path::Path;
 
 use anyhow::Result;
+use ratatui::crossterm::terminal;
 
-pub fn stream_file(path: &Path, writer: &mut impl Write) -> Result<()> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    io::copy(&mut reader, writer)?;
-    Ok(())
+/// The width of the terminal stdout is writing to, else the `COLUMNS` environment variable;
+/// `None` when output goes elsewhere.
+pub fn terminal_width() -> Option<usize> {
+    if io::stdout().is_terminal()
+        && let Ok((width, _)) = terminal::size()
+    {
+        return Some(usize::from(width));
+    }
+    std::env::var("COLUMNS").ok()?.parse().ok()
 }
 
 pub fn is_stdin_path(path: &Path) -> bool {
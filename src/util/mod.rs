@@ -1,2 +1,7 @@
+pub mod encoding;
 pub mod errors;
+pub mod hex;
 pub mod io;
+pub mod sample;
+pub mod text;
+pub mod window;
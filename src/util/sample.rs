@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+
+pub const DEFAULT_SAMPLE_ROWS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SampleMode {
+    /// Take the first N records.
+    #[default]
+    Head,
+    /// Reservoir-sample N records uniformly from the whole input.
+    Random,
+    /// Keep evenly spaced records from the whole input.
+    Stratified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleOptions {
+    /// Maximum number of records to inspect; `None` scans every record.
+    pub rows: Option<usize>,
+    pub mode: SampleMode,
+    pub seed: u64,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        Self {
+            rows: Some(DEFAULT_SAMPLE_ROWS),
+            mode: SampleMode::Head,
+            seed: 0,
+        }
+    }
+}
+
+impl SampleOptions {
+    pub fn full_scan() -> Self {
+        Self {
+            rows: None,
+            ..Self::default()
+        }
+    }
+}
+
+/// Selects records from a stream according to [`SampleOptions`].
+///
+/// Head sampling and full scans hand records straight back from [`Sampler::offer`] so they
+/// can be processed without buffering. Random and stratified sampling must see the whole
+/// input before the sample is known, so they retain up to `rows` records until
+/// [`Sampler::finish`].
+pub struct Sampler<T> {
+    mode: SampleMode,
+    limit: Option<usize>,
+    items: Vec<T>,
+    seen: u64,
+    stride: u64,
+    rng: Rng,
+}
+
+impl<T> Sampler<T> {
+    pub fn new(options: &SampleOptions) -> Self {
+        let mode = if options.rows.is_none() {
+            SampleMode::Head
+        } else {
+            options.mode
+        };
+        Self {
+            mode,
+            limit: options.rows,
+            items: Vec::new(),
+            seen: 0,
+            stride: 1,
+            rng: Rng::new(options.seed),
+        }
+    }
+
+    /// Returns true once no further record can enter the sample.
+    pub fn is_done(&self) -> bool {
+        match (self.mode, self.limit) {
+            (SampleMode::Head, Some(limit)) => self.seen >= limit as u64,
+            _ => false,
+        }
+    }
+
+    /// Offers the next record, returning it if the caller should process it immediately.
+    pub fn offer(&mut self, item: T) -> Option<T> {
+        self.offer_with(|| item)
+    }
+
+    /// Like [`Sampler::offer`], but only builds the record when it enters the sample, such as a
+    /// row copied out of a larger batch.
+    pub fn offer_with(&mut self, item: impl FnOnce() -> T) -> Option<T> {
+        let index = self.seen;
+        self.seen += 1;
+        let Some(limit) = self.limit else {
+            return Some(item());
+        };
+        if limit == 0 {
+            return None;
+        }
+        match self.mode {
+            SampleMode::Head => (index < limit as u64).then(item),
+            SampleMode::Random => {
+                if self.items.len() < limit {
+                    self.items.push(item());
+                } else {
+                    let slot = self.rng.below(index + 1);
+                    if slot < limit as u64 {
+                        self.items[slot as usize] = item();
+                    }
+                }
+                None
+            }
+            SampleMode::Stratified => {
+                if !index.is_multiple_of(self.stride) {
+                    return None;
+                }
+                if self.items.len() >= limit {
+                    self.compact();
+                    if !index.is_multiple_of(self.stride) {
+                        return None;
+                    }
+                }
+                self.items.push(item());
+                None
+            }
+        }
+    }
+
+    /// Returns the retained sample in input order (random samples are unordered).
+    pub fn finish(self) -> Vec<T> {
+        self.items
+    }
+
+    // Drops every other retained record and doubles the stride, so the kept records stay
+    // evenly spaced over everything seen so far.
+    fn compact(&mut self) {
+        let mut idx = 0usize;
+        self.items.retain(|_| {
+            let keep = idx.is_multiple_of(2);
+            idx += 1;
+            keep
+        });
+        self.stride *= 2;
+    }
+}
+
+/// Chooses `count` distinct indices uniformly from `0..total`, in ascending order.
+///
+/// Used when the number of records is known up front (such as from a Parquet footer), so
+/// only the chosen records need to be read.
+pub fn choose_indices(total: u64, count: usize, rng: &mut Rng) -> Vec<u64> {
+    let count = (count as u64).min(total);
+    // Floyd's algorithm: one draw per chosen index, whatever the total.
+    let mut chosen = HashSet::with_capacity(count as usize);
+    for upper in total - count..total {
+        let candidate = rng.below(upper + 1);
+        if !chosen.insert(candidate) {
+            chosen.insert(upper);
+        }
+    }
+    let mut chosen: Vec<u64> = chosen.into_iter().collect();
+    chosen.sort_unstable();
+    chosen
+}
+
+/// Chooses each index in `0..total` independently with probability `fraction`, in ascending
+/// order.
+pub fn bernoulli_indices(total: u64, fraction: f64, rng: &mut Rng) -> Vec<u64> {
+    if fraction >= 1.0 {
+        return (0..total).collect();
+    }
+    let mut chosen = Vec::new();
+    if fraction <= 0.0 {
+        return chosen;
+    }
+    // The gap to the next chosen index is geometric, so unchosen indices cost nothing.
+    let log_miss = (1.0 - fraction).ln();
+    let mut next = 0u64;
+    loop {
+        let gap = ((1.0 - rng.next_f64()).ln() / log_miss).floor();
+        if gap >= (total - next) as f64 {
+            return chosen;
+        }
+        next += gap as u64;
+        chosen.push(next);
+        next += 1;
+    }
+}
+
+/// Small deterministic PRNG (SplitMix64) so seeded samples are reproducible across builds.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a value in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(options: &SampleOptions, count: u64) -> Vec<u64> {
+        let mut sampler = Sampler::new(options);
+        let mut out = Vec::new();
+        for value in 0..count {
+            if sampler.is_done() {
+                break;
+            }
+            if let Some(value) = sampler.offer(value) {
+                out.push(value);
+            }
+        }
+        out.extend(sampler.finish());
+        out
+    }
+
+    #[test]
+    fn head_takes_prefix() {
+        let options = SampleOptions {
+            rows: Some(3),
+            ..SampleOptions::default()
+        };
+        assert_eq!(run(&options, 10), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn random_is_seeded_and_bounded() {
+        let options = SampleOptions {
+            rows: Some(5),
+            mode: SampleMode::Random,
+            seed: 42,
+        };
+        let first = run(&options, 1000);
+        assert_eq!(first.len(), 5);
+        assert_eq!(first, run(&options, 1000));
+        assert!(first.iter().any(|value| *value >= 5));
+    }
+
+    #[test]
+    fn chosen_indices_are_distinct_and_sorted() {
+        let mut rng = Rng::new(7);
+        let chosen = choose_indices(100, 10, &mut rng);
+        assert_eq!(chosen.len(), 10);
+        assert!(chosen.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(chosen.iter().all(|index| *index < 100));
+        assert_eq!(choose_indices(3, 10, &mut rng), vec![0, 1, 2]);
+
+        let kept = bernoulli_indices(10_000, 0.1, &mut Rng::new(7));
+        assert!((800..1200).contains(&kept.len()));
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn stratified_spans_input() {
+        let options = SampleOptions {
+            rows: Some(4),
+            mode: SampleMode::Stratified,
+            seed: 0,
+        };
+        assert_eq!(run(&options, 16), vec![0, 4, 8, 12]);
+    }
+}
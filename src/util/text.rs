@@ -0,0 +1,57 @@
+use serde_json::Value;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The number of terminal columns `text` takes up.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Shortens `text` to at most `max` terminal columns, ending in `…` when anything was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width + 1 > max {
+            break;
+        }
+        out.push(ch);
+        used += ch_width;
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// A value as one line of text for a grid cell: strings unquoted, null empty and nested values
+/// as compact JSON, with line breaks and tabs shown as spaces.
+pub fn cell_text(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    text.replace(['\n', '\r', '\t'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_by_display_width() {
+        assert_eq!(truncate("megrez", 6), "megrez");
+        assert_eq!(truncate("megrez", 4), "meg…");
+        // Each of these takes two columns.
+        assert_eq!(truncate("東京都港区", 5), "東京…");
+        assert_eq!(cell_text(&serde_json::json!("a\tb\nc")), "a b c");
+        assert_eq!(
+            cell_text(&serde_json::json!({"a": [1, null]})),
+            r#"{"a":[1,null]}"#
+        );
+    }
+}
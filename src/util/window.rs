@@ -0,0 +1,77 @@
+/// Offset/limit bookkeeping shared across every file of a dataset, so `--offset` and
+/// `--limit` count records over the whole input rather than per file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowWindow {
+    offset: usize,
+    remaining: Option<usize>,
+}
+
+impl RowWindow {
+    pub fn new(offset: usize, limit: Option<usize>) -> Self {
+        Self {
+            offset,
+            remaining: limit,
+        }
+    }
+
+    /// Returns how many leading records are still to be skipped.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns how many more records may be emitted; `None` without a limit.
+    pub fn remaining(&self) -> Option<usize> {
+        self.remaining
+    }
+
+    /// Returns true once the limit has been reached.
+    pub fn is_done(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Consumes the next record, returning true if it should be emitted.
+    pub fn admit(&mut self) -> bool {
+        if self.offset > 0 {
+            self.offset -= 1;
+            return false;
+        }
+        match &mut self.remaining {
+            Some(0) => false,
+            Some(remaining) => {
+                *remaining -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Consumes `count` records at once, such as the rows of a batch, once the offset has been
+    /// skipped.
+    pub fn admit_rows(&mut self, count: usize) {
+        debug_assert_eq!(self.offset, 0);
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.saturating_sub(count);
+        }
+    }
+
+    /// Skips up to `available` records at once, returning how many were skipped.
+    pub fn skip(&mut self, available: usize) -> usize {
+        let skipped = self.offset.min(available);
+        self.offset -= skipped;
+        skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_spans_multiple_inputs() {
+        let mut window = RowWindow::new(3, Some(2));
+        assert_eq!(window.skip(2), 2);
+        let admitted: Vec<bool> = (0..3).map(|_| window.admit()).collect();
+        assert_eq!(admitted, vec![false, true, true]);
+        assert!(window.is_done());
+    }
+}
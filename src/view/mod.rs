@@ -0,0 +1,519 @@
+//! State and key handling for `megrez view`, kept apart from the terminal so it can be tested.
+
+mod ui;
+
+pub use ui::{draw, page_size};
+
+use anyhow::Result;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::model::schema::Schema;
+use crate::source::{Record, RecordSource};
+use crate::util::text;
+
+/// Rows measured when choosing initial column widths.
+const WIDTH_SAMPLE_ROWS: usize = 100;
+const MIN_COLUMN_WIDTH: u16 = 3;
+const MAX_INITIAL_WIDTH: u16 = 32;
+
+/// Records read from a source only as far as the view has needed them.
+///
+/// Readers decode on demand, so a Parquet file's row groups are read as scrolling reaches
+/// them rather than up front.
+struct Rows {
+    source: Box<dyn RecordSource>,
+    records: Vec<Record>,
+    done: bool,
+}
+
+impl Rows {
+    // Reads until at least `count` records are loaded or the input ends.
+    fn load(&mut self, count: usize) -> Result<()> {
+        while !self.done && self.records.len() < count {
+            match self.source.next() {
+                Some(record) => self.records.push(record?),
+                None => self.done = true,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A grid column: a top-level record key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub width: u16,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Grid,
+    Schema,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    /// Typing a `/` search query.
+    Search(String),
+    /// The selected record, pretty-printed, scrolled down by this many lines.
+    Detail(u16),
+}
+
+pub struct App {
+    title: String,
+    schema: Schema,
+    rows: Rows,
+    columns: Vec<Column>,
+    row: usize,
+    top: usize,
+    column: usize,
+    left: usize,
+    page: usize,
+    focus: Focus,
+    schema_cursor: usize,
+    mode: Mode,
+    last_search: Option<String>,
+    message: Option<String>,
+}
+
+impl App {
+    /// Loads the first `page` records and sizes the columns from them.
+    pub fn new(title: String, source: Box<dyn RecordSource>, page: usize) -> Result<Self> {
+        let schema = source.schema().clone();
+        let mut app = Self {
+            title,
+            schema,
+            rows: Rows {
+                source,
+                records: Vec::new(),
+                done: false,
+            },
+            columns: Vec::new(),
+            row: 0,
+            top: 0,
+            column: 0,
+            left: 0,
+            page: page.max(1),
+            focus: Focus::Grid,
+            schema_cursor: 0,
+            mode: Mode::Normal,
+            last_search: None,
+            message: None,
+        };
+        app.rows.load(app.page.max(WIDTH_SAMPLE_ROWS))?;
+        app.add_columns(0);
+        Ok(app)
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.rows.records
+    }
+
+    /// Whether every record has been read.
+    pub fn is_complete(&self) -> bool {
+        self.rows.done
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    pub fn focus(&self) -> Focus {
+        self.focus
+    }
+
+    pub fn schema_cursor(&self) -> usize {
+        self.schema_cursor
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The type shown for a column in the schema pane. Flattened JSON fields such as
+    /// `user.id` make `user` a struct.
+    pub fn column_type(&self, name: &str) -> String {
+        if let Some(field) = self.schema.field(name) {
+            return field.dtype.to_string();
+        }
+        let nested = format!("{name}.");
+        if self
+            .schema
+            .fields
+            .iter()
+            .any(|f| f.name.starts_with(&nested))
+        {
+            "struct".to_string()
+        } else {
+            "-".to_string()
+        }
+    }
+
+    /// Sets how many rows fit on screen, loading enough records to fill it.
+    pub fn set_page(&mut self, page: usize) -> Result<()> {
+        self.page = page.max(1);
+        self.scroll_to(self.row)
+    }
+
+    /// Keeps the selected column on screen given the grid's width in terminal columns.
+    pub fn fit_columns(&mut self, width: u16) {
+        if self.column < self.left {
+            self.left = self.column;
+        }
+        loop {
+            let used: u16 = self.columns[self.left..=self.column.min(self.columns.len() - 1)]
+                .iter()
+                .filter(|column| !column.hidden)
+                .map(|column| column.width + 1)
+                .sum();
+            if used <= width || self.left >= self.column {
+                break;
+            }
+            self.left += 1;
+        }
+    }
+
+    /// Handles one key press, returning false when the viewer should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
+        self.message = None;
+        match self.mode.clone() {
+            Mode::Search(query) => self.search_key(key, query)?,
+            Mode::Detail(scroll) => self.detail_key(key, scroll),
+            Mode::Normal if self.focus == Focus::Schema => return Ok(self.schema_key(key)),
+            Mode::Normal => return self.grid_key(key),
+        }
+        Ok(true)
+    }
+
+    /// The selected record as indented JSON, for the detail popup.
+    pub fn detail_text(&self) -> String {
+        match self.rows.records.get(self.row) {
+            Some(record) => serde_json::to_string_pretty(record).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    fn grid_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let page = self.page;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(false);
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_to(self.row + 1)?,
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_to(self.row.saturating_sub(1))?,
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_to(self.row + page)?,
+            KeyCode::PageUp => self.scroll_to(self.row.saturating_sub(page))?,
+            KeyCode::Home | KeyCode::Char('g') => self.scroll_to(0)?,
+            KeyCode::End | KeyCode::Char('G') => self.scroll_to(usize::MAX)?,
+            KeyCode::Right | KeyCode::Char('l') => self.move_column(true),
+            KeyCode::Left | KeyCode::Char('h') => self.move_column(false),
+            KeyCode::Char('>') | KeyCode::Char('+') => self.resize_column(2),
+            KeyCode::Char('<') | KeyCode::Char('-') => self.resize_column(-2),
+            KeyCode::Char('x') => self.hide_column(),
+            KeyCode::Char('a') => self.columns.iter_mut().for_each(|c| c.hidden = false),
+            KeyCode::Char('/') => self.mode = Mode::Search(String::new()),
+            KeyCode::Char('n') => {
+                if let Some(query) = self.last_search.clone() {
+                    self.search(&query)?;
+                }
+            }
+            KeyCode::Enter if !self.rows.records.is_empty() => self.mode = Mode::Detail(0),
+            KeyCode::Tab => self.focus = Focus::Schema,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn schema_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab => self.focus = Focus::Grid,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.schema_cursor = (self.schema_cursor + 1).min(self.columns.len() - 1);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.schema_cursor = self.schema_cursor.saturating_sub(1);
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                let cursor = self.schema_cursor;
+                if let Some(column) = self.columns.get_mut(cursor) {
+                    column.hidden = !column.hidden;
+                }
+                if self.columns.iter().all(|column| column.hidden) {
+                    self.columns[cursor].hidden = false;
+                    self.message = Some("at least one column must stay visible".to_string());
+                }
+                self.select_visible_column();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn search_key(&mut self, key: KeyEvent, mut query: String) -> Result<()> {
+        match key.code {
+            KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                if !query.is_empty() {
+                    self.search(&query)?;
+                    self.last_search = Some(query);
+                }
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                self.mode = Mode::Search(query);
+            }
+            KeyCode::Char(ch) => {
+                query.push(ch);
+                self.mode = Mode::Search(query);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn detail_key(&mut self, key: KeyEvent, scroll: u16) {
+        self.mode = match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => Mode::Normal,
+            KeyCode::Down | KeyCode::Char('j') => Mode::Detail(scroll.saturating_add(1)),
+            KeyCode::Up | KeyCode::Char('k') => Mode::Detail(scroll.saturating_sub(1)),
+            KeyCode::PageDown | KeyCode::Char(' ') => Mode::Detail(scroll.saturating_add(10)),
+            KeyCode::PageUp => Mode::Detail(scroll.saturating_sub(10)),
+            _ => Mode::Detail(scroll),
+        };
+    }
+
+    // Selects `row`, reading ahead so the page below it is loaded; `usize::MAX` reads to the end.
+    fn scroll_to(&mut self, row: usize) -> Result<()> {
+        let loaded = self.rows.records.len();
+        self.rows
+            .load(row.saturating_add(self.page).saturating_add(1))?;
+        self.add_columns(loaded);
+        self.row = row.min(self.rows.records.len().saturating_sub(1));
+        if self.row < self.top {
+            self.top = self.row;
+        } else if self.row >= self.top + self.page {
+            self.top = self.row + 1 - self.page;
+        }
+        Ok(())
+    }
+
+    // Finds the next record after the selected one with a visible cell containing `query`
+    // (ignoring case), wrapping around to the start.
+    fn search(&mut self, query: &str) -> Result<()> {
+        let loaded = self.rows.records.len();
+        let found = self.find(&query.to_lowercase())?;
+        self.add_columns(loaded);
+        match found {
+            Some(index) => self.scroll_to(index)?,
+            None => self.message = Some(format!("no match for `{query}`")),
+        }
+        Ok(())
+    }
+
+    fn find(&mut self, query: &str) -> Result<Option<usize>> {
+        let start = self.row + 1;
+        let mut index = start;
+        loop {
+            self.rows.load(index + 1)?;
+            if index >= self.rows.records.len() {
+                break;
+            }
+            if self.matches(index, query) {
+                return Ok(Some(index));
+            }
+            index += 1;
+        }
+        Ok((0..start.min(self.rows.records.len())).find(|index| self.matches(*index, query)))
+    }
+
+    // Keys not yet shown as columns count as visible.
+    fn matches(&self, index: usize, query: &str) -> bool {
+        let hidden = |key: &str| {
+            self.columns
+                .iter()
+                .any(|column| column.hidden && column.name == key)
+        };
+        self.rows.records[index].iter().any(|(key, value)| {
+            !hidden(key) && text::cell_text(value).to_lowercase().contains(query)
+        })
+    }
+
+    fn move_column(&mut self, forward: bool) {
+        let visible: Vec<usize> = (0..self.columns.len())
+            .filter(|idx| !self.columns[*idx].hidden)
+            .collect();
+        let Some(pos) = visible.iter().position(|idx| *idx == self.column) else {
+            return;
+        };
+        let next = if forward {
+            visible.get(pos + 1)
+        } else {
+            pos.checked_sub(1).and_then(|pos| visible.get(pos))
+        };
+        if let Some(next) = next {
+            self.column = *next;
+        }
+    }
+
+    fn resize_column(&mut self, delta: i32) {
+        if let Some(column) = self.columns.get_mut(self.column) {
+            let width = (i32::from(column.width) + delta).clamp(MIN_COLUMN_WIDTH.into(), 200);
+            column.width = width as u16;
+        }
+    }
+
+    fn hide_column(&mut self) {
+        if self.columns.iter().filter(|column| !column.hidden).count() <= 1 {
+            self.message = Some("at least one column must stay visible".to_string());
+            return;
+        }
+        self.columns[self.column].hidden = true;
+        self.select_visible_column();
+    }
+
+    // Moves the column selection off a hidden column, preferring the next visible one.
+    fn select_visible_column(&mut self) {
+        if !self.columns[self.column].hidden {
+            return;
+        }
+        let next = (self.column..self.columns.len())
+            .chain((0..self.column).rev())
+            .find(|idx| !self.columns[*idx].hidden);
+        if let Some(next) = next {
+            self.column = next;
+            self.left = self.left.min(next);
+        }
+    }
+
+    // Adds columns for keys first seen in records loaded from `from` on. Widths fit the header
+    // and the first rows' values, up to a limit; the rest are truncated.
+    fn add_columns(&mut self, from: usize) {
+        if self.columns.is_empty() {
+            for field in &self.schema.fields {
+                let name = field.name.split('.').next().unwrap_or(&field.name);
+                let name = if self.schema.field(name).is_some()
+                    || self.rows.records.iter().any(|r| r.contains_key(name))
+                {
+                    name
+                } else {
+                    &field.name
+                };
+                if !self.columns.iter().any(|column| column.name == name) {
+                    self.columns.push(new_column(name));
+                }
+            }
+        }
+        let known = self.columns.len();
+        for record in &self.rows.records[from..] {
+            for key in record.keys() {
+                if !self.columns.iter().any(|column| &column.name == key) {
+                    self.columns.push(new_column(key));
+                }
+            }
+        }
+        let sample = &self.rows.records[..self.rows.records.len().min(WIDTH_SAMPLE_ROWS)];
+        let widths_from = if from == 0 { 0 } else { known };
+        for column in &mut self.columns[widths_from..] {
+            let widest = sample
+                .iter()
+                .filter_map(|record| record.get(&column.name))
+                .map(|value| text::width(&text::cell_text(value)))
+                .max()
+                .unwrap_or(0);
+            let widest = widest.max(text::width(&column.name));
+            column.width = (widest as u16).clamp(MIN_COLUMN_WIDTH, MAX_INITIAL_WIDTH);
+        }
+        if self.columns.is_empty() {
+            self.columns.push(new_column("value"));
+        }
+    }
+}
+
+fn new_column(name: &str) -> Column {
+    Column {
+        name: name.to_string(),
+        width: MIN_COLUMN_WIDTH,
+        hidden: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::OpenOptions;
+
+    fn app(input: &'static str) -> App {
+        let source = OpenOptions::new().open_reader(input.as_bytes()).unwrap();
+        App::new("test".to_string(), Box::new(source), 2).unwrap()
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> bool {
+        app.handle_key(KeyEvent::from(code)).unwrap()
+    }
+
+    #[test]
+    fn scrolls_and_sizes_columns() {
+        let mut app = app("id,city\n1,Oslo\n2,Reykjavik\n3,Bergen\n");
+        let widths: Vec<u16> = app.columns().iter().map(|c| c.width).collect();
+        assert_eq!(widths, vec![3, 9]);
+        press(&mut app, KeyCode::Char('G'));
+        assert_eq!((app.row(), app.top()), (2, 1));
+        assert!(app.is_complete());
+        press(&mut app, KeyCode::Char('>'));
+        assert_eq!(app.columns()[0].width, 5);
+        assert!(!press(&mut app, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn searches_hides_columns_and_shows_detail() {
+        let mut app = app("{\"id\":1,\"tags\":[\"a\"]}\n{\"id\":2,\"tags\":[\"needle\"]}\n");
+        for ch in "/NEEDLE".chars() {
+            press(&mut app, KeyCode::Char(ch));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.row(), 1);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.detail_text().contains("\"needle\""));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode(), &Mode::Normal);
+
+        press(&mut app, KeyCode::Char('x'));
+        assert!(app.columns()[0].hidden);
+        assert_eq!(app.column(), 1);
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.message(), Some("at least one column must stay visible"));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char(' '));
+        assert!(!app.columns()[0].hidden);
+    }
+}
@@ -0,0 +1,180 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table};
+
+use super::{App, Focus, Mode};
+use crate::util::text;
+
+const SCHEMA_PANE_WIDTH: u16 = 32;
+const HELP: &str = "↑↓ rows  ←→ columns  </> width  x hide  a show all  Tab schema  / search  n next  Enter record  q quit";
+
+/// How many rows the grid shows in a terminal `height` lines tall: everything but the
+/// status line, the grid's borders and its header.
+pub fn page_size(height: u16) -> usize {
+    usize::from(height.saturating_sub(4)).max(1)
+}
+
+/// Draws the schema pane, the grid, the status line and the record popup if it is open.
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let pane = SCHEMA_PANE_WIDTH.min(main.width / 3);
+    let [schema, grid] =
+        Layout::horizontal([Constraint::Length(pane), Constraint::Min(1)]).areas(main);
+    draw_schema(frame, app, schema);
+    draw_grid(frame, app, grid);
+    draw_status(frame, app, status);
+    if let Mode::Detail(scroll) = app.mode() {
+        draw_detail(frame, app, *scroll);
+    }
+}
+
+fn draw_schema(frame: &mut Frame, app: &App, area: Rect) {
+    let width = usize::from(area.width.saturating_sub(2));
+    let items: Vec<ListItem> = app
+        .columns()
+        .iter()
+        .map(|column| {
+            let mark = if column.hidden { "[ ]" } else { "[x]" };
+            let line = format!("{mark} {}: {}", column.name, app.column_type(&column.name));
+            let item = ListItem::new(text::truncate(&line, width));
+            if column.hidden {
+                item.style(Style::new().add_modifier(Modifier::DIM))
+            } else {
+                item
+            }
+        })
+        .collect();
+    let mut state = ListState::default();
+    if app.focus() == Focus::Schema {
+        state.select(Some(app.schema_cursor()));
+    }
+    let list = List::new(items)
+        .block(pane_block(" Schema ", app.focus() == Focus::Schema))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_grid(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.fit_columns(area.width.saturating_sub(2));
+    let shown: Vec<usize> = (app.left()..app.columns().len())
+        .filter(|idx| !app.columns()[*idx].hidden)
+        .collect();
+    let columns = app.columns();
+    let widths = shown
+        .iter()
+        .map(|idx| Constraint::Length(columns[*idx].width));
+    let header = Row::new(shown.iter().map(|idx| {
+        let column = &columns[*idx];
+        let style = if *idx == app.column() {
+            Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            Style::new().add_modifier(Modifier::BOLD)
+        };
+        Cell::from(text::truncate(&column.name, usize::from(column.width))).style(style)
+    }));
+    let rows = app
+        .records()
+        .iter()
+        .enumerate()
+        .skip(app.top())
+        // Borders and header take three lines.
+        .take(usize::from(area.height.saturating_sub(3)))
+        .map(|(index, record)| {
+            let cells = shown.iter().map(|idx| {
+                let column = &columns[*idx];
+                let value = record.get(&column.name).map(text::cell_text);
+                let cell = Cell::from(text::truncate(
+                    &value.unwrap_or_default(),
+                    usize::from(column.width),
+                ));
+                if index == app.row() && *idx == app.column() {
+                    cell.style(Style::new().add_modifier(Modifier::BOLD))
+                } else {
+                    cell
+                }
+            });
+            let row = Row::new(cells);
+            if index == app.row() {
+                row.style(Style::new().add_modifier(Modifier::REVERSED))
+            } else {
+                row
+            }
+        });
+    let title = format!(" {} ", app.title());
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(1)
+        .block(pane_block(&title, app.focus() == Focus::Grid));
+    frame.render_widget(table, area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let line = match (app.mode(), app.message()) {
+        (Mode::Search(query), _) => format!("/{query}"),
+        (_, Some(message)) => message.to_string(),
+        _ => {
+            let loaded = app.records().len();
+            let more = if app.is_complete() { "" } else { "+" };
+            let row = if loaded == 0 { 0 } else { app.row() + 1 };
+            format!("row {row}/{loaded}{more}  {HELP}")
+        }
+    };
+    let width = usize::from(area.width);
+    frame.render_widget(Paragraph::new(text::truncate(&line, width)), area);
+}
+
+fn draw_detail(frame: &mut Frame, app: &App, scroll: u16) {
+    let area = frame.area();
+    let popup = Rect {
+        x: area.width / 10,
+        y: area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+    let title = format!(" Record {} (Esc to close) ", app.row() + 1);
+    let detail = Paragraph::new(app.detail_text())
+        .block(Block::bordered().title(title))
+        .scroll((scroll, 0));
+    frame.render_widget(Clear, popup);
+    frame.render_widget(detail, popup);
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::new().add_modifier(Modifier::BOLD))
+    } else {
+        block.border_style(Style::new().add_modifier(Modifier::DIM))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::dataset::OpenOptions;
+
+    #[test]
+    fn draws_schema_pane_and_grid() {
+        let input = "id,city\n1,Oslo\n2,Reykjavik\n";
+        let source = OpenOptions::new().open_reader(input.as_bytes()).unwrap();
+        let mut app = App::new("cities.csv (CSV)".to_string(), Box::new(source), 4).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+        let screen: Vec<String> = terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(60)
+            .map(|line| line.iter().map(|cell| cell.symbol()).collect())
+            .collect();
+        assert!(screen[0].contains("Schema") && screen[0].contains("cities.csv (CSV)"));
+        assert!(screen[1].contains("[x] id: int") && screen[1].contains("id  city"));
+        assert!(screen[3].contains("2   Reykjavik"));
+        assert!(screen[7].starts_with("row 1/2"));
+    }
+}
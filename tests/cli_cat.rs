@@ -2,7 +2,8 @@ mod util;
 
 use anyhow::Result;
 use serde_json::Value;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
 
 #[test]
 fn cat_parquet_limit() -> Result<()> {
@@ -49,3 +50,516 @@ fn cat_avro_limit() -> Result<()> {
     assert_eq!(lines, expected);
     Ok(())
 }
+
+#[test]
+fn cat_csv_jsonl_typed() -> Result<()> {
+    let path = util::fixtures_dir().join("sample.csv");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--output", "jsonl"])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("parse json"))
+        .collect();
+
+    let expected = vec![
+        serde_json::json!({"id": 1, "name": "alice", "score": 10.5, "active": true}),
+        serde_json::json!({"id": 2, "name": "bob", "score": 7.0, "active": false}),
+        serde_json::json!({"id": 3, "name": null, "score": null, "active": null}),
+    ];
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+#[test]
+fn cat_csv_numeric_bool_tokens_keep_ids_numeric() -> Result<()> {
+    let path = util::write_temp_file("flags.csv", b"id,flag\n1,yes\n2,no\n3,yes\n")?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--output", "jsonl"])
+        .args(["--true-values", "yes,Y,1", "--false-values", "no,N,0"])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first: Value = serde_json::from_str(stdout.lines().next().unwrap())?;
+    assert_eq!(first, serde_json::json!({"id": 1, "flag": true}));
+    Ok(())
+}
+
+#[test]
+fn cat_json_array_to_jsonl_with_offset() -> Result<()> {
+    let path = util::fixtures_dir().join("sample.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            path.to_str().unwrap(),
+            "--output",
+            "jsonl",
+            "--offset",
+            "1",
+            "--limit",
+            "1",
+        ])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "{\"id\":2,\"user\":{\"id\":\"u2\",\"active\":null},\"tags\":[]}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_glob_limit_spans_files() -> Result<()> {
+    let dir = util::temp_dir("cat_dataset")?;
+    std::fs::write(dir.join("part-0.csv"), "id\n1\n2\n")?;
+    std::fs::write(dir.join("part-1.csv"), "id\n3\n4\n")?;
+    let pattern = dir.join("part-*.csv");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            pattern.to_str().unwrap(),
+            "--output",
+            "jsonl",
+            "--offset",
+            "1",
+            "--limit",
+            "2",
+        ])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "{\"id\":2}\n{\"id\":3}\n");
+    Ok(())
+}
+
+#[test]
+fn cat_directory_defaults_to_jsonl_across_files() -> Result<()> {
+    let dir = util::temp_dir("cat_dataset_raw")?;
+    std::fs::write(dir.join("part-0.csv"), "id\n1\n2\n")?;
+    std::fs::write(dir.join("part-1.csv"), "id\n3\n4\n")?;
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_megrez"))
+            .arg("cat")
+            .arg(&dir)
+            .args(args)
+            .output()
+            .expect("run megrez cat")
+    };
+
+    let output = run(&["--limit", "3"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n"
+    );
+
+    // Without a window the files are still records, so the header is not repeated.
+    let output = run(&[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?.lines().count(), 4);
+
+    let output = run(&["--output", "raw"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("raw output copies a single file"));
+    Ok(())
+}
+
+#[test]
+fn cat_hive_partitions_injected_and_pruned() -> Result<()> {
+    let dir = util::temp_dir("cat_partitions")?;
+    let keep = dir.join("year=2024").join("month=03");
+    let pruned = dir.join("year=2023").join("month=12");
+    std::fs::create_dir_all(&keep)?;
+    std::fs::create_dir_all(&pruned)?;
+    std::fs::write(keep.join("part-0.csv"), "id\n1\n")?;
+    // Unreadable on purpose: the filter must prune this directory without opening it.
+    std::fs::write(pruned.join("part-0.parquet"), "not parquet")?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            dir.to_str().unwrap(),
+            "--output",
+            "jsonl",
+            "--filter",
+            "year=2024",
+            "--filter",
+            "month=3",
+        ])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "{\"id\":1,\"year\":2024,\"month\":3}\n");
+    Ok(())
+}
+
+#[test]
+fn cat_partition_value_wins_over_file_column() -> Result<()> {
+    let dir = util::temp_dir("cat_partition_precedence")?;
+    let partition = dir.join("year=2024");
+    std::fs::create_dir_all(&partition)?;
+    std::fs::write(partition.join("part-0.csv"), "id,year\n1,1999\n")?;
+
+    // A single partitioned file is still read as records, so the partition column is kept.
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", dir.to_str().unwrap()])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "{\"id\":1,\"year\":2024}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_parquet_offset_and_columns() {
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            "tests/fixtures/sample.parquet",
+            "--offset",
+            "1",
+            "--columns",
+            "name,id",
+        ])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "{\"name\":\"bob\",\"id\":2}\n");
+}
+
+#[test]
+fn cat_json_array_copies_elements_that_are_not_objects() -> Result<()> {
+    let path = util::write_temp_file("scalars.json", br#"[1, "a", [2], {"b": 3}]"#)?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--output", "jsonl"])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "1\n\"a\"\n[2]\n{\"b\":3}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_nested_column_keeps_its_object() {
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", "tests/fixtures/sample.json", "--columns", "user.id"])
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "{\"user\":{\"id\":\"u1\"}}\n{\"user\":{\"id\":\"u2\"}}\n"
+    );
+}
+
+#[test]
+fn cat_runs_in_process_with_any_writer() -> Result<()> {
+    let path = util::ensure_avro_fixture()?;
+    let options = megrez::commands::cat::CatOptions {
+        limit: Some(1),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    megrez::commands::cat::run(&path, &options, &mut out)?;
+    assert_eq!(
+        String::from_utf8(out)?,
+        "{\"id\":1,\"name\":\"alice\",\"active\":true}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_closed_pipe_exits_quietly() -> Result<()> {
+    let rows: String = (0..200_000)
+        .map(|idx| format!("{idx},row {idx}\n"))
+        .collect();
+    let path = util::write_temp_file("closed_pipe.csv", format!("id,name\n{rows}").as_bytes())?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--output", "jsonl"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut first = [0u8; 16];
+    child.stdout.take().unwrap().read_exact(&mut first)?;
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn cat_arrow_ipc_file_and_stdin_stream() -> Result<()> {
+    let expected = "{\"id\":1,\"name\":\"alice\",\"active\":true}\n";
+    let path = util::write_temp_file("sample.feather", &util::arrow_ipc_bytes(false)?)?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--limit", "1"])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", "-", "--limit", "1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&util::arrow_ipc_bytes(true)?)?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    Ok(())
+}
+
+#[test]
+fn cat_msgpack_stdin_detected_by_leading_map() -> Result<()> {
+    // {"id": 1, "name": "a"} then {"id": 2}.
+    let bytes = b"\x82\xa2id\x01\xa4name\xa1a\x81\xa2id\x02";
+    let mut child = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(bytes)?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":1,\"name\":\"a\"}\n{\"id\":2}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_stdin_warns_that_spread_sampling_reads_the_head() -> Result<()> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", "-", "--output", "jsonl", "--sampling", "random"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(b"id\n1\n2\n")?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "{\"id\":1}\n{\"id\":2}\n"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("warning: --sampling random is not supported for stdin")
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_delta_table_snapshot_and_time_travel() -> Result<()> {
+    let table = util::delta_table("cat_delta")?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", table.to_str().unwrap(), "--columns", "id,region"])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":1,\"region\":\"eu\"}\n{\"id\":2,\"region\":\"eu\"}\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", table.to_str().unwrap(), "--as-of-version", "0"])
+        .args(["--offset", "1", "--limit", "2"])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":2,\"name\":\"bob\",\"active\":false,\"region\":\"eu\"}\n\
+         {\"id\":1,\"name\":\"alice\",\"active\":true,\"region\":\"us\"}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_iceberg_metadata_file_reads_live_files() -> Result<()> {
+    let table = util::iceberg_table("cat_iceberg")?;
+    let metadata = table.join("metadata/v1.metadata.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", metadata.to_str().unwrap()])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":1,\"name\":\"alice\",\"active\":true,\"score\":null}\n\
+         {\"id\":2,\"name\":\"bob\",\"active\":false,\"score\":null}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_json_records_path() -> Result<()> {
+    let path = util::write_temp_file(
+        "cat_envelope.json",
+        br#"{"data": {"items": [{"id": 1}, {"id": 2}]}, "next": null}"#,
+    )?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            path.to_str().unwrap(),
+            "--records-path",
+            "data.items",
+        ])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":1}\n{\"id\":2}\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            path.to_str().unwrap(),
+            "--records-path",
+            "$.data.rows",
+        ])
+        .output()
+        .expect("run megrez cat");
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr).trim(),
+        "records path `$.data.rows` not found"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_spreadsheet_sheet_and_header_row() {
+    let path = util::fixtures_dir().join("sample.xlsx");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--limit", "1"])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":1,\"name\":\"alice\",\"joined\":1705307400000,\"active\":true}\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            path.to_str().unwrap(),
+            "--sheet",
+            "notes",
+            "--header-row",
+            "3",
+        ])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"topic\":\"intro\",\"score\":2.5}\n"
+    );
+}
+
+#[test]
+fn cat_protobuf_delimited_messages() -> Result<()> {
+    let proto = util::write_temp_file(
+        "point.proto",
+        b"syntax = \"proto3\";\npackage geo;\nmessage Point { int32 x = 1; string label = 2; }\n",
+    )?;
+    // Two length-delimited `geo.Point` messages: {x: 1, label: "a"} and {x: 2, label: "b"}.
+    let data = util::write_temp_file(
+        "points.bin",
+        &[5, 0x08, 1, 0x12, 1, b'a', 5, 0x08, 2, 0x12, 1, b'b'],
+    )?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", data.to_str().unwrap(), "--message", "geo.Point"])
+        .args(["--proto-descriptor", proto.to_str().unwrap()])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"x\":1,\"label\":\"a\"}\n{\"x\":2,\"label\":\"b\"}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cat_table_output_fits_columns_env() -> Result<()> {
+    let path = util::ensure_parquet_fixture()?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--output", "table"])
+        .env("COLUMNS", "18")
+        .output()
+        .expect("run megrez cat");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "id  name   active");
+    assert_eq!(lines[1], "--  -----  ------");
+    assert_eq!(lines[2], " 1  alice  true");
+    assert!(lines.iter().all(|line| line.chars().count() <= 18));
+    Ok(())
+}
+
+#[test]
+fn cat_transcodes_legacy_encodings_to_utf8() -> Result<()> {
+    let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("都市,人口\n東京,1400\n大阪,880\n");
+    let path = util::write_temp_file("cities_sjis.csv", &shift_jis)?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--encoding", "shift_jis"])
+        .args(["--output", "jsonl", "--limit", "1"])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"都市\":\"東京\",\"人口\":1400}\n"
+    );
+
+    // Raw output is transcoded too; windows-1252 is detected without --encoding.
+    let path = util::write_temp_file("cities_latin1.csv", b"city\nK\xF8benhavn\nMalm\xF6\n")?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap()])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "city\nKøbenhavn\nMalmö\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,140 @@
+mod util;
+
+use anyhow::Result;
+use serde_json::Value;
+use std::process::Command;
+
+#[test]
+fn detect_prints_format_name() -> Result<()> {
+    let path = util::fixtures_dir().join("sample.csv");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["detect", path.to_str().unwrap()])
+        .output()
+        .expect("run megrez detect");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "CSV\n");
+    Ok(())
+}
+
+#[test]
+fn detect_explain_reports_extension_conflict() -> Result<()> {
+    let path = util::ensure_mislabeled_parquet_fixture()?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["detect", path.to_str().unwrap(), "--explain"])
+        .output()
+        .expect("run megrez detect");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = "format: PARQUET\n\
+                    rule: magic\n\
+                    confidence: high\n\
+                    conflict: extension says AVRO (`.avro` extension)\n\
+                    \n\
+                    rule\tformat\tevidence\n\
+                    table\t-\tnot a Delta Lake or Iceberg table\n\
+                    magic\tPARQUET\tleading bytes `PAR1`\n\
+                    footer\tPARQUET\ttrailing bytes `PAR1`\n\
+                    extension\tAVRO\t`.avro` extension\n\
+                    content\t-\tbinary data\n";
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
+#[test]
+fn detect_json_output_on_failure() -> Result<()> {
+    let path = util::write_temp_file("detect_unknown.bin", b"\0\0\0\x07data")?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["detect", path.to_str().unwrap(), "--output", "json"])
+        .output()
+        .expect("run megrez detect");
+    assert_eq!(output.status.code(), Some(2));
+    let report: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["format"], Value::Null);
+    assert_eq!(report["confidence"], Value::Null);
+    assert_eq!(report["evidence"].as_array().unwrap().len(), 5);
+    assert_eq!(
+        report["evidence"][3]["detail"],
+        "`.bin` is not a known extension"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("format detection failed"));
+    Ok(())
+}
+
+#[test]
+fn strict_mode_fails_on_mislabeled_file() -> Result<()> {
+    let path = util::write_temp_file("strict_mislabeled.json", b"id,name\n1,a\n")?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", path.to_str().unwrap(), "--strict"])
+        .output()
+        .expect("run megrez schema");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("format mismatch: "));
+    assert!(stderr.contains(
+        "`.json` extension but the content is CSV (first line parses as CSV with 2 fields)"
+    ));
+
+    // An explicit format is trusted.
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", path.to_str().unwrap(), "--strict", "--format", "csv"])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn strict_warn_reports_and_reads_as_detected() -> Result<()> {
+    let path = util::ensure_mislabeled_parquet_fixture()?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args([
+            "cat",
+            path.to_str().unwrap(),
+            "--strict=warn",
+            "--limit",
+            "1",
+        ])
+        .output()
+        .expect("run megrez cat");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("warning: "));
+    assert!(stderr.contains("`.avro` extension but the content is PARQUET (leading bytes `PAR1`)"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["detect", path.to_str().unwrap(), "--strict"])
+        .output()
+        .expect("run megrez detect");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "PARQUET\n");
+    Ok(())
+}
+
+#[test]
+fn unsupported_format_is_named() -> Result<()> {
+    let path = util::write_temp_file("unsupported.csv", b"SQLite format 3\0\x10\0\x01\x01")?;
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", path.to_str().unwrap()])
+        .output()
+        .expect("run megrez schema");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).starts_with(
+            "this looks like an SQLite database (leading bytes `SQLite format 3\\x00`)"
+        )
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["detect", path.to_str().unwrap(), "--output", "json"])
+        .output()
+        .expect("run megrez detect");
+    assert_eq!(output.status.code(), Some(2));
+    let report: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["format"], Value::Null);
+    assert_eq!(report["unsupported"], "SQLITE");
+    assert_eq!(report["conflicts"][0]["format"], "CSV");
+    Ok(())
+}
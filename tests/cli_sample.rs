@@ -0,0 +1,143 @@
+mod util;
+
+use anyhow::Result;
+use serde_json::Value;
+use std::process::{Command, Output};
+
+fn megrez(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(args)
+        .output()
+        .expect("run megrez sample")
+}
+
+fn ids(output: &Output) -> Vec<i64> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let record: Value = serde_json::from_str(line).expect("parse json");
+            record["id"].as_i64().expect("integer id")
+        })
+        .collect()
+}
+
+#[test]
+fn sample_parquet_row_groups_is_seeded() -> Result<()> {
+    let path = util::parquet_row_groups("sample_groups.parquet", 1000, 100)?;
+    let path = path.to_str().unwrap();
+    let first = megrez(&["sample", path, "-n", "20", "--seed", "42"]);
+    assert!(first.status.success());
+    let first_ids = ids(&first);
+    assert_eq!(first_ids.len(), 20);
+    assert!(first_ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(first_ids.iter().any(|id| *id >= 100));
+
+    let again = megrez(&["sample", path, "-n", "20", "--seed", "42"]);
+    assert_eq!(first.stdout, again.stdout);
+    let other = megrez(&["sample", path, "-n", "20", "--seed", "7"]);
+    assert_ne!(first_ids, ids(&other));
+
+    let line = String::from_utf8_lossy(&first.stdout)
+        .lines()
+        .next()
+        .map(str::to_string)
+        .unwrap();
+    let record: Value = serde_json::from_str(&line)?;
+    assert_eq!(record["label"], format!("row-{}", record["id"]));
+    Ok(())
+}
+
+#[test]
+fn sample_parquet_output_round_trips() -> Result<()> {
+    let path = util::parquet_row_groups("sample_round_trip.parquet", 500, 50)?;
+    let output = megrez(&[
+        "sample",
+        path.to_str().unwrap(),
+        "--fraction",
+        "0.1",
+        "--seed",
+        "3",
+        "--columns",
+        "id",
+        "--output",
+        "parquet",
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"PAR1"));
+
+    let extract = util::write_temp_file("sample_extract.parquet", &output.stdout)?;
+    let cat = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["cat", extract.to_str().unwrap()])
+        .output()
+        .expect("run megrez cat");
+    assert!(cat.status.success());
+    let kept = ids(&cat);
+    assert!((20..80).contains(&kept.len()), "kept {}", kept.len());
+    let first: Value = serde_json::from_slice(cat.stdout.split(|b| *b == b'\n').next().unwrap())?;
+    assert_eq!(first.as_object().unwrap().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn sample_directory_parquet_output_keeps_arrow_types() -> Result<()> {
+    let dir = util::temp_dir("sample_parquet_dir")?;
+    for (part, rows) in [("part-0.parquet", 300), ("part-1.parquet", 200)] {
+        let path = util::parquet_row_groups(&format!("sample_dir_{part}"), rows, 50)?;
+        std::fs::rename(path, dir.join(part))?;
+    }
+    let dir = dir.to_str().unwrap();
+    let output = megrez(&[
+        "sample", dir, "-n", "10", "--seed", "5", "--output", "parquet",
+    ]);
+    assert!(output.status.success());
+
+    let extract = util::write_temp_file("sample_dir_extract.parquet", &output.stdout)?;
+    let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+        std::fs::File::open(extract)?,
+    )?;
+    let schema = reader.schema().clone();
+    assert!(!schema.field_with_name("id")?.is_nullable());
+    assert_eq!(
+        schema.field_with_name("label")?.data_type(),
+        &arrow::datatypes::DataType::Utf8
+    );
+    let mut ids = Vec::new();
+    for batch in reader.build()? {
+        let batch = batch?;
+        let column = batch.column(0).as_any();
+        let column = column.downcast_ref::<arrow::array::Int64Array>().unwrap();
+        ids.extend(column.values().iter().copied());
+    }
+    // The same rows as the JSON Lines sample for this seed.
+    let jsonl = megrez(&["sample", dir, "-n", "10", "--seed", "5"]);
+    assert_eq!(ids, self::ids(&jsonl));
+    Ok(())
+}
+
+#[test]
+fn sample_csv_writes_csv_with_header() -> Result<()> {
+    let mut csv = String::from("id,city\n");
+    for id in 0..50 {
+        csv.push_str(&format!("{id},city {id}\n"));
+    }
+    let path = util::write_temp_file("sample_input.csv", csv.as_bytes())?;
+    let output = megrez(&["sample", path.to_str().unwrap(), "-n", "5", "--seed", "1"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "id,city");
+    for line in &lines[1..] {
+        let (id, city) = line.split_once(',').unwrap();
+        assert_eq!(city, format!("city {id}"));
+    }
+    Ok(())
+}
+
+#[test]
+fn sample_rejects_fraction_out_of_range() {
+    let path = util::fixtures_dir().join("sample.csv");
+    let output = megrez(&["sample", path.to_str().unwrap(), "--fraction", "1.5"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not between 0 and 1"));
+}
@@ -26,7 +26,7 @@ fn schema_json() {
 
     assert!(output.status.success());
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let expected = "format: JSON\nname\ttype\tnullable\nid\tint\tfalse\ntags\tlist<string>\tfalse\nuser.active\tbool\ttrue\nuser.id\tstring\tfalse\n";
+    let expected = "format: JSON\nlayout: array\nname\ttype\tnullable\nid\tint\tfalse\ntags\tlist<string>\tfalse\nuser.active\tbool\ttrue\nuser.id\tstring\tfalse\n";
     assert_eq!(stdout, expected);
 }
 
@@ -62,3 +62,248 @@ fn schema_mislabeled_parquet_detects_magic() {
     let expected = "format: PARQUET\nname\ttype\tnullable\nid\tint\tfalse\nname\tstring\ttrue\nactive\tbool\tfalse\n";
     assert_eq!(stdout, expected);
 }
+
+#[test]
+fn schema_csv_sample_rows_and_full_scan() {
+    let mut contents = String::from("code\n");
+    for idx in 0..20 {
+        contents.push_str(&format!("{idx}\n"));
+    }
+    contents.push_str("N/A\n");
+    let path = util::write_temp_file("late_string.csv", contents.as_bytes()).expect("write csv");
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+            .args(["schema", path.to_str().unwrap(), "--show-format-name=false"])
+            .args(extra)
+            .output()
+            .expect("run megrez schema");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert_eq!(
+        run(&["--sample-rows", "10"]),
+        "name\ttype\tnullable\ncode\tint\tfalse\n"
+    );
+    assert_eq!(
+        run(&["--full-scan"]),
+        "name\ttype\tnullable\ncode\tstring\tfalse\n"
+    );
+    assert_eq!(
+        run(&["--sample-rows", "10", "--sampling", "stratified"]),
+        "name\ttype\tnullable\ncode\tstring\tfalse\n"
+    );
+}
+
+#[test]
+fn schema_json_full_scan_reads_every_array_element() {
+    let tags: Vec<String> = (0..1500).map(|idx| idx.to_string()).collect();
+    let contents = format!("{{\"tags\": [{}, \"x\"]}}\n", tags.join(","));
+    let path = util::write_temp_file("long_array.json", contents.as_bytes()).expect("write json");
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+            .args(["schema", path.to_str().unwrap(), "--show-format-name=false"])
+            .args(extra)
+            .output()
+            .expect("run megrez schema");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert_eq!(run(&[]), "name\ttype\tnullable\ntags\tlist<int>\tfalse\n");
+    assert_eq!(
+        run(&["--full-scan"]),
+        "name\ttype\tnullable\ntags\tlist<string>\tfalse\n"
+    );
+}
+
+#[test]
+fn schema_directory_merges_and_reports_conflicts() {
+    let dir = util::temp_dir("schema_dataset").expect("create dir");
+    std::fs::write(dir.join("part-0.csv"), "id,code\n1,10\n").unwrap();
+    std::fs::write(dir.join("part-1.csv"), "id,code,extra\n2,x,true\n").unwrap();
+    std::fs::write(dir.join("_SUCCESS"), "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", dir.to_str().unwrap()])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = "format: CSV\nname\ttype\tnullable\nid\tint\tfalse\ncode\tstring\tfalse\nextra\tbool\ttrue\n";
+    assert_eq!(stdout, expected);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("field `code` has conflicting types: int in"));
+    assert!(stderr.contains("part-1.csv"));
+}
+
+#[test]
+fn schema_directory_adds_partition_columns() {
+    let dir = util::temp_dir("schema_partitions").expect("create dir");
+    for (year, region) in [("2023", "eu"), ("2024", "us")] {
+        let part = dir
+            .join(format!("year={year}"))
+            .join(format!("region={region}"));
+        std::fs::create_dir_all(&part).unwrap();
+        std::fs::write(part.join("part-0.csv"), "id\n1\n").unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", dir.to_str().unwrap(), "--show-format-name=false"])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected =
+        "name\ttype\tnullable\nid\tint\tfalse\nyear\tint\tfalse\nregion\tstring\tfalse\n";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn schema_partition_column_replaces_file_column() {
+    let dir = util::temp_dir("schema_partition_precedence").expect("create dir");
+    let part = dir.join("year=2024");
+    std::fs::create_dir_all(&part).unwrap();
+    std::fs::write(part.join("part-0.csv"), "id,year\n1,unknown\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", dir.to_str().unwrap(), "--show-format-name=false"])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    // The records carry the path's value, so the schema reports its type.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "name\ttype\tnullable\nid\tint\tfalse\nyear\tint\tfalse\n"
+    );
+}
+
+#[test]
+fn schema_reports_json_layout_and_reads_records_path() {
+    let ndjson = util::write_temp_file("events.jsonl", b"{\"id\":1}\n{\"id\":2}\n").expect("write");
+    let envelope = util::write_temp_file(
+        "envelope.json",
+        br#"{"meta": {"page": 1}, "data": {"items": [{"id": 1, "name": "a"}, {"id": 2}]}}"#,
+    )
+    .expect("write");
+
+    let run = |path: &std::path::Path, extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+            .args(["schema", path.to_str().unwrap()])
+            .args(extra)
+            .output()
+            .expect("run megrez schema");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert_eq!(
+        run(&ndjson, &[]),
+        "format: JSON\nlayout: ndjson\nname\ttype\tnullable\nid\tint\tfalse\n"
+    );
+    assert!(run(&envelope, &[]).starts_with("format: JSON\nlayout: document\n"));
+    assert_eq!(
+        run(&envelope, &["--records-path", "$.data.items"]),
+        "format: JSON\nlayout: document\nname\ttype\tnullable\nid\tint\tfalse\nname\tstring\ttrue\n"
+    );
+}
+
+#[test]
+fn schema_cbor_records() {
+    // {"id": 1, "tags": ["x"]} then {"id": 2}, in a file without a telling extension.
+    let path = util::write_temp_file(
+        "events.dat",
+        b"\xa2\x62id\x01\x64tags\x81\x61x\xa1\x62id\x02",
+    )
+    .expect("write CBOR");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", path.to_str().unwrap()])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = "format: CBOR\nname\ttype\tnullable\nid\tint\tfalse\ntags\tlist<string>\ttrue\n";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn schema_delta_table_reports_version() {
+    let table = util::delta_table("schema_delta").expect("build Delta table");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", table.to_str().unwrap()])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = "format: DELTA\nversion: 1\npartitioning: region\nname\ttype\tnullable\nid\tint\tfalse\nname\tstring\ttrue\nactive\tbool\tfalse\nregion\tstring\ttrue\n";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn schema_iceberg_table_shows_field_ids_and_snapshots() {
+    let table = util::iceberg_table("schema_iceberg").expect("build Iceberg table");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", table.to_str().unwrap()])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = "format: ICEBERG\nsnapshot: 2\npartitioning: bucket[4](id)\n\
+        name\ttype\tnullable\tfield_id\nid\tint\tfalse\t1\nname\tstring\ttrue\t2\n\
+        active\tbool\tfalse\t3\nscore\tfloat\ttrue\t4\n\n\
+        snapshot_id\tparent_id\ttimestamp_ms\toperation\n1\t-\t1000\tappend\n2\t1\t2000\tdelete\n";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn schema_spreadsheet_detected_by_workbook_structure() {
+    let bytes = std::fs::read(util::fixtures_dir().join("sample.xlsx")).expect("read fixture");
+    let path = util::write_temp_file("export.bin", &bytes).expect("write workbook");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["schema", path.to_str().unwrap()])
+        .output()
+        .expect("run megrez schema");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = "format: SPREADSHEET\nname\ttype\tnullable\nid\tint\tfalse\nname\tstring\tfalse\njoined\ttimestamp\ttrue\nactive\tbool\tfalse\n";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn schema_utf16_and_bom_input_is_transcoded() {
+    // An Excel "Unicode text" export: UTF-16LE with a BOM. Without a BOM, UTF-8 JSON.
+    let utf16: Vec<u8> = "\u{feff}id,名前\n1,東京\n"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let utf16 = util::write_temp_file("export_utf16.txt", &utf16).expect("write utf-16");
+    let bom =
+        util::write_temp_file("bom.json", "\u{feff}[{\"id\":1}]".as_bytes()).expect("write json");
+    for (path, expected) in [
+        (
+            utf16,
+            "format: CSV\nname\ttype\tnullable\nid\tint\tfalse\n名前\tstring\tfalse\n",
+        ),
+        (
+            bom,
+            "format: JSON\nlayout: array\nname\ttype\tnullable\nid\tint\tfalse\n",
+        ),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+            .args(["schema", path.to_str().unwrap()])
+            .output()
+            .expect("run megrez schema");
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    }
+}
@@ -0,0 +1,15 @@
+mod util;
+
+use std::process::Command;
+
+#[test]
+fn view_requires_a_terminal() {
+    let path = util::fixtures_dir().join("sample.csv");
+    let output = Command::new(env!("CARGO_BIN_EXE_megrez"))
+        .args(["view", path.to_str().unwrap()])
+        .output()
+        .expect("run megrez view");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs an interactive terminal"));
+    assert!(output.stdout.is_empty());
+}
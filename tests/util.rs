@@ -1,17 +1,39 @@
+#![allow(dead_code)]
+
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use apache_avro::types::Value;
 use arrow::array::{BooleanArray, Int64Array, StringArray};
 use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::{FileWriter, StreamWriter};
 use arrow::record_batch::RecordBatch;
 use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
 
 pub fn fixtures_dir() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
 }
 
+pub fn temp_dir(name: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("megrez_tests_{}_{name}", std::process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).context("remove stale temp dir")?;
+    }
+    fs::create_dir_all(&dir).context("create temp dir")?;
+    Ok(dir)
+}
+
+pub fn write_temp_file(name: &str, contents: &[u8]) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("megrez_tests_{}", std::process::id()));
+    fs::create_dir_all(&dir).context("create temp dir")?;
+    let path = dir.join(name);
+    fs::write(&path, contents).context("write temp file")?;
+    Ok(path)
+}
+
 pub fn ensure_parquet_fixture() -> Result<PathBuf> {
     let path = fixtures_dir().join("sample.parquet");
     fs::create_dir_all(fixtures_dir()).context("create fixtures dir")?;
@@ -24,11 +46,11 @@ pub fn ensure_parquet_fixture() -> Result<PathBuf> {
 
 pub fn ensure_avro_fixture() -> Result<PathBuf> {
     let path = fixtures_dir().join("sample.avro");
-    fs::create_dir_all(fixtures_dir()).context("create fixtures dir")?;
-    if path.exists() {
-        fs::remove_file(&path).context("remove stale Avro fixture")?;
+    // The fixture is committed; rewriting it would only change its random sync marker.
+    if !path.exists() {
+        fs::create_dir_all(fixtures_dir()).context("create fixtures dir")?;
+        create_avro(&path)?;
     }
-    create_avro(&path)?;
     Ok(path)
 }
 
@@ -43,7 +65,197 @@ pub fn ensure_mislabeled_parquet_fixture() -> Result<PathBuf> {
     Ok(path)
 }
 
+/// Builds a Delta table partitioned by `region`, with the sample rows in each of two files.
+/// Version 0 adds both files; version 1 removes the `us` one.
+pub fn delta_table(name: &str) -> Result<PathBuf> {
+    let root = temp_dir(name)?;
+    for region in ["eu", "us"] {
+        fs::create_dir_all(root.join(format!("region={region}")))?;
+        create_parquet(&root.join(format!("region={region}/part-0.parquet")))?;
+    }
+    fs::create_dir_all(root.join("_delta_log"))?;
+    let schema = r#"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"long\",\"nullable\":false,\"metadata\":{}},{\"name\":\"name\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}},{\"name\":\"active\",\"type\":\"boolean\",\"nullable\":false,\"metadata\":{}},{\"name\":\"region\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}]}"#;
+    let add = |region: &str| {
+        format!(
+            r#"{{"add":{{"path":"region={region}/part-0.parquet","partitionValues":{{"region":"{region}"}},"size":1,"modificationTime":0,"dataChange":true}}}}"#
+        )
+    };
+    fs::write(
+        root.join("_delta_log/00000000000000000000.json"),
+        format!(
+            "{}\n{}\n{}\n{}\n",
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#,
+            format_args!(
+                r#"{{"metaData":{{"id":"t","format":{{"provider":"parquet","options":{{}}}},"schemaString":"{schema}","partitionColumns":["region"],"configuration":{{}}}}}}"#
+            ),
+            add("eu"),
+            add("us"),
+        ),
+    )?;
+    fs::write(
+        root.join("_delta_log/00000000000000000001.json"),
+        r#"{"remove":{"path":"region=us/part-0.parquet","deletionTimestamp":1,"dataChange":true}}
+"#,
+    )?;
+    Ok(root)
+}
+
+/// Builds an Iceberg table whose metadata points at `s3://warehouse/db/events`, so paths must
+/// be resolved against the local copy. Of its two data files (the sample rows, without a
+/// `score` column) the second is deleted by the current snapshot's manifest.
+pub fn iceberg_table(name: &str) -> Result<PathBuf> {
+    let root = temp_dir(name)?;
+    let location = "s3://warehouse/db/events";
+    fs::create_dir_all(root.join("data"))?;
+    fs::create_dir_all(root.join("metadata"))?;
+    create_parquet(&root.join("data/a.parquet"))?;
+    create_parquet(&root.join("data/b.parquet"))?;
+
+    let manifest_schema = apache_avro::Schema::parse_str(
+        r#"{"type": "record", "name": "manifest_entry", "fields": [
+            {"name": "status", "type": "int"},
+            {"name": "snapshot_id", "type": ["null", "long"]},
+            {"name": "data_file", "type": {"type": "record", "name": "r2", "fields": [
+                {"name": "content", "type": "int"},
+                {"name": "file_path", "type": "string"},
+                {"name": "file_format", "type": "string"},
+                {"name": "record_count", "type": "long"}
+            ]}}
+        ]}"#,
+    )?;
+    let mut writer = apache_avro::Writer::new(
+        &manifest_schema,
+        File::create(root.join("metadata/m0.avro"))?,
+    );
+    for (status, file) in [(1, "a"), (2, "b")] {
+        writer.append(Value::Record(vec![
+            ("status".into(), Value::Int(status)),
+            (
+                "snapshot_id".into(),
+                Value::Union(1, Box::new(Value::Long(2))),
+            ),
+            (
+                "data_file".into(),
+                Value::Record(vec![
+                    ("content".into(), Value::Int(0)),
+                    (
+                        "file_path".into(),
+                        Value::String(format!("{location}/data/{file}.parquet")),
+                    ),
+                    ("file_format".into(), Value::String("PARQUET".into())),
+                    ("record_count".into(), Value::Long(2)),
+                ]),
+            ),
+        ]))?;
+    }
+    writer.flush()?;
+
+    let list_schema = apache_avro::Schema::parse_str(
+        r#"{"type": "record", "name": "manifest_file", "fields": [
+            {"name": "manifest_path", "type": "string"},
+            {"name": "manifest_length", "type": "long"},
+            {"name": "partition_spec_id", "type": "int"},
+            {"name": "content", "type": "int"}
+        ]}"#,
+    )?;
+    let mut writer = apache_avro::Writer::new(
+        &list_schema,
+        File::create(root.join("metadata/snap-2.avro"))?,
+    );
+    writer.append(Value::Record(vec![
+        (
+            "manifest_path".into(),
+            Value::String(format!("{location}/metadata/m0.avro")),
+        ),
+        ("manifest_length".into(), Value::Long(0)),
+        ("partition_spec_id".into(), Value::Int(0)),
+        ("content".into(), Value::Int(0)),
+    ]))?;
+    writer.flush()?;
+
+    let metadata = serde_json::json!({
+        "format-version": 2,
+        "location": location,
+        "current-schema-id": 0,
+        "schemas": [{"schema-id": 0, "type": "struct", "fields": [
+            {"id": 1, "name": "id", "required": true, "type": "long"},
+            {"id": 2, "name": "name", "required": false, "type": "string"},
+            {"id": 3, "name": "active", "required": true, "type": "boolean"},
+            {"id": 4, "name": "score", "required": false, "type": "double"}
+        ]}],
+        "default-spec-id": 0,
+        "partition-specs": [{"spec-id": 0, "fields": [
+            {"source-id": 1, "field-id": 1000, "name": "id_bucket", "transform": "bucket[4]"}
+        ]}],
+        "current-snapshot-id": 2,
+        "snapshots": [
+            {"snapshot-id": 1, "timestamp-ms": 1000, "summary": {"operation": "append"},
+             "manifest-list": format!("{location}/metadata/snap-1.avro")},
+            {"snapshot-id": 2, "parent-snapshot-id": 1, "timestamp-ms": 2000,
+             "summary": {"operation": "delete"},
+             "manifest-list": format!("{location}/metadata/snap-2.avro")}
+        ]
+    });
+    fs::write(
+        root.join("metadata/v1.metadata.json"),
+        serde_json::to_string_pretty(&metadata)?,
+    )?;
+    Ok(root)
+}
+
+/// Writes `rows` rows (`id` 0..rows, `label` "row-{id}") as Parquet, `group_size` rows per row
+/// group.
+pub fn parquet_row_groups(name: &str, rows: i64, group_size: usize) -> Result<PathBuf> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("label", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from_iter_values(0..rows)),
+            Arc::new(StringArray::from_iter_values(
+                (0..rows).map(|id| format!("row-{id}")),
+            )),
+        ],
+    )?;
+    let path = write_temp_file(name, b"")?;
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(group_size)
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(&path)?, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(path)
+}
+
+/// Writes the sample rows as an Arrow IPC file (`stream == false`) or IPC stream.
+pub fn arrow_ipc_bytes(stream: bool) -> Result<Vec<u8>> {
+    let batch = sample_batch()?;
+    let mut bytes = Vec::new();
+    if stream {
+        let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema())?;
+        writer.write(&batch)?;
+        writer.finish()?;
+    } else {
+        let mut writer = FileWriter::try_new(&mut bytes, &batch.schema())?;
+        writer.write(&batch)?;
+        writer.finish()?;
+    }
+    Ok(bytes)
+}
+
 fn create_parquet(path: &Path) -> Result<()> {
+    let batch = sample_batch()?;
+    let file = File::create(path).context("create Parquet file")?;
+    let mut writer =
+        ArrowWriter::try_new(file, batch.schema(), None).context("create Parquet writer")?;
+    writer.write(&batch).context("write Parquet batch")?;
+    writer.close().context("close Parquet writer")?;
+    Ok(())
+}
+
+fn sample_batch() -> Result<RecordBatch> {
     let schema = Arc::new(Schema::new(vec![
         Field::new("id", DataType::Int64, false),
         Field::new("name", DataType::Utf8, true),
@@ -51,7 +263,7 @@ fn create_parquet(path: &Path) -> Result<()> {
     ]));
 
     let batch = RecordBatch::try_new(
-        schema.clone(),
+        schema,
         vec![
             Arc::new(Int64Array::from(vec![1, 2])),
             Arc::new(StringArray::from(vec![Some("alice"), Some("bob")])),
@@ -59,12 +271,7 @@ fn create_parquet(path: &Path) -> Result<()> {
         ],
     )
     .context("build record batch")?;
-
-    let file = File::create(path).context("create Parquet file")?;
-    let mut writer = ArrowWriter::try_new(file, schema, None).context("create Parquet writer")?;
-    writer.write(&batch).context("write Parquet batch")?;
-    writer.close().context("close Parquet writer")?;
-    Ok(())
+    Ok(batch)
 }
 
 fn create_avro(path: &Path) -> Result<()> {